@@ -0,0 +1,86 @@
+use crate::error::RuntimeError;
+use crate::interpreter::core::Interpreter;
+use crate::limits::Limits;
+use crate::parser::parser_ds::ExprAst;
+use crate::vm::core::Vm;
+use std::cell::RefCell;
+use std::path::Path;
+use std::rc::Rc;
+use std::str::FromStr;
+
+/// Executes resolved statements one at a time, keeping its globals between
+/// calls. Both the tree-walking interpreter and the bytecode VM implement it,
+/// and must print exactly the same things.
+pub trait Backend {
+    /// Runs `stmt` and returns its value as the `evaluate` command prints it.
+    fn execute(&mut self, stmt: &ExprAst) -> Result<String, RuntimeError>;
+
+    /// The global variables, sorted by name, with their values as `print`
+    /// shows them.
+    fn globals(&self) -> Vec<(String, String)>;
+
+    /// Records the file the program was read from, imports resolve relative
+    /// to it.
+    fn set_script(&mut self, path: &Path);
+
+    /// Bounds what the statements executed from now on may use together,
+    /// starting the clock again.
+    fn set_limits(&mut self, limits: Limits);
+}
+
+pub fn new_backend(kind: BackendKind) -> Rc<RefCell<dyn Backend>> {
+    match kind {
+        BackendKind::Tree => Rc::new(RefCell::new(Interpreter::new())),
+        BackendKind::Vm => Rc::new(RefCell::new(Vm::new())),
+    }
+}
+
+/// How many arguments a function takes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Arity {
+    Fixed(usize),
+    /// Any number of arguments.
+    Variadic,
+}
+
+impl Arity {
+    /// The runtime error a call with `argc` arguments raises, if any.
+    pub fn check(self, argc: usize) -> Result<(), String> {
+        match self {
+            Arity::Fixed(arity) if arity != argc => {
+                Err(format!("Expected {} arguments but got {}.", arity, argc))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Position `index` designates in a list of `len` elements, or the runtime
+/// error to raise.
+pub fn list_index(index: f64, len: usize) -> Result<usize, String> {
+    if index.fract() != 0.0 {
+        return Err("List index must be an integer.".to_string());
+    }
+    if index < 0.0 || index >= len as f64 {
+        return Err("List index out of range.".to_string());
+    }
+    Ok(index as usize)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BackendKind {
+    Tree,
+    Vm,
+}
+
+impl FromStr for BackendKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "tree" => Ok(Self::Tree),
+            "vm" => Ok(Self::Vm),
+            _ => Err(format!("Unknown backend: {}", s)),
+        }
+    }
+}
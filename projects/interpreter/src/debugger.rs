@@ -0,0 +1,153 @@
+use codecrafters_interpreter::{DebugHooks, Paused, Resume, Stop};
+use std::collections::BTreeSet;
+use std::io::{self, BufRead, Write};
+use std::process::exit;
+
+const HELP: &str = "\
+break <line>     stop before the statements on a line (b)
+delete <line>    remove the breakpoint on a line
+breakpoints      list the breakpoints
+continue         run until the next breakpoint (c)
+step             go to the next line, into calls (s)
+next             go to the next line, over calls (n)
+finish           run until the current function returns (f)
+backtrace        show the calls in progress (bt)
+locals           show the variables of the current call
+captured         show the variables the current function closes over
+globals [all]    show the global variables, the natives too with all
+print <name>     show the value of a variable (p)
+list             show the source around the current line (l)
+help             show this message
+quit             stop the program (q)";
+
+/// Lines of source shown by `list` on each side of the current one.
+const CONTEXT: usize = 3;
+
+/// Command-line front end: reads commands from stdin whenever the program
+/// stops, until it is resumed.
+pub struct Debugger {
+    source: Vec<String>,
+    breakpoints: BTreeSet<usize>,
+    /// Set once stdin is closed: the program then runs to the end.
+    detached: bool,
+}
+
+impl Debugger {
+    pub fn new(source: &str) -> Self {
+        Self {
+            source: source.lines().map(str::to_string).collect(),
+            breakpoints: BTreeSet::new(),
+            detached: false,
+        }
+    }
+
+    fn show_line(&self, line: usize) {
+        if let Some(text) = line.checked_sub(1).and_then(|i| self.source.get(i)) {
+            println!("{:>4} | {}", line, text);
+        }
+    }
+
+    fn list(&self, line: usize) {
+        let first = line.saturating_sub(CONTEXT).max(1);
+        for n in first..=(line + CONTEXT).min(self.source.len()) {
+            let marker = if n == line { ">" } else { " " };
+            println!("{}{:>3} | {}", marker, n, self.source[n - 1]);
+        }
+    }
+
+    fn break_line(&mut self, arg: &str, set: bool) {
+        let Ok(line) = arg.parse::<usize>() else {
+            println!("Expected a line number, got '{}'.", arg);
+            return;
+        };
+        if !set {
+            if !self.breakpoints.remove(&line) {
+                println!("No breakpoint on line {}.", line);
+            }
+        } else if line == 0 || line > self.source.len() {
+            println!("Line {} is not in the script.", line);
+        } else {
+            self.breakpoints.insert(line);
+            println!("Breakpoint on line {}.", line);
+        }
+    }
+
+    /// Runs the commands typed at a stop, until one resumes the program.
+    fn prompt(&mut self, state: &Paused) -> Resume {
+        let stdin = io::stdin();
+        let mut lines = stdin.lock().lines();
+        loop {
+            print!("(debug) ");
+            let _ = io::stdout().flush();
+            let Some(Ok(line)) = lines.next() else {
+                println!();
+                self.detached = true;
+                return Resume::Continue;
+            };
+            let (cmd, arg) = line.trim().split_once(' ').unwrap_or((line.trim(), ""));
+            let arg = arg.trim();
+            match cmd {
+                "" => (),
+                "break" | "b" => self.break_line(arg, true),
+                "delete" => self.break_line(arg, false),
+                "breakpoints" => {
+                    for line in &self.breakpoints {
+                        self.show_line(*line);
+                    }
+                }
+                "continue" | "c" => return Resume::Continue,
+                "step" | "s" => return Resume::StepInto,
+                "next" | "n" => return Resume::StepOver,
+                "finish" | "f" => return Resume::StepOut,
+                "backtrace" | "bt" => {
+                    for (i, frame) in state.stack().enumerate() {
+                        println!("#{} {} at line {}", i, frame.function, frame.line);
+                    }
+                }
+                "locals" => print_vars(&state.locals()),
+                "captured" => print_vars(&state.captured()),
+                "globals" if arg == "all" => print_vars(&state.all_globals()),
+                "globals" => print_vars(&state.globals()),
+                "print" | "p" => {
+                    let vars = [state.locals(), state.captured(), state.all_globals()];
+                    match vars.iter().flatten().find(|(name, _)| name == arg) {
+                        Some((_, val)) => println!("{}", val),
+                        None => println!("Undefined variable '{}'.", arg),
+                    }
+                }
+                "list" | "l" => self.list(state.line),
+                "help" => println!("{}", HELP),
+                "quit" | "q" => {
+                    let _ = io::stdout().flush();
+                    exit(0);
+                }
+                _ => println!("Unknown command: {}, try help.", cmd),
+            }
+        }
+    }
+}
+
+fn print_vars(vars: &[(String, String)]) {
+    for (name, val) in vars {
+        println!("{} = {}", name, val);
+    }
+}
+
+impl DebugHooks for Debugger {
+    fn is_breakpoint(&mut self, line: usize) -> bool {
+        !self.detached && self.breakpoints.contains(&line)
+    }
+
+    fn paused(&mut self, stop: Stop, state: &Paused) -> Resume {
+        if self.detached {
+            return Resume::Continue;
+        }
+        let function = state.stack().next().map_or("script", |f| &f.function);
+        match stop {
+            Stop::Breakpoint => println!("Breakpoint, {} at line {}", function, state.line),
+            Stop::Step => println!("{} at line {}", function, state.line),
+        }
+        self.show_line(state.line);
+        self.prompt(state)
+    }
+}
@@ -1,8 +1,16 @@
+use crate::lexer::Span;
+use crate::limits::Limit;
+use std::fmt;
+
 #[derive(Debug)]
 pub enum Error {
     Lexer,
     Parser,
+    Resolver,
     Runtime,
+    /// The program ran into one of its limits. Exits like other runtime
+    /// errors.
+    Limit,
 }
 
 impl Error {
@@ -10,7 +18,213 @@ impl Error {
         match &self {
             Self::Lexer => 65,
             Self::Parser => 65,
+            Self::Resolver => 65,
             Self::Runtime => 70,
+            Self::Limit => 70,
+        }
+    }
+
+    /// The error a program stopped by `err` ends with.
+    pub fn runtime(err: &RuntimeError) -> Self {
+        match err.limit {
+            Some(_) => Self::Limit,
+            None => Self::Runtime,
+        }
+    }
+}
+
+/// One call the error unwound through, with the line it was executing.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TraceFrame {
+    pub function: String,
+    pub line: usize,
+}
+
+/// An error raised while executing a program: where it happened, and the
+/// calls it went through, innermost first, down to the top-level script.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RuntimeError {
+    pub message: String,
+    pub line: usize,
+    pub column: usize,
+    pub trace: Vec<TraceFrame>,
+    /// The limit that stopped the program, such errors can't be caught.
+    pub limit: Option<Limit>,
+    frame_line: usize,
+}
+
+impl RuntimeError {
+    pub fn new(message: impl Into<String>, span: Span) -> Self {
+        Self {
+            message: message.into(),
+            line: span.line,
+            column: span.column,
+            trace: Vec::new(),
+            limit: None,
+            frame_line: span.line,
+        }
+    }
+
+    /// The error raised when the program runs into `limit`.
+    pub fn exceeded(limit: Limit, span: Span) -> Self {
+        Self {
+            limit: Some(limit),
+            ..Self::new(limit.message(), span)
+        }
+    }
+
+    /// Records that the error left `function`, which was called at `call`.
+    pub fn unwind(mut self, function: &str, call: Span) -> Self {
+        self.trace.push(TraceFrame {
+            function: function.to_string(),
+            line: self.frame_line,
+        });
+        self.frame_line = call.line;
+        self
+    }
+
+    /// Closes the trace with the top-level frame.
+    pub fn in_script(self) -> Self {
+        let line = self.frame_line;
+        self.unwind(
+            "script",
+            Span {
+                line,
+                ..Span::default()
+            },
+        )
+    }
+}
+
+/// Frames shown at each end of a trace too long to print whole, as that of
+/// a stack overflow.
+const TRACE_ENDS: usize = 10;
+
+impl fmt::Display for RuntimeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[line {}:{}] {}", self.line, self.column, self.message)?;
+        let len = self.trace.len();
+        for (i, frame) in self.trace.iter().enumerate() {
+            if len > 2 * TRACE_ENDS + 1 && i >= TRACE_ENDS && i < len - TRACE_ENDS {
+                if i == TRACE_ENDS {
+                    write!(f, "\n... {} more calls", len - 2 * TRACE_ENDS)?;
+                }
+                continue;
+            }
+            write!(f, "\n[line {}] in {}", frame.line, frame.function)?;
+        }
+        Ok(())
+    }
+}
+
+/// A runtime error caught by a `catch` clause, as Lox code sees it: its
+/// `message` and `line` properties read the error, and printing it shows the
+/// message. Compares by identity like other objects.
+#[derive(Debug)]
+pub struct CaughtError {
+    pub message: String,
+    pub line: usize,
+}
+
+impl CaughtError {
+    pub fn new(err: &RuntimeError) -> Self {
+        Self {
+            message: err.message.clone(),
+            line: err.line,
+        }
+    }
+}
+
+impl PartialEq for CaughtError {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
+
+impl fmt::Display for CaughtError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+/// Why running a program through the embedding API failed.
+#[derive(Debug, Clone, PartialEq)]
+pub enum LoxError {
+    Lex(Vec<LexError>),
+    /// Syntax errors, and the static errors the resolver reports.
+    Syntax(Vec<Diagnostic>),
+    Runtime(RuntimeError),
+    /// The program ran into one of the limits set with
+    /// [`crate::Lox::set_limits`].
+    Limit(RuntimeError),
+}
+
+impl fmt::Display for LoxError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Lex(errors) => write_lines(f, errors),
+            Self::Syntax(diagnostics) => write_lines(f, diagnostics),
+            Self::Runtime(err) | Self::Limit(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl std::error::Error for LoxError {}
+
+impl From<RuntimeError> for LoxError {
+    fn from(err: RuntimeError) -> Self {
+        match err.limit {
+            Some(_) => Self::Limit(err),
+            None => Self::Runtime(err),
+        }
+    }
+}
+
+fn write_lines(f: &mut fmt::Formatter<'_>, errors: &[impl fmt::Display]) -> fmt::Result {
+    for (i, err) in errors.iter().enumerate() {
+        if i > 0 {
+            writeln!(f)?;
+        }
+        write!(f, "{}", err)?;
+    }
+    Ok(())
+}
+
+/// A lexical error, reported as "[line N] Error: message".
+#[derive(Debug, Clone, PartialEq)]
+pub struct LexError {
+    pub message: String,
+    pub span: Span,
+}
+
+impl fmt::Display for LexError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[line {}] Error: {}", self.span.line, self.message)
+    }
+}
+
+/// A syntax error, reported as "[line N] Error at 'x': message".
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub message: String,
+    pub span: Span,
+    /// The offending lexeme, `None` when the error is at the end of input.
+    pub lexeme: Option<String>,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.lexeme {
+            Some(lexeme) => write!(
+                f,
+                "[line {}] Error at '{}': {}",
+                self.span.line, lexeme, self.message
+            ),
+            None => write!(
+                f,
+                "[line {}] Error at end: {}",
+                self.span.line, self.message
+            ),
         }
     }
 }
@@ -0,0 +1,195 @@
+use codecrafters_interpreter::lexer::read_token;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// What a test script expects, read from its comments with the conventions
+/// of the Crafting Interpreters suite:
+///
+/// - `// expect: text` is a line printed on stdout,
+/// - `// expect runtime error: message` is the runtime error stopping the
+///   program, raised on the line of the comment,
+/// - `// Error at 'x': message` and `// [line N] Error at 'x': message` are
+///   compile errors, reported on the line of the comment or on line N.
+#[derive(Debug, Default)]
+struct Expectations {
+    output: Vec<String>,
+    compile_errors: Vec<String>,
+    runtime_error: Option<(String, usize)>,
+}
+
+impl Expectations {
+    /// Reads the comments as the lexer finds them, so that `//` inside a
+    /// string is not taken for one.
+    fn parse(source: &str) -> Self {
+        let mut expect = Self::default();
+        for comment in read_token(source).comments {
+            let line = comment.span.line;
+            let Some(comment) = comment.text.strip_prefix("// ") else {
+                continue;
+            };
+            if let Some(text) = comment.strip_prefix("expect: ") {
+                expect.output.push(text.to_string());
+            } else if let Some(message) = comment.strip_prefix("expect runtime error: ") {
+                expect.runtime_error = Some((message.to_string(), line));
+            } else if comment.starts_with("[line ") {
+                expect.compile_errors.push(comment.to_string());
+            } else if comment.starts_with("Error") {
+                expect
+                    .compile_errors
+                    .push(format!("[line {}] {}", line, comment));
+            }
+        }
+        expect
+    }
+
+    fn exit_code(&self) -> i32 {
+        if !self.compile_errors.is_empty() {
+            65
+        } else if self.runtime_error.is_some() {
+            70
+        } else {
+            0
+        }
+    }
+}
+
+/// The message and line of a runtime error as the interpreter reports it,
+/// "[line N:C] message".
+fn runtime_error(line: &str) -> Option<(&str, usize)> {
+    let (position, message) = line.strip_prefix("[line ")?.split_once("] ")?;
+    let line = position.split(':').next()?.parse().ok()?;
+    Some((message, line))
+}
+
+/// `expected` and `actual` line by line, with "-" before the lines missing
+/// and "+" before the unexpected ones.
+fn diff(expected: &[String], actual: &[String]) -> Vec<String> {
+    // Longest common subsequence of the suffixes.
+    let (n, m) = (expected.len(), actual.len());
+    let mut lcs = vec![vec![0; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lcs[i][j] = if expected[i] == actual[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+    let (mut i, mut j) = (0, 0);
+    let mut lines = Vec::new();
+    while i < n || j < m {
+        if i < n && j < m && expected[i] == actual[j] {
+            lines.push(format!("  {}", expected[i]));
+            i += 1;
+            j += 1;
+        } else if j == m || (i < n && lcs[i + 1][j] >= lcs[i][j + 1]) {
+            lines.push(format!("- {}", expected[i]));
+            i += 1;
+        } else {
+            lines.push(format!("+ {}", actual[j]));
+            j += 1;
+        }
+    }
+    lines
+}
+
+fn lines(bytes: &[u8]) -> Vec<String> {
+    String::from_utf8_lossy(bytes)
+        .lines()
+        .map(str::to_string)
+        .collect()
+}
+
+/// Runs the script at `path` with the interpreter itself, returns what went
+/// wrong.
+fn check(path: &Path, flags: &[String]) -> Result<(), Vec<String>> {
+    let source = fs::read_to_string(path).map_err(|err| vec![err.to_string()])?;
+    let expect = Expectations::parse(&source);
+    let out = env::current_exe()
+        .and_then(|exe| Command::new(exe).arg("run").arg(path).args(flags).output())
+        .map_err(|err| vec![format!("Can't run the interpreter: {}", err)])?;
+
+    let mut failures = Vec::new();
+    let stdout = lines(&out.stdout);
+    if stdout != expect.output {
+        failures.push("stdout:".to_string());
+        failures.extend(diff(&expect.output, &stdout));
+    }
+    let stderr = lines(&out.stderr);
+    match expect.runtime_error {
+        Some((ref message, line)) => match stderr.first().and_then(|l| runtime_error(l)) {
+            Some(found) if found == (message.as_str(), line) => (),
+            _ => {
+                failures.push(format!(
+                    "expected runtime error on line {}: {}",
+                    line, message
+                ));
+                failures.push("stderr:".to_string());
+                failures.extend(stderr.iter().map(|l| format!("+ {}", l)));
+            }
+        },
+        None if stderr != expect.compile_errors => {
+            failures.push("stderr:".to_string());
+            failures.extend(diff(&expect.compile_errors, &stderr));
+        }
+        None => (),
+    }
+    match out.status.code() {
+        Some(code) if code == expect.exit_code() => (),
+        code => failures.push(format!(
+            "exit code: expected {}, got {}",
+            expect.exit_code(),
+            code.map_or("none".to_string(), |c| c.to_string())
+        )),
+    }
+    if failures.is_empty() {
+        Ok(())
+    } else {
+        Err(failures)
+    }
+}
+
+/// The `.lox` files under `path`, sorted.
+fn scripts(path: &Path, found: &mut Vec<PathBuf>) {
+    if !path.is_dir() {
+        found.push(path.to_path_buf());
+        return;
+    }
+    let Ok(entries) = fs::read_dir(path) else {
+        return;
+    };
+    let mut entries: Vec<PathBuf> = entries.filter_map(|e| Some(e.ok()?.path())).collect();
+    entries.sort();
+    for entry in entries {
+        if entry.is_dir() || entry.extension().is_some_and(|ext| ext == "lox") {
+            scripts(&entry, found);
+        }
+    }
+}
+
+/// Runs every test script under `paths`, passing `flags` to the interpreter.
+/// Returns whether they all passed.
+pub fn run(paths: &[String], flags: &[String]) -> bool {
+    let mut found = Vec::new();
+    for path in paths {
+        scripts(Path::new(path), &mut found);
+    }
+    let mut failed = 0;
+    for path in &found {
+        match check(path, flags) {
+            Ok(()) => println!("PASS {}", path.display()),
+            Err(failures) => {
+                failed += 1;
+                println!("FAIL {}", path.display());
+                for failure in failures {
+                    println!("    {}", failure);
+                }
+            }
+        }
+    }
+    println!("{} passed, {} failed", found.len() - failed, failed);
+    failed == 0
+}
@@ -0,0 +1,119 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+use std::rc::Rc;
+
+use super::core::{SharedGlobals, SharedVarMemory, VarMemory, VarValue};
+use super::func::builtin::{Function, FunctionObject, SharedFunctionObject};
+use crate::lexer::Span;
+
+pub struct Class {
+    pub name: String,
+    pub methods: HashMap<String, Rc<Function>>,
+    pub superclass: Option<SharedClass>,
+    pub closure: Option<SharedVarMemory>,
+    /// Globals of the module declaring the class, seen by its methods.
+    pub globals: SharedGlobals,
+    /// Where the class is declared.
+    pub span: Span,
+}
+
+pub type SharedClass = Rc<Class>;
+
+impl Class {
+    pub fn new(
+        name: String,
+        methods: HashMap<String, Rc<Function>>,
+        superclass: Option<SharedClass>,
+        closure: Option<SharedVarMemory>,
+        globals: SharedGlobals,
+        span: Span,
+    ) -> Self {
+        Self {
+            name,
+            methods,
+            superclass,
+            closure,
+            globals,
+            span,
+        }
+    }
+}
+
+/// Walks the superclass chain and returns the method along with the class
+/// declaring it, so that `super` inside the method refers to its parent.
+pub fn find_method(class: &SharedClass, name: &str) -> Option<(Rc<Function>, SharedClass)> {
+    let mut curr = Some(class.clone());
+    while let Some(c) = curr {
+        if let Some(method) = c.methods.get(name) {
+            return Some((method.clone(), c));
+        }
+        curr = c.superclass.clone();
+    }
+    None
+}
+
+impl PartialEq for Class {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
+
+impl fmt::Debug for Class {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Class {{ name : {} }}", self.name)
+    }
+}
+
+pub struct Instance {
+    pub class: SharedClass,
+    pub fields: HashMap<String, VarValue>,
+}
+
+pub type SharedInstance = Rc<RefCell<Instance>>;
+
+impl Instance {
+    pub fn new(class: SharedClass) -> Self {
+        Self {
+            class,
+            fields: HashMap::new(),
+        }
+    }
+}
+
+impl PartialEq for Instance {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
+
+impl fmt::Debug for Instance {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Instance {{ class : {} }}", self.class.name)
+    }
+}
+
+/// Looks `name` up on the instance: fields shadow methods, and methods are
+/// returned bound to the instance through `this`.
+pub fn get_property(instance: &SharedInstance, name: &str) -> Option<VarValue> {
+    if let Some(v) = instance.borrow().fields.get(name) {
+        return Some(v.clone());
+    }
+    let class = instance.borrow().class.clone();
+    find_method(&class, name).map(|(method, owner)| VarValue::Func(bind(&owner, method, instance)))
+}
+
+pub fn bind(
+    class: &Class,
+    method: Rc<Function>,
+    instance: &SharedInstance,
+) -> SharedFunctionObject {
+    let this = VarValue::Instance(instance.clone());
+    let closure = VarMemory::with_var("this", this, class.closure.clone());
+    let globals = Some(class.globals.clone());
+    Rc::new(RefCell::new(FunctionObject::new(
+        method,
+        Some(closure),
+        globals,
+    )))
+}
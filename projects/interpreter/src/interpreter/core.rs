@@ -1,18 +1,23 @@
+use super::class::{Class, Instance, SharedClass, SharedInstance};
+use super::debug::{DebugHooks, Debugger};
 use super::evaluate::IntermRepr;
 use super::func::builtin::*;
-use crate::error::Error;
-use crate::parser::core::{opti_run, parse_token};
-use crate::parser::parser_ds::{ExprAst, ParserOptions};
+use super::list::SharedList;
+use super::map::SharedMap;
+use super::module::SharedModule;
+use crate::error::{CaughtError, RuntimeError};
+use crate::lexer::Span;
+use crate::limits::{Budget, Limits};
+use crate::module::Loader;
+use crate::parser::parser_ds::{ExprAst, Slot};
 use std::cell::RefCell;
 use std::collections::HashMap;
-use std::process::exit;
 use std::rc::Rc;
-use std::sync::Arc;
 
 #[derive(Clone, Debug, PartialEq)]
 pub enum KindOfVal {
     Str(String),
-    Num(String),
+    Num(f64),
     Bool(bool),
     Nil,
 }
@@ -22,74 +27,53 @@ pub enum KindOfVal {
 pub enum VarValue {
     Classic(KindOfVal),
     Func(SharedFunctionObject),
+    Class(SharedClass),
+    Instance(SharedInstance),
+    List(SharedList),
+    Map(SharedMap),
+    Module(SharedModule),
+    Error(Rc<CaughtError>),
 }
 
 impl VarValue {
-    pub fn get_str_value(&self) -> Option<String> {
-        match self {
-            VarValue::Classic(v) => {
-                return match v {
-                    KindOfVal::Str(s) => Some(s.clone()),
-                    KindOfVal::Num(s) => Some(s.clone()),
-                    KindOfVal::Bool(s) => Some(s.to_string()),
-                    KindOfVal::Nil => None,
-                };
-            }
-            VarValue::Func(_) => return None,
-        }
-    }
-    pub fn get_kind(&self) -> Option<&KindOfVal> {
-        // Todo a renommer en get_classic_type
-        match self {
-            VarValue::Classic(v) => Some(v),
-            VarValue::Func(_) => None,
-        }
-    }
     pub fn to_interm(&self) -> IntermRepr {
         match self {
             VarValue::Classic(v) => kind_of_val_to_interm(v),
             VarValue::Func(f) => IntermRepr::Func(f.clone()),
-        }
-    }
-
-    pub fn to_bool(&self) -> bool {
-        match self.get_kind().unwrap() {
-            KindOfVal::Bool(value) => value.clone(),
-            KindOfVal::Num(value) => {
-                let n: f64 = value.parse().unwrap();
-                n != 0.0
-            }
-            KindOfVal::Nil => false,
-            KindOfVal::Str(_) => !self.get_str_value().unwrap().is_empty(),
-        }
-    }
-}
-
-pub fn interm_to_kind_of_val(r: &IntermRepr) -> KindOfVal {
-    match r {
-        IntermRepr::Bool(s) => KindOfVal::Bool(s.clone()),
-        IntermRepr::Num(s) => KindOfVal::Num(s.clone()),
-        IntermRepr::Nil => KindOfVal::Nil,
-        IntermRepr::Str(s) => KindOfVal::Str(s.clone()),
-        _ => {
-            eprintln!("Unknown type {:?}", r);
-            exit(70);
+            VarValue::Class(c) => IntermRepr::Class(c.clone()),
+            VarValue::Instance(i) => IntermRepr::Instance(i.clone()),
+            VarValue::List(l) => IntermRepr::List(l.clone()),
+            VarValue::Map(m) => IntermRepr::Map(m.clone()),
+            VarValue::Module(m) => IntermRepr::Module(m.clone()),
+            VarValue::Error(e) => IntermRepr::Error(e.clone()),
         }
     }
 }
 
 pub fn interm_to_var_val(r: &IntermRepr) -> VarValue {
     match r {
+        IntermRepr::Bool(s) => VarValue::Classic(KindOfVal::Bool(*s)),
+        IntermRepr::Num(n) => VarValue::Classic(KindOfVal::Num(*n)),
+        IntermRepr::Nil | IntermRepr::Break | IntermRepr::Continue => {
+            VarValue::Classic(KindOfVal::Nil)
+        }
+        IntermRepr::Str(s) | IntermRepr::Op(s) => VarValue::Classic(KindOfVal::Str(s.clone())),
         IntermRepr::Func(f) => VarValue::Func(f.clone()),
-        _ => VarValue::Classic(interm_to_kind_of_val(r)),
+        IntermRepr::Class(c) => VarValue::Class(c.clone()),
+        IntermRepr::Instance(i) => VarValue::Instance(i.clone()),
+        IntermRepr::List(l) => VarValue::List(l.clone()),
+        IntermRepr::Map(m) => VarValue::Map(m.clone()),
+        IntermRepr::Module(m) => VarValue::Module(m.clone()),
+        IntermRepr::Error(e) => VarValue::Error(e.clone()),
+        IntermRepr::Ret(r) => interm_to_var_val(r),
     }
 }
 
 pub fn kind_of_val_to_interm(k: &KindOfVal) -> IntermRepr {
     match k {
         KindOfVal::Str(s) => IntermRepr::Str(s.clone()),
-        KindOfVal::Num(s) => IntermRepr::Num(s.clone()),
-        KindOfVal::Bool(s) => IntermRepr::Bool(s.clone()),
+        KindOfVal::Num(n) => IntermRepr::Num(*n),
+        KindOfVal::Bool(s) => IntermRepr::Bool(*s),
         KindOfVal::Nil => IntermRepr::Nil,
     }
 }
@@ -114,28 +98,45 @@ pub fn kind_of_val_to_interm(k: &KindOfVal) -> IntermRepr {
 //     }
 // }
 
-#[derive(Debug, Clone)]
-#[allow(dead_code)]
-pub enum RegisterX {
-    Empty,
-    Var(VarValue),
-    Fn(Rc<RefCell<FunctionObject>>),
-}
-
+/// One lexical scope. Scopes are shared between the frame executing them and
+/// every closure declared inside, so writes are seen by all of them.
 #[derive(Debug)]
 pub struct VarMemory {
-    variables: HashMap<String, VarValue>,
+    variables: Vec<(String, VarValue)>,
+    enclosing: Option<SharedVarMemory>,
+}
+
+pub type SharedVarMemory = Rc<RefCell<VarMemory>>;
+
+/// The global variables of one module. Functions and classes keep the one
+/// they were declared in, and see it from wherever they are called.
+pub type SharedGlobals = Rc<RefCell<HashMap<String, VarValue>>>;
+
+/// A global namespace holding only the native functions.
+pub fn builtin_globals() -> SharedGlobals {
+    let globals = get_fn_builtin()
+        .into_iter()
+        .map(|f| {
+            let fo = FunctionObject::new(f.clone(), None, None);
+            (f.name.clone(), VarValue::Func(Rc::new(RefCell::new(fo))))
+        })
+        .collect();
+    Rc::new(RefCell::new(globals))
 }
 
+/// The scopes opened by one call, innermost last. The first one encloses the
+/// environment the callee was declared in.
 #[derive(Debug)]
 pub struct InnerMemory {
-    base: Vec<VarMemory>,
+    base: Vec<SharedVarMemory>,
+    globals: SharedGlobals,
 }
 
 impl InnerMemory {
-    pub fn new() -> Self {
+    pub fn new(closure: Option<SharedVarMemory>, globals: SharedGlobals) -> Self {
         Self {
-            base: vec![VarMemory::new()],
+            base: vec![VarMemory::new(closure)],
+            globals,
         }
     }
 }
@@ -143,269 +144,339 @@ impl InnerMemory {
 #[derive(Debug)]
 pub struct BaseMemory {
     iner_memory: Vec<InnerMemory>,
-    functions: Vec<Arc<Function>>,
 }
 
 impl BaseMemory {
-    pub fn get_fn(&self, name: &str) -> Option<&Arc<Function>> {
-        for func in self.functions.iter() {
-            if func.name == name {
-                return Some(func);
-            }
+    pub fn new() -> Self {
+        Self {
+            iner_memory: vec![InnerMemory {
+                base: vec![],
+                globals: builtin_globals(),
+            }],
         }
-        None
     }
-}
 
-impl BaseMemory {
-    pub fn new() -> Self {
-        Self {
-            iner_memory: vec![InnerMemory::new()],
-            functions: get_fn_builtin(),
+    fn frame(&self) -> &InnerMemory {
+        self.iner_memory.last().unwrap()
+    }
+
+    fn frame_mut(&mut self) -> &mut InnerMemory {
+        self.iner_memory.last_mut().unwrap()
+    }
+
+    fn scope(&self) -> Option<SharedVarMemory> {
+        self.frame().base.last().cloned()
+    }
+
+    fn ancestor(&self, depth: usize) -> Option<SharedVarMemory> {
+        let mut scope = self.scope()?;
+        for _ in 0..depth {
+            let enclosing = scope.borrow().enclosing.clone()?;
+            scope = enclosing;
         }
+        Some(scope)
     }
 }
 
 impl VarMemory {
-    pub fn new() -> Self {
-        Self {
-            variables: HashMap::new(),
-        }
+    pub fn new(enclosing: Option<SharedVarMemory>) -> SharedVarMemory {
+        Rc::new(RefCell::new(Self {
+            variables: Vec::new(),
+            enclosing,
+        }))
     }
-}
-#[derive(Debug)]
-struct Context {
-    pub curr_func: String,
-}
 
-impl Context {
-    fn new() -> Self {
-        Self {
-            curr_func: Context::default_func(),
+    pub fn with_var(
+        name: &str,
+        val: VarValue,
+        enclosing: Option<SharedVarMemory>,
+    ) -> SharedVarMemory {
+        let scope = Self::new(enclosing);
+        scope.borrow_mut().define(0, name.to_string(), val);
+        scope
+    }
+
+    pub fn get(&self, index: usize) -> Option<VarValue> {
+        self.variables.get(index).map(|(_, v)| v.clone())
+    }
+
+    fn define(&mut self, index: usize, name: String, val: VarValue) {
+        if index >= self.variables.len() {
+            let nil = (String::new(), VarValue::Classic(KindOfVal::Nil));
+            self.variables.resize(index + 1, nil);
         }
+        self.variables[index] = (name, val);
     }
+}
 
-    fn default_func() -> String {
-        format!("global_ctx")
+/// The variables of `scope` with their values, in declaration order.
+fn variables(scope: &SharedVarMemory) -> Vec<(String, String)> {
+    scope
+        .borrow()
+        .variables
+        .iter()
+        .filter(|(name, _)| !name.is_empty())
+        .map(|(name, val)| (name.clone(), val.to_interm().to_string()))
+        .collect()
+}
+
+impl Default for BaseMemory {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
 #[derive(Debug)]
 pub struct Interpreter {
     memory: BaseMemory,
-    register: RegisterX,
-    ctx: Context,
+    pub(super) modules: Loader<SharedModule>,
+    /// The value of the `throw` whose error is propagating, handed to the
+    /// `catch` clause stopping it instead of the error itself.
+    pub(super) thrown: Option<IntermRepr>,
+    pub(super) budget: Budget,
+    pub(super) debugger: Option<Debugger>,
+}
+
+impl Default for Interpreter {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Interpreter {
     pub fn new() -> Self {
         Self {
             memory: BaseMemory::new(),
-            register: RegisterX::Empty,
-            ctx: Context::new(),
+            modules: Loader::new(),
+            thrown: None,
+            budget: Budget::default(),
+            debugger: None,
         }
     }
 
-    pub fn allocate_memory_scope(&mut self) {
-        let last: &mut InnerMemory = self.memory.iner_memory.last_mut().unwrap();
-        last.base.push(VarMemory::new());
+    /// Hands the program to `hooks` before every statement; it stops before
+    /// the first one.
+    pub fn set_debugger(&mut self, hooks: Box<dyn DebugHooks>) {
+        self.debugger = Some(Debugger::new(hooks));
     }
 
-    pub fn deallocate_memory_scope(&mut self) {
-        // assert!(self.memory.iner_memory.len() > 1);
-        self.memory.iner_memory.last_mut().unwrap().base.pop();
+    pub fn set_limits(&mut self, limits: Limits) {
+        self.budget = Budget::new(limits);
     }
 
-    pub fn allocate_func_scope(&mut self) {
-        self.memory.iner_memory.push(InnerMemory::new());
+    /// Gives the code run from now on the whole of the limits again.
+    pub fn restart_budget(&mut self) {
+        self.budget.restart();
     }
 
-    pub fn deallocate_func_scope(&mut self) {
-        // assert!(self.memory.iner_memory.len() > 1);s
-        self.memory.iner_memory.pop();
+    /// Counts a value of `size` units created at `span` against the
+    /// allocation limit.
+    pub(super) fn allocate(&mut self, span: Span, size: usize) -> Result<(), RuntimeError> {
+        self.budget
+            .allocate(size)
+            .map_err(|limit| RuntimeError::exceeded(limit, span))
     }
 
-    pub fn set_fn_name(&mut self, name: String) {
-        self.ctx.curr_func = name;
+    /// Calls in progress, module top levels included.
+    pub(super) fn call_depth(&self) -> usize {
+        self.memory.iner_memory.len() - 1
     }
 
-    pub fn eval(inter: Rc<RefCell<Interpreter>>, s: String) -> Result<(), Error> {
-        parse_token(s, Some(inter), ParserOptions::EVALUATE)
-    }
-    pub fn exec(inter: Rc<RefCell<Interpreter>>, s: String) -> Result<(), Error> {
-        opti_run(s, Some(inter), ParserOptions::RUN)
-    }
-    pub fn push_var(&mut self, name: String, val: VarValue) {
-        self.memory
-            .iner_memory
-            .last_mut()
-            .unwrap()
-            .base
-            .last_mut()
-            .unwrap()
-            .variables
-            .insert(name, val);
-    }
-
-    pub fn get_local_var(&self, name: &str) -> Option<VarValue> {
-        for mem in self.memory.iner_memory.last().unwrap().base.iter().rev() {
-            // Local Memory
-            if let Some(var) = mem.variables.get(name) {
-                return Some(var.clone());
-            }
-        }
-        None
+    /// Forgets the value of a `throw` once its error left the program.
+    pub fn clear_thrown(&mut self) {
+        self.thrown = None;
     }
 
-    pub fn get_var(&self, name: &str) -> Option<VarValue> {
-        if self.ctx.curr_func == Context::default_func() {
-            for var in self.memory.iner_memory.first().unwrap().base.iter().rev() {
-                if let Some(var) = var.variables.get(name) {
-                    return Some(var.clone());
-                }
-            }
-        } else {
-            if let Some(var) = self.get_local_var(name) {
-                return Some(var);
-            }
+    /// The globals of the module being run.
+    pub fn current_globals(&self) -> SharedGlobals {
+        self.memory.frame().globals.clone()
+    }
 
-            for mem in self.memory.iner_memory.first().unwrap().base.iter() {
-                // Global Memory
-                if let Some(var) = mem.variables.get(name) {
-                    return Some(var.clone());
-                }
-            }
-        }
+    pub fn allocate_memory_scope(&mut self) {
+        let scope = VarMemory::new(self.memory.scope());
+        self.memory.frame_mut().base.push(scope);
+    }
 
-        self.memory.get_fn(name).map(|f| {
-            VarValue::Func(Rc::new(RefCell::new(FunctionObject::new(
-                Arc::clone(f),
-                None,
-            ))))
-        })
+    pub fn deallocate_memory_scope(&mut self) {
+        self.memory.frame_mut().base.pop();
     }
 
-    pub fn extend_memory(&mut self, vars: HashMap<String, VarValue>) {
+    /// Enters a call to a function declared in `closure`, with the globals of
+    /// its module, or the current ones for natives.
+    pub fn allocate_func_scope(
+        &mut self,
+        closure: Option<SharedVarMemory>,
+        globals: Option<SharedGlobals>,
+    ) {
+        let globals = globals.unwrap_or_else(|| self.current_globals());
         self.memory
             .iner_memory
-            .last_mut()
-            .unwrap()
-            .base
-            .last_mut()
-            .unwrap()
-            .variables
-            .extend(vars);
-    }
-
-    pub fn get_fn_name(&self) -> String {
-        self.ctx.curr_func.clone()
-    }
-
-    pub fn get_curr_fn(&self) -> Option<SharedFunctionObject> {
-        for mem in self.memory.iner_memory.iter().rev() {
-            for (k, v) in &mem.base.last().unwrap().variables {
-                match v {
-                    VarValue::Func(f) => {
-                        if f.borrow().ptr.name == self.ctx.curr_func || *k == f.borrow().ptr.name {
-                            return Some(f.clone());
-                        }
-                    }
-                    _ => {}
-                }
-            }
-        }
-        None
+            .push(InnerMemory::new(closure, globals));
     }
 
-    pub fn is_closure(&self) -> bool {
-        if self.get_fn_name() != Context::default_func() && self.memory.iner_memory.len() > 1 {
-            true
-        } else {
-            false
-        }
+    /// Enters the top level of a module, whose globals are `globals`.
+    pub fn allocate_module_scope(&mut self, globals: SharedGlobals) {
+        self.memory.iner_memory.push(InnerMemory {
+            base: vec![],
+            globals,
+        });
     }
 
-    pub fn set_reg(&mut self, reg: RegisterX) {
-        self.register = reg;
+    pub fn deallocate_func_scope(&mut self) {
+        self.memory.iner_memory.pop();
     }
 
-    pub fn get_fn(&self, name: &str) -> Option<&Function> {
-        self.memory.get_fn(name).map(|f| f.as_ref())
+    /// Declares `name` in the current scope, or as a global when the resolver
+    /// did not give it a slot.
+    pub fn define_var(&mut self, name: String, slot: Option<Slot>, val: VarValue) {
+        match slot {
+            Some(slot) => self
+                .memory
+                .scope()
+                .unwrap()
+                .borrow_mut()
+                .define(slot.index, name, val),
+            None => {
+                self.memory.frame().globals.borrow_mut().insert(name, val);
+            }
+        }
     }
 
-    pub fn pop_reg(&mut self) -> RegisterX {
-        let tmp = self.register.clone();
-        self.register = RegisterX::Empty;
-        tmp
+    pub fn get_var(&self, name: &str, slot: Option<Slot>) -> Option<VarValue> {
+        match slot {
+            Some(slot) => self.memory.ancestor(slot.depth)?.borrow().get(slot.index),
+            None => self.memory.frame().globals.borrow().get(name).cloned(),
+        }
     }
 
-    pub fn edit_existing_var(&mut self, name: &str, value: VarValue) -> Result<(), Error> {
-        for mem in self
-            .memory
-            .iner_memory
-            .last_mut()
-            .unwrap()
-            .base
-            .iter_mut()
-            .rev()
-        {
-            // Local Memory
-            if let Some(_var) = mem.variables.get(name) {
-                mem.variables.insert(name.to_string(), value);
-                return Ok(());
+    pub fn assign_var(
+        &mut self,
+        name: &str,
+        slot: Option<Slot>,
+        value: VarValue,
+    ) -> Result<(), String> {
+        let undefined = || format!("Undefined variable '{}'.", name);
+        match slot {
+            Some(slot) => {
+                let scope = self.memory.ancestor(slot.depth).ok_or_else(undefined)?;
+                let mut scope = scope.borrow_mut();
+                let var = scope.variables.get_mut(slot.index).ok_or_else(undefined)?;
+                var.1 = value;
             }
-        }
-        if let Some(mem) = self
-            .memory
-            .iner_memory
-            .first_mut()
-            .unwrap()
-            .base
-            .first_mut()
-        {
-            // Global Memory
-            if let Some(_var) = mem.variables.get(name) {
-                mem.variables.insert(name.to_string(), value);
-                return Ok(());
+            None => {
+                let mut globals = self.memory.frame().globals.borrow_mut();
+                *globals.get_mut(name).ok_or_else(undefined)? = value;
             }
         }
-        Err(Error::Runtime)
-    }
-
-    pub fn push_func_var(&mut self, var_name: String, f: Rc<RefCell<FunctionObject>>) {
-        self.push_var(var_name.clone(), VarValue::Func(f));
-    }
-
-    pub fn push_func(&mut self, name: String, args: Vec<ExprAst>, body: ExprAst) {
-        let f = Function::new(args, body, name.clone());
-        self.memory.functions.push(Arc::new(f));
+        Ok(())
+    }
+
+    pub fn push_func(
+        &mut self,
+        name: String,
+        slot: Option<Slot>,
+        args: Vec<ExprAst>,
+        body: ExprAst,
+    ) {
+        let fo = self.new_function(name.clone(), args, body);
+        self.define_var(name, slot, VarValue::Func(fo));
+    }
+
+    /// A function closing over the current scope.
+    pub fn new_function(
+        &self,
+        name: String,
+        args: Vec<ExprAst>,
+        body: ExprAst,
+    ) -> SharedFunctionObject {
+        let f = Rc::new(Function::new(args, body, name));
+        let globals = self.current_globals();
+        let fo = FunctionObject::new(f, self.memory.scope(), Some(globals));
+        Rc::new(RefCell::new(fo))
+    }
+
+    /// Defines the global function `name`, implemented by `native`.
+    pub fn define_native(&mut self, name: String, native: Native) {
+        let f = Rc::new(Function::new_native(native, name.clone()));
+        let fo = FunctionObject::new(f, None, None);
+        self.define_var(name, None, VarValue::Func(Rc::new(RefCell::new(fo))));
+    }
+
+    pub fn push_class(
+        &mut self,
+        name: String,
+        slot: Option<Slot>,
+        superclass: Option<SharedClass>,
+        methods: Vec<(String, Vec<ExprAst>, ExprAst)>,
+        span: Span,
+    ) {
+        let methods = methods
+            .into_iter()
+            .map(|(name, args, body)| {
+                let method = Rc::new(Function::new_method(args, body, name.clone()));
+                (name, method)
+            })
+            .collect();
+        let mut closure = self.memory.scope();
+        if let Some(ref superclass) = superclass {
+            let superclass = VarValue::Class(superclass.clone());
+            closure = Some(VarMemory::with_var("super", superclass, closure));
+        }
+        let globals = self.current_globals();
+        let class = Rc::new(Class::new(
+            name.clone(),
+            methods,
+            superclass,
+            closure,
+            globals,
+            span,
+        ));
+        self.define_var(name, slot, VarValue::Class(class));
+    }
+
+    pub fn globals(&self) -> Vec<(String, String)> {
+        self.list_globals(true)
+    }
+
+    /// The globals the program defined, the natives left out.
+    pub fn defined_globals(&self) -> Vec<(String, String)> {
+        self.list_globals(false)
+    }
+
+    fn list_globals(&self, natives: bool) -> Vec<(String, String)> {
+        let is_native =
+            |val: &VarValue| matches!(val, VarValue::Func(f) if f.borrow().ptr.native.is_some());
+        let mut globals: Vec<_> = self
+            .current_globals()
+            .borrow()
+            .iter()
+            .filter(|(_, val)| natives || !is_native(val))
+            .map(|(name, val)| (name.clone(), val.to_interm().to_string()))
+            .collect();
+        globals.sort();
+        globals
+    }
+
+    /// The variables of the scopes the current call opened, innermost first.
+    pub fn locals(&self) -> Vec<(String, String)> {
+        let base = &self.memory.frame().base;
+        base.iter().rev().flat_map(variables).collect()
+    }
+
+    /// The variables of the scopes enclosing the current call's, those a
+    /// closure captured, innermost first.
+    pub fn captured(&self) -> Vec<(String, String)> {
+        let mut vars = Vec::new();
+        let first = self.memory.frame().base.first();
+        let mut scope = first.and_then(|s| s.borrow().enclosing.clone());
+        while let Some(s) = scope {
+            vars.extend(variables(&s));
+            scope = s.borrow().enclosing.clone();
+        }
+        vars
     }
 
-    pub fn push_closure(&mut self, name: String, args: Vec<ExprAst>, body: ExprAst) {
-        let capture_args: HashMap<String, VarValue> = self
-            .memory
-            .iner_memory
-            .last()
-            .unwrap()
-            .base
-            .last()
-            .unwrap()
-            .variables
-            .clone();
-        let f = Arc::new(Function::new(args, body, name.clone()));
-        let fo = Rc::new(RefCell::new(FunctionObject::new(
-            f.clone(),
-            Some(capture_args),
-        )));
-        self.memory
-            .iner_memory
-            .last_mut()
-            .unwrap()
-            .base
-            .last_mut()
-            .unwrap()
-            .variables
-            .insert(name.clone(), VarValue::Func(fo));
-        self.memory.functions.push(f);
+    pub fn instantiate(&self, class: &SharedClass) -> SharedInstance {
+        Rc::new(RefCell::new(Instance::new(class.clone())))
     }
 }
@@ -0,0 +1,186 @@
+use super::core::Interpreter;
+use std::fmt;
+
+/// Why the program stopped.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Stop {
+    /// The statement is on a line with a breakpoint.
+    Breakpoint,
+    /// The step asked for last is over, or the program just started.
+    Step,
+}
+
+/// How a stopped program goes on.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Resume {
+    /// Runs until the next breakpoint.
+    Continue,
+    /// Stops at the next line, in a function the current one calls if
+    /// it calls one.
+    StepInto,
+    /// Stops at the next line of the current function, or of its
+    /// caller once it returns.
+    StepOver,
+    /// Stops at the next line of the caller.
+    StepOut,
+}
+
+/// What a debugger front end implements to drive the tree-walking
+/// interpreter: it is asked about breakpoints before every statement, and
+/// called when the program stops there.
+pub trait DebugHooks {
+    /// Whether the program stops before the statement starting on `line`.
+    fn is_breakpoint(&mut self, line: usize) -> bool;
+
+    /// The program stopped before the statement on `state.line`, until this
+    /// returns.
+    fn paused(&mut self, stop: Stop, state: &Paused) -> Resume;
+}
+
+/// A call in progress.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Frame {
+    /// The function called, `script` for the top level.
+    pub function: String,
+    /// The line of the statement it runs.
+    pub line: usize,
+    /// Whether the program stopped on `line` already, it doesn't stop there
+    /// again before moving to another line.
+    stopped: bool,
+}
+
+/// The state of a stopped program, as the hooks see it.
+pub struct Paused<'a> {
+    interpreter: &'a Interpreter,
+    frames: &'a [Frame],
+    pub line: usize,
+}
+
+impl Paused<'_> {
+    /// The calls in progress, innermost first.
+    pub fn stack(&self) -> impl Iterator<Item = &Frame> {
+        self.frames.iter().rev()
+    }
+
+    /// The variables of the current call, innermost scope first.
+    pub fn locals(&self) -> Vec<(String, String)> {
+        self.interpreter.locals()
+    }
+
+    /// The variables of the scopes the current function closes over,
+    /// innermost first.
+    pub fn captured(&self) -> Vec<(String, String)> {
+        self.interpreter.captured()
+    }
+
+    /// The global variables the program defined, sorted by name.
+    pub fn globals(&self) -> Vec<(String, String)> {
+        self.interpreter.defined_globals()
+    }
+
+    /// The global variables along with the natives, sorted by name.
+    pub fn all_globals(&self) -> Vec<(String, String)> {
+        self.interpreter.globals()
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Step {
+    Run,
+    Into,
+    /// Until the stack is at most this deep.
+    Over(usize),
+    /// Until the stack is less deep than this.
+    Out(usize),
+}
+
+/// The hooks of an interpreter being debugged, with the calls it made and
+/// the step in progress.
+pub struct Debugger {
+    hooks: Box<dyn DebugHooks>,
+    frames: Vec<Frame>,
+    step: Step,
+}
+
+impl fmt::Debug for Debugger {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Debugger")
+            .field("frames", &self.frames)
+            .field("step", &self.step)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Debugger {
+    /// Stops at the first statement, so breakpoints can be set before
+    /// anything runs.
+    pub fn new(hooks: Box<dyn DebugHooks>) -> Self {
+        Self {
+            hooks,
+            frames: vec![Frame {
+                function: "script".to_string(),
+                line: 0,
+                stopped: false,
+            }],
+            step: Step::Into,
+        }
+    }
+
+    pub(super) fn enter(&mut self, function: &str) {
+        self.frames.push(Frame {
+            function: function.to_string(),
+            line: 0,
+            stopped: false,
+        });
+    }
+
+    pub(super) fn leave(&mut self) {
+        self.frames.pop();
+    }
+
+    /// Moves the current call to `line`, where a loop goes back to before
+    /// each pass.
+    pub(super) fn goto(&mut self, line: usize) {
+        if let Some(frame) = self.frames.last_mut() {
+            if frame.line != line {
+                frame.line = line;
+                frame.stopped = false;
+            }
+        }
+    }
+
+    /// Calls the hooks if the program stops before the statement on `line`,
+    /// unless it stopped on that line already.
+    pub(super) fn statement(&mut self, interpreter: &Interpreter, line: usize) {
+        self.goto(line);
+        let depth = self.frames.len();
+        let Some(frame) = self.frames.last_mut() else {
+            return;
+        };
+        if frame.stopped {
+            return;
+        }
+        let stop = if self.hooks.is_breakpoint(line) {
+            Stop::Breakpoint
+        } else {
+            match self.step {
+                Step::Into => Stop::Step,
+                Step::Over(d) if depth <= d => Stop::Step,
+                Step::Out(d) if depth < d => Stop::Step,
+                _ => return,
+            }
+        };
+        frame.stopped = true;
+        let state = Paused {
+            interpreter,
+            frames: &self.frames,
+            line,
+        };
+        self.step = match self.hooks.paused(stop, &state) {
+            Resume::Continue => Step::Run,
+            Resume::StepInto => Step::Into,
+            Resume::StepOver => Step::Over(depth),
+            Resume::StepOut => Step::Out(depth),
+        };
+    }
+}
@@ -1,735 +1,740 @@
+use super::class::*;
 use super::core::*;
 use super::func::builtin::SharedFunctionObject;
-use crate::lexer::Token;
+use super::list::{List, SharedList};
+use super::map::{self, Map, SharedMap};
+use super::module::SharedModule;
+use crate::backend::{list_index, Backend};
+use crate::error::{CaughtError, RuntimeError};
+use crate::lexer::{Span, Token};
+use crate::limits::Limits;
+use crate::map::{MapKey, OrderedMap};
 use crate::parser::parser_ds::*;
-use std::collections::HashMap;
-use std::process::exit;
+use std::fmt;
+use std::path::Path;
+use std::rc::Rc;
 
-impl IntermRepr {
-    pub fn to_string(&self) -> String {
-        match &self {
+impl fmt::Display for IntermRepr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match &self {
             Self::Bool(s) => s.to_string(),
-            Self::Num(s) => s.clone(),
+            Self::Num(n) => n.to_string(),
             Self::Nil => "nil".to_string(),
             Self::Str(s) => s.clone(),
             Self::Op(s) => s.clone(),
-            Self::Ident(id) => id.0.to_string(),
-            Self::Func(s) => s.borrow().ptr.name.clone(),
+            Self::Func(s) => format!("<fn {}>", s.borrow().ptr.name),
+            Self::Class(c) => format!("<class {}>", c.name),
+            Self::Instance(i) => format!("<instance {}>", i.borrow().class.name),
+            Self::List(l) => l.borrow().to_string(),
+            Self::Map(m) => m.borrow().to_string(),
+            Self::Module(m) => format!("<module {}>", m.name),
+            Self::Error(e) => e.to_string(),
             Self::Ret(ref r) => r.to_string().clone(),
-        }
+            Self::Break | Self::Continue => "nil".to_string(),
+        };
+        f.write_str(&s)
     }
+}
 
-    fn expect_ident(&self) -> Result<String, String> {
+impl IntermRepr {
+    /// Lox truthiness: `nil`, `false`, `0` and `""` are false.
+    pub fn is_truthy(&self) -> bool {
         match self {
-            IntermRepr::Ident(ref id) => Ok(id.0.clone()),
-            IntermRepr::Func(ref f) => Ok(f.borrow().ptr.name.clone()),
-            _ => Err(format!("Can only call functions and classes.")),
+            IntermRepr::Bool(v) => *v,
+            IntermRepr::Str(v) => !v.is_empty(),
+            IntermRepr::Nil => false,
+            IntermRepr::Num(n) => *n != 0.0,
+            IntermRepr::Ret(r) => r.is_truthy(),
+            IntermRepr::Break | IntermRepr::Continue => false,
+            IntermRepr::Op(_)
+            | IntermRepr::Func(_)
+            | IntermRepr::Class(_)
+            | IntermRepr::Instance(_)
+            | IntermRepr::List(_)
+            | IntermRepr::Map(_)
+            | IntermRepr::Module(_)
+            | IntermRepr::Error(_) => true,
         }
     }
 
-    pub fn to_number(&self) -> f64 {
-        let n: f64 = self.to_string().parse().unwrap();
-        n
+    pub fn get_value(&self) -> VarValue {
+        interm_to_var_val(self)
     }
 
-    pub fn get_bool(&self) -> Option<bool> {
-        match self {
-            IntermRepr::Bool(v) => Some(v.clone()),
-            IntermRepr::Str(_v) => Some(true),
-            IntermRepr::Nil => Some(false),
-            IntermRepr::Num(_) => Some(self.to_number() != 0.0),
-            IntermRepr::Ident(ref id) => Some(id.1.to_bool()),
-            _ => None,
-        }
-    }
-
-    pub fn get_value(&self) -> Option<VarValue> {
-        match self {
-            // IntermRepr::Func(f) => f,
-            IntermRepr::Ident(id) => Some(id.1.clone()),
-            _ => Some(interm_to_var_val(self)),
-        }
+    /// Whether this is a `return`, `break` or `continue` on its way out of
+    /// the enclosing blocks.
+    fn is_jump(&self) -> bool {
+        matches!(
+            self,
+            IntermRepr::Ret(_) | IntermRepr::Break | IntermRepr::Continue
+        )
     }
 }
 
-#[allow(dead_code)]
 #[derive(Debug, Clone, PartialEq)]
 pub enum IntermRepr {
     Bool(bool),
-    Num(String),
+    Num(f64),
     Nil,
     Str(String),
-    Ident(Box<(String, VarValue)>),
     Func(SharedFunctionObject),
+    Class(SharedClass),
+    Instance(SharedInstance),
+    List(SharedList),
+    Map(SharedMap),
+    Module(SharedModule),
+    Error(Rc<CaughtError>),
     Op(String),
     Ret(Box<IntermRepr>),
+    Break,
+    Continue,
 }
 
-pub fn to_number(rep: &IntermRepr) -> Result<f64, String> {
-    match rep {
-        IntermRepr::Num(ref n) => {
-            let f: f64 = n.parse().unwrap();
-            Ok(f)
-        }
-        _ => Err("Operands must be numbers.".to_string()),
+fn add_values(lhs: &IntermRepr, rhs: &IntermRepr, span: Span) -> Result<IntermRepr, RuntimeError> {
+    match (lhs, rhs) {
+        (IntermRepr::Num(l), IntermRepr::Num(r)) => Ok(IntermRepr::Num(l + r)),
+        (IntermRepr::Str(l), IntermRepr::Str(r)) => Ok(IntermRepr::Str(l.clone() + r)),
+        _ => Err(RuntimeError::new(
+            "Operands must be two numbers or two strings.",
+            span,
+        )),
     }
 }
 
-fn to_number_rhs(rep: &IntermRepr) -> Result<f64, String> {
-    match rep {
-        IntermRepr::Ident(n) => {
-            let f: f64 = n.0.parse().unwrap();
-            Ok(f)
-        }
-        IntermRepr::Num(ref n) => {
-            let f: f64 = n.parse().unwrap();
-            Ok(f)
-        }
-        _ => Err("Operands must be two numbers or two strings.".to_string()),
+fn number_op(
+    lhs: &IntermRepr,
+    rhs: &IntermRepr,
+    span: Span,
+    op: impl FnOnce(f64, f64) -> IntermRepr,
+) -> Result<IntermRepr, RuntimeError> {
+    match (lhs, rhs) {
+        (IntermRepr::Num(l), IntermRepr::Num(r)) => Ok(op(*l, *r)),
+        _ => Err(RuntimeError::new("Operands must be numbers.", span)),
     }
 }
 
-impl Interpreter {
-    pub fn expect_num(&self, ir: &IntermRepr) -> Result<f64, String> {
-        match ir {
-            IntermRepr::Bool(_) => todo!(),
-            IntermRepr::Num(n) => return Ok(n.parse::<f64>().unwrap()),
-            IntermRepr::Nil => todo!(),
-            IntermRepr::Str(_) => todo!(),
-            IntermRepr::Ident(_) => {
-                if let Some(vv) = self.get_var(&ir.to_string()) {
-                    match vv.get_kind().unwrap() {
-                        KindOfVal::Num(value) => {
-                            let f: f64 = value.parse().unwrap();
-                            return Ok(f);
-                        }
-                        _ => unreachable!("{:?}", vv.get_kind()),
-                    }
-                }
-            }
-            IntermRepr::Op(_) => todo!(),
-            IntermRepr::Func(_) => todo!(),
-            IntermRepr::Ret(_) => todo!(),
-        }
-        Err(format!("Expect a number and get {:?}", ir))
-    }
+/// What `object[index]` designates.
+enum Element {
+    List(SharedList, usize),
+    Map(SharedMap, MapKey),
+}
 
-    fn lhs_to_number(&mut self, bin_expr: &BinExprAst) -> Result<f64, String> {
-        match *bin_expr.lhs {
-            ExprAst::IdenAst(ref ident_expr_ast) => {
-                let eval = &self.eval_ident_expr(ident_expr_ast)?;
-                self.expect_num(eval)
-            }
-            _ => Ok(to_number(&self.evaluate(&bin_expr.lhs)?)?),
+fn element(object: IntermRepr, index: IntermRepr) -> Result<Element, String> {
+    match (object, index) {
+        (IntermRepr::List(list), IntermRepr::Num(index)) => {
+            let i = list_index(index, list.borrow().items.len())?;
+            Ok(Element::List(list, i))
         }
+        (IntermRepr::List(_), _) => Err("List index must be a number.".to_string()),
+        (IntermRepr::Map(map), index) => Ok(Element::Map(map, map::key(&index)?)),
+        _ => Err("Only lists and maps can be indexed.".to_string()),
     }
+}
 
-    fn rhs_to_number(&mut self, bin_expr: &BinExprAst) -> Result<f64, String> {
-        match *bin_expr.rhs {
-            ExprAst::IdenAst(ref ident_expr_ast) => {
-                let eval = &self.eval_ident_expr(ident_expr_ast)?;
-                self.expect_num(eval)
-            }
-            _ => Ok(to_number_rhs(&self.evaluate(&bin_expr.rhs)?)?),
-        }
-    }
-    fn evaluate_star(&mut self, bin_expr: &BinExprAst) -> Result<IntermRepr, String> {
-        let lhs: f64 = self.lhs_to_number(&bin_expr)?;
-        let rhs: f64 = self.rhs_to_number(&bin_expr)?;
-        Ok(IntermRepr::Num((lhs * rhs).to_string()))
+impl Interpreter {
+    fn eval_var_assign(&mut self, expr_ast: &AssignExprAst) -> Result<IntermRepr, RuntimeError> {
+        let res = self.evaluate(&expr_ast.rhs)?;
+        let id = &expr_ast.lhs;
+        self.define_var(id.val.clone(), id.slot, res.get_value());
+        Ok(res)
     }
 
-    fn evaluate_minus(&mut self, bin_expr: &BinExprAst) -> Result<IntermRepr, String> {
-        let lhs: f64 = self.lhs_to_number(bin_expr)?;
-        let rhs: f64 = self.rhs_to_number(bin_expr)?;
-        Ok(IntermRepr::Num((lhs - rhs).to_string()))
+    fn eval_bool(&self, expr_ast: &BoolExprAst) -> Result<IntermRepr, RuntimeError> {
+        Ok(IntermRepr::Bool(expr_ast.val))
     }
 
-    fn evaluate_slash(&mut self, bin_expr: &BinExprAst) -> Result<IntermRepr, String> {
-        let lhs: f64 = self.lhs_to_number(bin_expr)?;
-        let rhs: f64 = self.rhs_to_number(bin_expr)?;
-        Ok(IntermRepr::Num((lhs / rhs).to_string()))
+    fn eval_number(&self, expr_ast: &NumberExprAst) -> Result<IntermRepr, RuntimeError> {
+        Ok(IntermRepr::Num(expr_ast.number))
     }
 
-    fn compare_number(&mut self, bin_expr: &BinExprAst) -> Result<IntermRepr, String> {
-        let lhs: f64 = self.lhs_to_number(bin_expr)?;
-        let rhs: f64 = self.rhs_to_number(bin_expr)?;
-
-        let res = match bin_expr.op {
-            Token::Less => lhs < rhs,
-            Token::LessEqual => lhs <= rhs,
-            Token::Greater => lhs > rhs,
-            Token::GreaterEqual => lhs >= rhs,
-            _ => unreachable!(),
-        };
-        Ok(IntermRepr::Bool(res))
+    fn eval_negative_number(
+        &mut self,
+        expr_ast: &NegativeExprAst,
+    ) -> Result<IntermRepr, RuntimeError> {
+        match self.evaluate(&expr_ast.rhs)? {
+            IntermRepr::Num(n) => Ok(IntermRepr::Num(-n)),
+            _ => Err(RuntimeError::new(
+                "Operands must be numbers.",
+                expr_ast.span,
+            )),
+        }
     }
 
-    fn id_add(&mut self, var: &VarValue, bin_expr: &BinExprAst) -> Result<IntermRepr, String> {
-        match var {
-            VarValue::Classic(ref kind) => match kind {
-                KindOfVal::Str(value) => {
-                    let o = value.clone() + &self.evaluate(&bin_expr.rhs)?.to_string();
-                    return Ok(IntermRepr::Str(o));
-                }
-                KindOfVal::Num(value) => {
-                    let val: f64 = to_number(&IntermRepr::Num(value.clone()))?;
-                    let ir = self.evaluate(&bin_expr.rhs).unwrap();
-                    let rhs: f64 = self.expect_num(&ir)?;
-                    return Ok(IntermRepr::Num((val + rhs).to_string()));
-                }
-                KindOfVal::Bool(_) => todo!(),
-                KindOfVal::Nil => todo!(),
-            },
-            VarValue::Func(_) => todo!(),
-        }
-    }
-
-    fn evaluate_plus(&mut self, bin_expr: &BinExprAst) -> Result<IntermRepr, String> {
-        match *bin_expr.lhs {
-            ExprAst::BinaryAst(ref bin) => {
-                let first = self.evaluate(&bin.lhs)?;
-                match first {
-                    IntermRepr::Num(_) => {
-                        let lhs: f64 = self.lhs_to_number(bin_expr)?;
-                        let rhs: f64 = self.rhs_to_number(bin_expr)?;
-                        let o = (lhs + rhs).to_string();
-                        Ok(IntermRepr::Num(o))
-                    }
-                    IntermRepr::Str(_) => {
-                        let o = first.to_string()
-                            + &self.expect_str_or_id(&bin.rhs)?
-                            + &self.expect_str_or_id(&bin_expr.rhs)?;
-                        Ok(IntermRepr::Str(o))
-                    }
-                    IntermRepr::Ident(_) => {
-                        if let Some(var) = self.get_var(&bin.lhs.to_string()) {
-                            match var.get_kind().unwrap() {
-                                KindOfVal::Str(_val) => {
-                                    let o = first.to_string()
-                                        + &self.expect_str_or_id(&bin.rhs)?
-                                        + &self.expect_str_or_id(&bin_expr.rhs)?;
-                                    Ok(IntermRepr::Str(o))
-                                }
-                                KindOfVal::Num(_val) => {
-                                    let lhs: f64 = self.lhs_to_number(bin_expr)?;
-                                    let rhs: f64 = self.rhs_to_number(bin_expr)?;
-                                    let o = (lhs + rhs).to_string();
-                                    Ok(IntermRepr::Num(o))
-                                }
-                                _ => todo!(),
-                            }
-                        } else {
-                            todo!()
-                        }
-                    }
-                    IntermRepr::Func(_f) => {
-                        todo!()
-                    }
-                    _ => unimplemented!(),
-                }
-            }
-            ExprAst::StrAst(_) => {
-                let o = self.evaluate(&bin_expr.lhs)?.to_string()
-                    + &self.expect_str_or_id(&bin_expr.rhs)?;
-                Ok(IntermRepr::Str(o))
-            }
-            ExprAst::NumAst(_) => {
-                let lhs: f64 = self.lhs_to_number(bin_expr)?;
-                let rhs: f64 = self.rhs_to_number(bin_expr)?;
-                Ok(IntermRepr::Num((lhs + rhs).to_string()))
-            }
-            ExprAst::FnCallAst(_) => {
-                let lhs = self.evaluate(&bin_expr.lhs).unwrap();
-                let rhs = self.evaluate(&bin_expr.rhs).unwrap();
-
-                match (&lhs, &rhs) {
-                    (IntermRepr::Num(_), _) => Ok(IntermRepr::Num(
-                        (lhs.to_number() + to_number_rhs(&rhs).unwrap()).to_string(),
-                    )),
-                    (_, IntermRepr::Num(_)) => Ok(IntermRepr::Num(
-                        (lhs.to_number() + rhs.to_number()).to_string(),
-                    )),
-
-                    (IntermRepr::Str(_), _) => Ok(IntermRepr::Str(
-                        lhs.to_string()
-                            + &self.expect_str_or_id(&bin_expr.rhs).unwrap().to_string(),
-                    )),
-                    _ => unreachable!("lhs : {:?}, rhs : {:?}", lhs, rhs),
+    fn eval_bin_expr(&mut self, expr_ast: &BinExprAst) -> Result<IntermRepr, RuntimeError> {
+        match expr_ast.op {
+            Token::Or => {
+                let lhs = self.evaluate(&expr_ast.lhs)?;
+                if lhs.is_truthy() {
+                    return Ok(lhs);
                 }
+                return self.eval_logical_rhs(expr_ast);
             }
-            ExprAst::IdenAst(ref id) => {
-                if let Some(ref var) = self.get_var(&id.val) {
-                    return self.id_add(&var, &bin_expr);
-                } else {
-                    eprintln!("var not found {}", id.val);
-                    exit(70);
+            Token::And => {
+                let lhs = self.evaluate(&expr_ast.lhs)?;
+                if !lhs.is_truthy() {
+                    return Ok(lhs);
                 }
+                return self.eval_logical_rhs(expr_ast);
             }
-            _ => {
-                let o = self.evaluate(&bin_expr.lhs)?.to_string()
-                    + &self.evaluate(&bin_expr.rhs)?.to_string();
-                Ok(IntermRepr::Str(o))
-            }
+            _ => (),
         }
-    }
 
-    fn expect_str_or_id(&mut self, rep: &ExprAst) -> Result<String, String> {
-        let interm_repr = match &rep {
-            ExprAst::StrAst(string_expr_ast) => self.eval_str_expr(string_expr_ast),
-            ExprAst::IdenAst(ident_expr_ast) => {
-                let v = self.get_var(&ident_expr_ast.val);
-                Ok(v.unwrap().to_interm())
-            }
-            ExprAst::BinaryAst(b) => self.eval_bin_expr(b),
-            _ => Err("Operands must be two numbers or two strings.".to_string()),
-        }?;
-        Ok(interm_repr.to_string())
-    }
-
-    fn eval_var_assign(&mut self, expr_ast: &AssignExprAst) -> Result<IntermRepr, String> {
-        match expr_ast.lhs.as_ref() {
-            ExprAst::IdenAst(ident_expr_ast) => {
-                let res = self.evaluate(&expr_ast.rhs)?;
-                if let Some(mut val) = res.get_value() {
-                    match val {
-                        VarValue::Func(ref mut _f) => {
-                            let reg = self.pop_reg();
-                            match reg {
-                                RegisterX::Fn(function) => {
-                                    self.push_func_var(ident_expr_ast.val.clone(), function);
-                                }
-                                _ => {
-                                    let eval = self.evaluate(&expr_ast.rhs)?;
-                                    match eval {
-                                        IntermRepr::Func(f) => {
-                                            self.push_func_var(
-                                                ident_expr_ast.val.clone(),
-                                                f.clone(),
-                                            );
-                                        }
-                                        _ => {
-                                            todo!()
-                                        }
-                                    }
-                                    return Ok(IntermRepr::Nil);
-                                }
-                            }
-                        }
-                        _ => {
-                            self.push_var(ident_expr_ast.val.clone(), val);
-                        }
-                    }
-                } else {
-                    dbg!("here");
-                    exit(70);
+        let lhs = self.evaluate(&expr_ast.lhs)?;
+        let rhs = self.evaluate(&expr_ast.rhs)?;
+        let span = expr_ast.span;
+        match expr_ast.op {
+            Token::Plus => {
+                if let (IntermRepr::Str(l), IntermRepr::Str(r)) = (&lhs, &rhs) {
+                    self.allocate(span, l.len() + r.len())?;
                 }
-
-                Ok(res)
+                add_values(&lhs, &rhs, span)
             }
-            _ => unimplemented!("Must be a variable!"),
+            Token::Minus => number_op(&lhs, &rhs, span, |l, r| IntermRepr::Num(l - r)),
+            Token::Slash => number_op(&lhs, &rhs, span, |l, r| IntermRepr::Num(l / r)),
+            Token::Star => number_op(&lhs, &rhs, span, |l, r| IntermRepr::Num(l * r)),
+            Token::Less => number_op(&lhs, &rhs, span, |l, r| IntermRepr::Bool(l < r)),
+            Token::LessEqual => number_op(&lhs, &rhs, span, |l, r| IntermRepr::Bool(l <= r)),
+            Token::Greater => number_op(&lhs, &rhs, span, |l, r| IntermRepr::Bool(l > r)),
+            Token::GreaterEqual => number_op(&lhs, &rhs, span, |l, r| IntermRepr::Bool(l >= r)),
+            Token::DoubleEqual => Ok(IntermRepr::Bool(lhs == rhs)),
+            Token::BangEqual => Ok(IntermRepr::Bool(lhs != rhs)),
+            ref op => Err(RuntimeError::new(
+                format!("Unknown binary operator '{}'.", op.to_usefull_str()),
+                span,
+            )),
         }
     }
-    fn eval_bool(&self, expr_ast: &BoolExprAst) -> Result<IntermRepr, String> {
-        Ok(IntermRepr::Bool(expr_ast.val))
-    }
 
-    fn eval_number(&self, expr_ast: &NumberExprAst) -> Result<IntermRepr, String> {
-        let f: f64 = expr_ast.number.parse().unwrap();
-        Ok(IntermRepr::Num(format!("{}", f)))
-    }
-
-    fn eval_negative_number(&mut self, expr_ast: &NegativeExprAst) -> Result<IntermRepr, String> {
-        let f: f64 = to_number(&self.evaluate(&expr_ast.rhs)?)?;
-        Ok(IntermRepr::Num((f * -1.).to_string()))
-    }
-
-    fn eval_double_equal(&mut self, expr_ast: &BinExprAst) -> Result<IntermRepr, String> {
-        // {
-        //     // dbg!(&self.lhs);
-        //     let lhs = self.evaluate(&expr_ast.lhs).unwrap();
-        //     let rhs = self.evaluate(&expr_ast.rhs).unwrap();
-        //     match (&lhs, &rhs) {
-        //         (IntermRepr::Str(s1), IntermRepr::Str(s2)) => {
-        //             Ok(IntermRepr::Bool(s1 == s2))
-        //         },
-        //         (IntermRepr::Str(_), _) => Ok(IntermRepr::Bool(false)),
-        //         (_, IntermRepr::Str(_)) => Ok(IntermRepr::Bool(false)),
-        //         _ => {
-        //             let op = lhs.get_bool().unwrap()
-        //                 == rhs.get_bool().unwrap();
-        //             Ok(IntermRepr::Bool(op))
-        //         }
-        //     }
-        // }
-        let lhs = self.evaluate(&expr_ast.lhs)?;
+    /// Right operand of `and`/`or` once the left one did not short-circuit:
+    /// its value when truthy, `false` otherwise.
+    fn eval_logical_rhs(&mut self, expr_ast: &BinExprAst) -> Result<IntermRepr, RuntimeError> {
         let rhs = self.evaluate(&expr_ast.rhs)?;
-        Ok(IntermRepr::Bool(lhs == rhs))
-    }
-
-    fn eval_bin_expr(&mut self, expr_ast: &BinExprAst) -> Result<IntermRepr, String> {
-        match expr_ast.op {
-            Token::Minus => self.evaluate_minus(expr_ast),
-            Token::Plus => self.evaluate_plus(expr_ast),
-            Token::Slash => self.evaluate_slash(expr_ast),
-            Token::Star => self.evaluate_star(expr_ast),
-            Token::GreaterEqual | Token::LessEqual | Token::Greater | Token::Less => {
-                self.compare_number(expr_ast)
-            }
-
-            Token::BangEqual => {
-                let op = self.evaluate(&expr_ast.lhs)? != self.evaluate(&expr_ast.rhs)?;
-                Ok(IntermRepr::Bool(op))
-            }
-            Token::DoubleEqual => self.eval_double_equal(expr_ast),
-            Token::Or => {
-                let left = self.evaluate(&expr_ast.lhs);
-                if let Ok(ref l) = left {
-                    if l.get_bool().unwrap() {
-                        return left;
-                    }
-                }
-                let rhs = self.evaluate(&expr_ast.rhs);
-                if let Ok(ref r) = rhs {
-                    if r.get_bool().unwrap() {
-                        return rhs;
-                    }
-                }
-                Ok(IntermRepr::Bool(false))
-            }
-            Token::And => {
-                let left = self.evaluate(&expr_ast.lhs);
-                if let Ok(ref l) = left {
-                    if !l.get_bool().unwrap() {
-                        return left;
-                    }
-                }
-                let rhs = self.evaluate(&expr_ast.rhs);
-                if let Ok(ref r) = rhs {
-                    if r.get_bool().unwrap() {
-                        return rhs;
-                    }
-                }
-                Ok(IntermRepr::Bool(false))
-            }
-            _ => unimplemented!(),
+        if rhs.is_truthy() {
+            Ok(rhs)
+        } else {
+            Ok(IntermRepr::Bool(false))
         }
     }
 
-    fn eval_unary_expr(&mut self, expr_ast: &UnaExprAst) -> Result<IntermRepr, String> {
+    fn eval_unary_expr(&mut self, expr_ast: &UnaExprAst) -> Result<IntermRepr, RuntimeError> {
         match expr_ast.op {
-            Token::Bang => match *expr_ast.rhs {
-                ExprAst::NumAst(ref n) => {
-                    let f: f64 = n.number.parse().unwrap();
-                    Ok(IntermRepr::Bool(f == 0.0))
-                }
-                ExprAst::BoolAst(ref b) => Ok(IntermRepr::Bool(!b.val)),
-                ExprAst::NilAst => Ok(IntermRepr::Bool(true)),
-                ExprAst::UnaryAst(ref e) => {
-                    let eval = self.evaluate(&e.rhs)?.get_bool();
-                    Ok(IntermRepr::Bool(eval.unwrap()))
-                }
-                ExprAst::IdenAst(ref id) => {
-                    let b = self.eval_ident_bool_expr(id).unwrap().get_bool().unwrap();
-                    Ok(IntermRepr::Bool(!b))
-                }
-                ExprAst::FnCallAst(ref f) => {
-                    let f = self.eval_fn_call_expr(f)?;
-                    let b = f.get_bool().unwrap();
-                    Ok(IntermRepr::Bool(!b))
-                }
-                _ => unimplemented!("{:?}", expr_ast.rhs),
-            },
-            _ => unimplemented!("{:?}", expr_ast.op),
+            Token::Bang => Ok(IntermRepr::Bool(!self.evaluate(&expr_ast.rhs)?.is_truthy())),
+            ref op => Err(RuntimeError::new(
+                format!("Unknown unary operator '{}'.", op.to_usefull_str()),
+                expr_ast.span,
+            )),
         }
     }
 
-    fn eval_if_expr(&mut self, expr_ast: &IfExprAst) -> Result<IntermRepr, String> {
-        if self
-            .evaluate_bool(&expr_ast.cond)
-            .unwrap()
-            .get_bool()
-            .unwrap()
-        {
-            self.evaluate(&expr_ast.then)
+    fn eval_if_expr(&mut self, expr_ast: &IfExprAst) -> Result<IntermRepr, RuntimeError> {
+        if self.evaluate(&expr_ast.cond)?.is_truthy() {
+            self.statement(&expr_ast.then)
         } else {
-            self.evaluate(&expr_ast.default)
+            self.statement(&expr_ast.default)
         }
     }
 
-    fn eval_print_expr(&mut self, expr_ast: &PrintExprAst) -> Result<IntermRepr, String> {
+    fn eval_print_expr(&mut self, expr_ast: &PrintExprAst) -> Result<IntermRepr, RuntimeError> {
         let r = self.evaluate(&expr_ast.expr)?;
-        match r {
-            IntermRepr::Ident(ref id) => {
-                dbg!(self.get_var(&id.0));
-                println!("{}", id.1.get_str_value().unwrap());
-            }
-            IntermRepr::Func(ref f) => {
-                println!("<fn {}>", f.borrow().ptr.name);
-            }
-            _ => println!("{}", r.to_string()),
-        }
+        println!("{}", r);
         Ok(r)
     }
 
-    fn eval_assign_expr(&mut self, expr_ast: &AssignExprAst) -> Result<IntermRepr, String> {
-        match expr_ast.lhs.as_ref() {
-            ExprAst::IdenAst(ident_expr_ast) => {
-                let res = self.evaluate(&expr_ast.rhs)?;
+    fn eval_assign_expr(&mut self, expr_ast: &AssignExprAst) -> Result<IntermRepr, RuntimeError> {
+        let res = self.evaluate(&expr_ast.rhs)?;
+        let id = &expr_ast.lhs;
+        self.assign_var(&id.val, id.slot, res.get_value())
+            .map_err(|msg| RuntimeError::new(msg, id.span))?;
+        Ok(res)
+    }
 
-                let _ = self.edit_existing_var(&ident_expr_ast.val, res.get_value().unwrap());
-                Ok(res)
-            }
-            _ => unimplemented!("Must be a variable!"),
-        }
-    }
-
-    fn eval_block_without_alloc(&mut self, expr_ast: &ExprAst) -> Result<IntermRepr, String> {
-        match expr_ast {
-            ExprAst::BlockAst(b) => {
-                let mut lr = IntermRepr::Nil;
-                for expr in &b.cont {
-                    lr = self.evaluate(&expr).unwrap();
-                    match &lr {
-                        IntermRepr::Ret(ref ret) => match ret.as_ref() {
-                            IntermRepr::Func(f) => {
-                                self.set_reg(RegisterX::Fn(f.clone()));
-                            }
-                            _ => break,
-                        },
-                        _ => continue,
-                    }
-                }
-                match lr {
-                    IntermRepr::Ret(_) => Ok(lr),
-                    _ => Ok(IntermRepr::Nil),
-                }
+    /// Runs a function body in the scope already opened for its arguments,
+    /// or a catch body in the one holding the error.
+    fn eval_block_without_alloc(&mut self, expr_ast: &ExprAst) -> Result<IntermRepr, RuntimeError> {
+        let ExprAst::BlockAst(b) = expr_ast else {
+            return self.statement(expr_ast);
+        };
+        for expr in &b.cont {
+            let r = self.statement(expr)?;
+            if r.is_jump() {
+                return Ok(r);
             }
-
-            _ => unimplemented!(),
         }
+        Ok(IntermRepr::Nil)
     }
 
-    fn eval_block_expr(&mut self, expr_ast: &BlockExprAst) -> Result<IntermRepr, String> {
-        let mut lr = IntermRepr::Nil;
+    fn eval_block_expr(&mut self, expr_ast: &BlockExprAst) -> Result<IntermRepr, RuntimeError> {
+        let mut lr = Ok(IntermRepr::Nil);
         self.allocate_memory_scope();
         for expr in &expr_ast.cont {
-            lr = self.evaluate(&expr).unwrap();
-            match &lr {
-                IntermRepr::Ret(ref ret) => match ret.as_ref() {
-                    IntermRepr::Func(f) => {
-                        self.set_reg(RegisterX::Fn(f.clone()));
-                    }
-                    _ => break,
-                },
-                _ => continue,
+            lr = self.statement(expr);
+            if lr.as_ref().map_or(true, IntermRepr::is_jump) {
+                break;
             }
         }
         self.deallocate_memory_scope();
-        match lr {
-            IntermRepr::Ret(_) => Ok(lr),
+        match lr? {
+            r if r.is_jump() => Ok(r),
             _ => Ok(IntermRepr::Nil),
         }
     }
 
-    fn eval_while_expr(&mut self, expr_ast: &WhileExprAst) -> Result<IntermRepr, String> {
-        while self
-            .evaluate_bool(&expr_ast.cond)
-            .unwrap()
-            .get_bool()
-            .unwrap()
-        {
-            if let Ok(r) = self.evaluate(&expr_ast.then) {
-                match r {
-                    IntermRepr::Ret(_) => {
-                        return Ok(r);
-                    }
-                    _ => (),
-                }
+    fn eval_while_expr(&mut self, expr_ast: &WhileExprAst) -> Result<IntermRepr, RuntimeError> {
+        while self.loop_pass(expr_ast.span, &expr_ast.cond)? {
+            match self.statement(&expr_ast.then)? {
+                r @ IntermRepr::Ret(_) => return Ok(r),
+                IntermRepr::Break => break,
+                _ => (),
             }
         }
         Ok(IntermRepr::Nil)
     }
 
-    fn eval_for_expr(&mut self, expr_ast: &ForExprAst) -> Result<IntermRepr, String> {
-        let _ = self.evaluate(&expr_ast.lhs);
-        while self
-            .evaluate_bool(&expr_ast.cond)
-            .unwrap()
-            .get_bool()
-            .unwrap()
-        {
-            let _ = self.evaluate(&expr_ast.then);
-            let _ = self.evaluate(&expr_ast.rhs);
+    /// Whether the loop at `span` makes another pass, going back to its line
+    /// for the debugger.
+    fn loop_pass(&mut self, span: Span, cond: &ExprAst) -> Result<bool, RuntimeError> {
+        if let Some(debugger) = &mut self.debugger {
+            debugger.goto(span.line);
         }
-        Ok(IntermRepr::Nil)
+        Ok(self.evaluate(cond)?.is_truthy())
     }
 
-    fn eval_fn_call_expr(&mut self, expr_ast: &FnCallExprAst) -> Result<IntermRepr, String> {
-        let interm = self.evaluate(&expr_ast.lhs)?;
-        let name = interm.expect_ident()?;
-        let prev_name = self.get_fn_name();
-        self.set_fn_name(name.clone());
-        match self.evaluate(&expr_ast.lhs).unwrap() {
-            IntermRepr::Func(ref mut func_obj) => {
-                let func = self.get_fn(&func_obj.borrow().ptr.name).unwrap();
-                let (args, body) = (func.args.clone(), func.body.clone());
-                if func.args.len() != expr_ast.args.len() {
-                    return Err(format!(
-                        "Expected {} arguments but got {}.",
-                        func.args.len(),
-                        expr_ast.args.len()
-                    ));
-                }
-                if func.builtin.is_some() {
-                    let builtin = func.builtin.unwrap();
-                    return Ok(builtin(func.args.clone()));
-                }
-                // self.allocate_args_memory();
-                let v: &Vec<IntermRepr> = &expr_ast
-                    .args
-                    .iter()
-                    .map(|expr| self.evaluate(&expr).unwrap())
-                    .collect();
-
-                let mut mem_args: HashMap<String, VarValue> = HashMap::new();
-                for (i, _) in expr_ast.args.iter().enumerate() {
-                    mem_args.insert(args[i].to_string(), v[i].get_value().unwrap());
-                }
-                if let Some(capture) = &func_obj.borrow().capture {
-                    mem_args.extend(capture.clone());
-                }
-                // self.merge_args_to_memory();
-                self.allocate_func_scope();
-
-                self.extend_memory(mem_args);
+    fn eval_for_expr(&mut self, expr_ast: &ForExprAst) -> Result<IntermRepr, RuntimeError> {
+        // The initializer lives in its own scope, wrapping the whole loop.
+        self.allocate_memory_scope();
+        let r = self.eval_for_loop(expr_ast);
+        self.deallocate_memory_scope();
+        r
+    }
 
-                let mut o: IntermRepr = self.eval_block_without_alloc(&body.unwrap()).unwrap();
+    fn eval_for_loop(&mut self, expr_ast: &ForExprAst) -> Result<IntermRepr, RuntimeError> {
+        self.evaluate(&expr_ast.lhs)?;
+        while self.loop_pass(expr_ast.span, &expr_ast.cond)? {
+            match self.statement(&expr_ast.then)? {
+                r @ IntermRepr::Ret(_) => return Ok(r),
+                IntermRepr::Break => break,
+                // `continue` still runs the increment.
+                _ => (),
+            }
+            self.evaluate(&expr_ast.rhs)?;
+        }
+        Ok(IntermRepr::Nil)
+    }
 
-                // update capture
-                let mut temp_capture: HashMap<String, VarValue> = HashMap::new();
-                if let Some(capture) = &func_obj.borrow().capture {
-                    for (k, _) in capture {
-                        let var = self.get_local_var(k).unwrap(); // should never fail
-                        temp_capture.insert(k.clone(), var.clone());
-                    }
-                }
-                if func_obj.borrow().capture.is_some() {
-                    for (k, v) in temp_capture {
-                        func_obj.borrow_mut().capture.as_mut().unwrap().insert(k, v);
-                    }
-                }
+    fn eval_throw_expr(&mut self, expr_ast: &ThrowExprAst) -> Result<IntermRepr, RuntimeError> {
+        let val = self.evaluate(&expr_ast.val)?;
+        let msg = val.to_string();
+        self.thrown = Some(val);
+        Err(RuntimeError::new(msg, expr_ast.span))
+    }
 
-                match o {
-                    IntermRepr::Ret(interm_repr) => o = *interm_repr,
-                    _ => (),
-                }
+    /// The value a `catch` clause gets for `err`: what was thrown, or the
+    /// error itself when the interpreter raised it.
+    fn take_thrown(&mut self, err: &RuntimeError) -> IntermRepr {
+        self.thrown
+            .take()
+            .unwrap_or_else(|| IntermRepr::Error(Rc::new(CaughtError::new(err))))
+    }
 
-                self.set_fn_name(prev_name);
-                self.deallocate_func_scope();
-                return Ok(o);
+    fn eval_try_expr(&mut self, expr_ast: &TryExprAst) -> Result<IntermRepr, RuntimeError> {
+        // A limit stops the program, neither clause runs then.
+        let tripped = |r: &Result<_, RuntimeError>| matches!(r, Err(err) if err.limit.is_some());
+        let mut r = self.evaluate(&expr_ast.body);
+        if tripped(&r) {
+            return r;
+        }
+        if let (Err(err), Some(catch)) = (&r, &expr_ast.catch) {
+            let val = self.take_thrown(err);
+            self.allocate_memory_scope();
+            let name = &catch.name;
+            self.define_var(name.val.clone(), name.slot, val.get_value());
+            r = self.eval_block_without_alloc(&catch.body);
+            self.deallocate_memory_scope();
+            if tripped(&r) {
+                return r;
             }
-            _ => unimplemented!(),
+        }
+        if let Some(ref finally) = expr_ast.finally {
+            // The error still propagating is raised again after the block,
+            // unless the block jumps out or fails itself.
+            let thrown = self.thrown.take();
+            let f = self.evaluate(finally)?;
+            if f.is_jump() {
+                return Ok(f);
+            }
+            self.thrown = thrown;
+        }
+        match r? {
+            r if r.is_jump() => Ok(r),
+            _ => Ok(IntermRepr::Nil),
         }
     }
 
-    fn eval_fn_decl_expr(&mut self, expr_ast: &FnDeclExprAst) -> Result<IntermRepr, String> {
-        if self.is_closure() {
-            self.push_closure(
-                expr_ast.name.clone(),
-                expr_ast.args.clone(),
-                *expr_ast.body.clone(),
-            );
-        } else {
-            self.push_func(
-                expr_ast.name.clone(),
-                expr_ast.args.clone(),
-                *expr_ast.body.clone(),
-            );
+    fn eval_fn_call_expr(&mut self, expr_ast: &FnCallExprAst) -> Result<IntermRepr, RuntimeError> {
+        let callee = self.evaluate(&expr_ast.lhs)?;
+        let args = expr_ast
+            .args
+            .iter()
+            .map(|expr| self.evaluate(expr))
+            .collect::<Result<_, _>>()?;
+        self.call(&callee, args, expr_ast.span)
+    }
+
+    /// Calls a function or a class with already evaluated arguments; `span`
+    /// is the call site.
+    pub fn call(
+        &mut self,
+        callee: &IntermRepr,
+        args: Vec<IntermRepr>,
+        span: Span,
+    ) -> Result<IntermRepr, RuntimeError> {
+        match callee {
+            IntermRepr::Func(func_obj) => self.call_function(func_obj, args, span),
+            IntermRepr::Class(class) => self.call_class(class, args, span),
+            _ => Err(RuntimeError::new(
+                "Can only call functions and classes.",
+                span,
+            )),
+        }
+    }
+
+    fn call_class(
+        &mut self,
+        class: &SharedClass,
+        args: Vec<IntermRepr>,
+        span: Span,
+    ) -> Result<IntermRepr, RuntimeError> {
+        self.allocate(span, 1)?;
+        let instance = self.instantiate(class);
+        if let Some((init, owner)) = find_method(class, "init") {
+            let init = bind(&owner, init, &instance);
+            self.call_function(&init, args, span)?;
+        } else if !args.is_empty() {
+            let msg = format!("Expected 0 arguments but got {}.", args.len());
+            return Err(RuntimeError::new(msg, span));
+        }
+        Ok(IntermRepr::Instance(instance))
+    }
+
+    fn call_function(
+        &mut self,
+        func_obj: &SharedFunctionObject,
+        args: Vec<IntermRepr>,
+        span: Span,
+    ) -> Result<IntermRepr, RuntimeError> {
+        let func = func_obj.borrow().ptr.clone();
+        func.arity()
+            .check(args.len())
+            .map_err(|msg| RuntimeError::new(msg, span))?;
+        if let Some(native) = &func.native {
+            return (native.fun)(&args).map_err(|msg| RuntimeError::new(msg, span));
+        }
+        let Some(body) = &func.body else {
+            return Ok(IntermRepr::Nil);
+        };
+        self.budget
+            .call(self.call_depth())
+            .map_err(|limit| RuntimeError::exceeded(limit, span))?;
+
+        let closure = func_obj.borrow().closure.clone();
+        let globals = func_obj.borrow().globals.clone();
+        self.allocate_func_scope(closure.clone(), globals);
+        for (i, arg) in args.iter().enumerate() {
+            let slot = Slot { depth: 0, index: i };
+            self.define_var(func.args[i].to_string(), Some(slot), arg.get_value());
+        }
+
+        if let Some(debugger) = &mut self.debugger {
+            debugger.enter(&func.name);
+        }
+        let o = self.eval_block_without_alloc(body);
+        if let Some(debugger) = &mut self.debugger {
+            debugger.leave();
         }
+        self.deallocate_func_scope();
+        let o = o.map_err(|err| err.unwind(&func.name, span))?;
+
+        if func.is_initializer {
+            // Initializers always hand back the instance bound to `this`.
+            let this = closure.and_then(|c| c.borrow().get(0));
+            return Ok(this.map_or(IntermRepr::Nil, |v| v.to_interm()));
+        }
+        match o {
+            IntermRepr::Ret(interm_repr) => Ok(*interm_repr),
+            o => Ok(o),
+        }
+    }
+
+    fn eval_class_decl_expr(
+        &mut self,
+        expr_ast: &ClassDeclExprAst,
+    ) -> Result<IntermRepr, RuntimeError> {
+        let span = match expr_ast.superclass.as_deref() {
+            Some(ExprAst::IdenAst(id)) => id.span,
+            Some(_) => Span::default(),
+            None => expr_ast.span,
+        };
+        let superclass = match expr_ast.superclass {
+            Some(ref superclass) => match self.evaluate(superclass)? {
+                IntermRepr::Class(c) => Some(c),
+                _ => return Err(RuntimeError::new("Superclass must be a class.", span)),
+            },
+            None => None,
+        };
+        self.allocate(span, 1)?;
+        let methods = expr_ast
+            .methods
+            .iter()
+            .map(|m| (m.name.clone(), m.args.clone(), *m.body.clone()))
+            .collect();
+        self.push_class(
+            expr_ast.name.clone(),
+            expr_ast.slot,
+            superclass,
+            methods,
+            expr_ast.span,
+        );
         Ok(IntermRepr::Nil)
     }
 
-    fn eval_ident_expr(&mut self, expr_ast: &IdentExprAst) -> Result<IntermRepr, String> {
-        let v = self.get_var(&expr_ast.val);
-        if v.is_some() {
-            Ok(v.unwrap().to_interm())
-        } else if let Some(f) = self.get_curr_fn() {
-            if let Some(capture) = &f.borrow().capture {
-                if let Some(v) = capture.get(&expr_ast.val) {
-                    return Ok(v.to_interm());
+    fn eval_get_expr(&mut self, expr_ast: &GetExprAst) -> Result<IntermRepr, RuntimeError> {
+        match self.evaluate(&expr_ast.object)? {
+            IntermRepr::Instance(ref instance) => get_property(instance, &expr_ast.name)
+                .map(|v| v.to_interm())
+                .ok_or_else(|| {
+                    let msg = format!("Undefined property '{}'.", expr_ast.name);
+                    RuntimeError::new(msg, expr_ast.span)
+                }),
+            IntermRepr::Module(ref module) => module
+                .get(&expr_ast.name)
+                .map(|v| v.to_interm())
+                .ok_or_else(|| {
+                    let msg = format!("Undefined property '{}'.", expr_ast.name);
+                    RuntimeError::new(msg, expr_ast.span)
+                }),
+            IntermRepr::Error(ref e) => match expr_ast.name.as_str() {
+                "message" => Ok(IntermRepr::Str(e.message.clone())),
+                "line" => Ok(IntermRepr::Num(e.line as f64)),
+                name => {
+                    let msg = format!("Undefined property '{}'.", name);
+                    Err(RuntimeError::new(msg, expr_ast.span))
                 }
-            }
-            eprintln!("var not found {}", expr_ast.val);
-            exit(70);
-        } else {
-            eprintln!("var not found {}", expr_ast.val);
-            exit(70);
+            },
+            _ => Err(RuntimeError::new(
+                "Only instances have properties.",
+                expr_ast.span,
+            )),
         }
     }
 
-    fn eval_ident_bool_expr(&self, expr_ast: &IdentExprAst) -> Result<IntermRepr, String> {
-        let v = self.get_var(&expr_ast.val).unwrap();
-        let r = match v.get_kind().unwrap() {
-            KindOfVal::Str(value) => value.is_empty(),
-            KindOfVal::Num(value) => {
-                let val: f64 = value.parse().unwrap();
-                val != 0.0
+    fn eval_set_expr(&mut self, expr_ast: &SetExprAst) -> Result<IntermRepr, RuntimeError> {
+        match self.evaluate(&expr_ast.object)? {
+            IntermRepr::Instance(ref instance) => {
+                let val = self.evaluate(&expr_ast.val)?;
+                instance
+                    .borrow_mut()
+                    .fields
+                    .insert(expr_ast.name.clone(), val.get_value());
+                Ok(val)
             }
-            KindOfVal::Bool(value) => value.clone(),
-            KindOfVal::Nil => false,
+            _ => Err(RuntimeError::new(
+                "Only instances have fields.",
+                expr_ast.span,
+            )),
+        }
+    }
+
+    fn eval_list_expr(&mut self, expr_ast: &ListExprAst) -> Result<IntermRepr, RuntimeError> {
+        let items: Vec<_> = expr_ast
+            .items
+            .iter()
+            .map(|item| Ok(self.evaluate(item)?.get_value()))
+            .collect::<Result<_, _>>()?;
+        self.allocate(expr_ast.span, items.len())?;
+        Ok(IntermRepr::List(List::new(items)))
+    }
+
+    fn eval_map_expr(&mut self, expr_ast: &MapExprAst) -> Result<IntermRepr, RuntimeError> {
+        let mut pairs = Vec::new();
+        for (key, val) in &expr_ast.entries {
+            pairs.push((self.evaluate(key)?, self.evaluate(val)?));
+        }
+        // Keys are checked once everything is evaluated, like the VM does.
+        let mut entries = OrderedMap::new();
+        for (key, val) in pairs {
+            let key = map::key(&key).map_err(|msg| RuntimeError::new(msg, expr_ast.span))?;
+            entries.insert(key, val.get_value());
+        }
+        self.allocate(expr_ast.span, entries.len())?;
+        Ok(IntermRepr::Map(Map::new(entries)))
+    }
+
+    fn eval_get_index_expr(&mut self, expr_ast: &IndexExprAst) -> Result<IntermRepr, RuntimeError> {
+        let object = self.evaluate(&expr_ast.object)?;
+        let index = self.evaluate(&expr_ast.index)?;
+        let err = |msg| RuntimeError::new(msg, expr_ast.span);
+        let val = match element(object, index).map_err(err)? {
+            Element::List(list, i) => list.borrow().items[i].to_interm(),
+            Element::Map(map, key) => match map.borrow().entries.get(&key) {
+                Some(val) => val.to_interm(),
+                None => return Err(err(format!("Undefined key '{}'.", key))),
+            },
         };
-        Ok(IntermRepr::Bool(r))
+        Ok(val)
+    }
+
+    fn eval_set_index_expr(
+        &mut self,
+        expr_ast: &SetIndexExprAst,
+    ) -> Result<IntermRepr, RuntimeError> {
+        let object = self.evaluate(&expr_ast.object)?;
+        let index = self.evaluate(&expr_ast.index)?;
+        let val = self.evaluate(&expr_ast.val)?;
+        match element(object, index).map_err(|msg| RuntimeError::new(msg, expr_ast.span))? {
+            Element::List(list, i) => list.borrow_mut().items[i] = val.get_value(),
+            Element::Map(map, key) => map.borrow_mut().entries.insert(key, val.get_value()),
+        }
+        Ok(val)
+    }
+
+    fn eval_super_expr(&mut self, expr_ast: &SuperExprAst) -> Result<IntermRepr, RuntimeError> {
+        // `this` is bound in the scope right inside the one holding `super`.
+        let this_slot = expr_ast.slot.map(|s| Slot {
+            depth: s.depth - 1,
+            index: 0,
+        });
+        let this = self.get_var("this", this_slot);
+        let (Some(VarValue::Class(superclass)), Some(VarValue::Instance(instance))) =
+            (self.get_var("super", expr_ast.slot), &this)
+        else {
+            let msg = if this.is_some() {
+                "Can't use 'super' in a class with no superclass."
+            } else {
+                "Can't use 'super' outside of a class."
+            };
+            return Err(RuntimeError::new(msg, expr_ast.span));
+        };
+        find_method(&superclass, &expr_ast.method)
+            .map(|(method, owner)| IntermRepr::Func(bind(&owner, method, instance)))
+            .ok_or_else(|| {
+                let msg = format!("Undefined property '{}'.", expr_ast.method);
+                RuntimeError::new(msg, expr_ast.span)
+            })
+    }
+
+    fn eval_this_expr(&mut self, expr_ast: &IdentExprAst) -> Result<IntermRepr, RuntimeError> {
+        self.get_var("this", expr_ast.slot)
+            .map(|v| v.to_interm())
+            .ok_or_else(|| RuntimeError::new("Can't use 'this' outside of a class.", expr_ast.span))
+    }
+
+    fn eval_fn_decl_expr(&mut self, expr_ast: &FnDeclExprAst) -> Result<IntermRepr, RuntimeError> {
+        self.allocate(expr_ast.span, 1)?;
+        self.push_func(
+            expr_ast.name.clone(),
+            expr_ast.slot,
+            expr_ast.args.clone(),
+            *expr_ast.body.clone(),
+        );
+        Ok(IntermRepr::Nil)
+    }
+
+    fn eval_lambda_expr(&mut self, expr_ast: &FnDeclExprAst) -> Result<IntermRepr, RuntimeError> {
+        self.allocate(expr_ast.span, 1)?;
+        let f = self.new_function(
+            expr_ast.name.clone(),
+            expr_ast.args.clone(),
+            *expr_ast.body.clone(),
+        );
+        Ok(IntermRepr::Func(f))
     }
 
-    fn eval_bool_str_expr(&self, expr_ast: &StringExprAst) -> Result<IntermRepr, String> {
-        let o = !expr_ast.val.is_empty();
-        Ok(IntermRepr::Bool(o))
+    fn eval_ident_expr(&mut self, expr_ast: &IdentExprAst) -> Result<IntermRepr, RuntimeError> {
+        self.get_var(&expr_ast.val, expr_ast.slot)
+            .map(|v| v.to_interm())
+            .ok_or_else(|| {
+                let msg = format!("Undefined variable '{}'.", expr_ast.val);
+                RuntimeError::new(msg, expr_ast.span)
+            })
     }
-    fn eval_str_expr(&self, expr_ast: &StringExprAst) -> Result<IntermRepr, String> {
+
+    fn eval_str_expr(&self, expr_ast: &StringExprAst) -> Result<IntermRepr, RuntimeError> {
         Ok(IntermRepr::Str(expr_ast.val.clone()))
     }
 
-    pub fn evaluate_bool(&mut self, expr_ast: &ExprAst) -> Result<IntermRepr, String> {
-        match &expr_ast {
-            ExprAst::BoolAst(b) => self.eval_bool(b),
-            ExprAst::NilAst => Ok(IntermRepr::Bool(false)),
-            ExprAst::NumAst(n) => self.eval_number(n),
-            ExprAst::StrAst(s) => self.eval_bool_str_expr(s),
-            ExprAst::BinaryAst(ref b) => self.eval_bin_expr(b),
-            ExprAst::IdenAst(ref id) => self.eval_ident_bool_expr(id),
-            ExprAst::AssignAst(ref a) => self.eval_assign_expr(a),
-            ExprAst::UnaryAst(ref u) => self.eval_unary_expr(u),
-            ExprAst::FnCallAst(f) => self.eval_fn_call_expr(f),
-            ExprAst::ParAst(p) => self.evaluate_bool(&p.val),
-            _ => unimplemented!("{:?}", &expr_ast),
+    /// Evaluates a statement, after the debugger had a chance to stop there.
+    pub fn statement(&mut self, stmt: &ExprAst) -> Result<IntermRepr, RuntimeError> {
+        if let Some(mut debugger) = self.debugger.take() {
+            debugger.statement(self, stmt.span().line);
+            self.debugger = Some(debugger);
         }
+        self.evaluate(stmt)
     }
-    pub fn evaluate(&mut self, expr_astr: &ExprAst) -> Result<IntermRepr, String> {
+
+    pub fn evaluate(&mut self, expr_astr: &ExprAst) -> Result<IntermRepr, RuntimeError> {
+        self.budget
+            .step()
+            .map_err(|limit| RuntimeError::exceeded(limit, expr_astr.span()))?;
         match &expr_astr {
             ExprAst::BoolAst(b) => self.eval_bool(b),
             ExprAst::NumAst(n) => self.eval_number(n),
             ExprAst::NegativeAst(ne) => self.eval_negative_number(ne),
             ExprAst::StrAst(s) => self.eval_str_expr(s),
             ExprAst::OpAst(o) => Ok(IntermRepr::Op(o.val.to_usefull_str())),
-            ExprAst::NilAst => Ok(IntermRepr::Nil),
+            ExprAst::NilAst(_) => Ok(IntermRepr::Nil),
             ExprAst::BinaryAst(b) => self.eval_bin_expr(b),
             ExprAst::UnaryAst(s) => self.eval_unary_expr(s),
             ExprAst::IdenAst(s) => self.eval_ident_expr(s),
             ExprAst::IfAst(fi) => self.eval_if_expr(fi),
             ExprAst::PrintAst(p) => self.eval_print_expr(p),
             ExprAst::AssignAst(a) => self.eval_assign_expr(a),
-            ExprAst::VarAssignAst(v) => self.eval_var_assign(&v),
+            ExprAst::VarAssignAst(v) => self.eval_var_assign(v),
             ExprAst::BlockAst(b) => self.eval_block_expr(b),
             ExprAst::WhileAst(e) => self.eval_while_expr(e),
             ExprAst::ForAst(f) => self.eval_for_expr(f),
             ExprAst::FnCallAst(f) => self.eval_fn_call_expr(f),
             ExprAst::FnDeclAst(f) => self.eval_fn_decl_expr(f),
+            ExprAst::LambdaAst(f) => self.eval_lambda_expr(f),
             ExprAst::ParAst(p) => self.evaluate(&p.val),
+            ExprAst::ClassDeclAst(c) => self.eval_class_decl_expr(c),
+            ExprAst::GetAst(g) => self.eval_get_expr(g),
+            ExprAst::SetAst(st) => self.eval_set_expr(st),
+            ExprAst::ThisAst(th) => self.eval_this_expr(th),
+            ExprAst::SuperAst(su) => self.eval_super_expr(su),
+            ExprAst::ListAst(l) => self.eval_list_expr(l),
+            ExprAst::MapAst(m) => self.eval_map_expr(m),
+            ExprAst::ImportAst(i) => self.eval_import(i),
+            ExprAst::ThrowAst(t) => self.eval_throw_expr(t),
+            ExprAst::TryAst(t) => self.eval_try_expr(t),
+            ExprAst::IndexAst(i) => self.eval_get_index_expr(i),
+            ExprAst::SetIndexAst(s) => self.eval_set_index_expr(s),
+            ExprAst::BreakAst(_) => Ok(IntermRepr::Break),
+            ExprAst::ContinueAst(_) => Ok(IntermRepr::Continue),
             ExprAst::RetAst(ref r) => {
-                let ir = self.evaluate(&r.val).unwrap();
+                let ir = self.evaluate(&r.val)?;
                 Ok(IntermRepr::Ret(Box::new(ir)))
             }
         }
     }
 }
+
+impl Backend for Interpreter {
+    fn execute(&mut self, stmt: &ExprAst) -> Result<String, RuntimeError> {
+        self.statement(stmt).map(|r| r.to_string()).map_err(|err| {
+            self.clear_thrown();
+            err.in_script()
+        })
+    }
+
+    fn globals(&self) -> Vec<(String, String)> {
+        Interpreter::globals(self)
+    }
+
+    fn set_script(&mut self, path: &Path) {
+        self.modules.set_script(path);
+    }
+
+    fn set_limits(&mut self, limits: Limits) {
+        Interpreter::set_limits(self, limits);
+    }
+}
@@ -1,24 +1,35 @@
 use std::cell::RefCell;
-use std::collections::HashMap;
+use std::fmt;
 use std::rc::Rc;
-use std::sync::Arc;
 use std::time::SystemTime;
 use std::time::UNIX_EPOCH;
 
 use super::super::evaluate::*;
-use crate::interpreter::core::VarValue;
+use crate::backend::Arity;
+use crate::interpreter::core::{SharedGlobals, SharedVarMemory};
+use crate::interpreter::list::list_natives;
+use crate::interpreter::map::map_natives;
 use crate::parser::parser_ds::ExprAst;
 
-fn clock(_: Vec<ExprAst>) -> IntermRepr {
+fn clock(_: &[IntermRepr]) -> Result<IntermRepr, String> {
     let now = SystemTime::now();
-    match now.duration_since(UNIX_EPOCH) {
-        Ok(duration) => {
-            let seconds = duration.as_secs();
-            IntermRepr::Num(seconds.to_string())
-        }
-        Err(_) => {
-            todo!()
-        }
+    let seconds = now.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+    Ok(IntermRepr::Num(seconds as f64))
+}
+
+pub type NativeFn = Rc<dyn Fn(&[IntermRepr]) -> Result<IntermRepr, String>>;
+
+/// A function implemented in Rust. It gets the evaluated arguments, and an
+/// `Err` becomes a runtime error at the call site.
+#[derive(Clone)]
+pub struct Native {
+    pub arity: Arity,
+    pub fun: NativeFn,
+}
+
+impl fmt::Debug for Native {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Native {{ arity : {:?} }}", self.arity)
     }
 }
 
@@ -29,26 +40,50 @@ pub struct Function {
     pub args: Vec<ExprAst>,
     ret: IntermRepr,
     pub body: Option<ExprAst>,
-    pub builtin: Option<fn(Vec<ExprAst>) -> IntermRepr>,
+    pub native: Option<Native>,
+    pub is_initializer: bool,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+/// A function value: the declaration plus the scope it closes over, shared
+/// with every other closure created in that scope.
+#[derive(Clone)]
 pub struct FunctionObject {
-    pub ptr: Arc<Function>,
-    pub capture: Option<HashMap<String, VarValue>>,
+    pub ptr: Rc<Function>,
+    pub closure: Option<SharedVarMemory>,
+    /// Globals of the module declaring the function, `None` for natives.
+    pub globals: Option<SharedGlobals>,
 }
 
 pub type SharedFunctionObject = Rc<RefCell<FunctionObject>>;
 
 impl FunctionObject {
-    pub fn new(fun: Arc<Function>, capture: Option<HashMap<String, VarValue>>) -> Self {
-        Self { ptr: fun, capture }
+    pub fn new(
+        fun: Rc<Function>,
+        closure: Option<SharedVarMemory>,
+        globals: Option<SharedGlobals>,
+    ) -> Self {
+        Self {
+            ptr: fun,
+            closure,
+            globals,
+        }
     }
 }
 
-impl PartialEq for Function {
+impl PartialEq for FunctionObject {
     fn eq(&self, other: &Self) -> bool {
-        self.name == other.name
+        let same_closure = match (&self.closure, &other.closure) {
+            (Some(l), Some(r)) => Rc::ptr_eq(l, r),
+            (None, None) => true,
+            _ => false,
+        };
+        Rc::ptr_eq(&self.ptr, &other.ptr) && same_closure
+    }
+}
+
+impl fmt::Debug for FunctionObject {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "FunctionObject {{ name : {} }}", self.ptr.name)
     }
 }
 
@@ -58,22 +93,46 @@ impl Function {
             args,
             ret: IntermRepr::Nil,
             body: Some(body),
-            builtin: None,
+            native: None,
+            is_initializer: false,
             name,
         }
     }
 
-    fn new_builtin(builtin: fn(Vec<ExprAst>) -> IntermRepr, name: String) -> Self {
+    pub fn new_method(args: Vec<ExprAst>, body: ExprAst, name: String) -> Self {
+        Self {
+            is_initializer: name == "init",
+            ..Self::new(args, body, name)
+        }
+    }
+
+    pub fn new_native(native: Native, name: String) -> Self {
         Self {
             args: vec![],
             ret: IntermRepr::Nil,
             body: None,
-            builtin: Some(builtin),
-            name: name,
+            native: Some(native),
+            is_initializer: false,
+            name,
+        }
+    }
+
+    pub fn arity(&self) -> Arity {
+        match &self.native {
+            Some(native) => native.arity,
+            None => Arity::Fixed(self.args.len()),
         }
     }
 }
 
-pub fn get_fn_builtin() -> Vec<Arc<Function>> {
-    vec![Arc::new(Function::new_builtin(clock, "clock".to_string()))]
+pub fn get_fn_builtin() -> Vec<Rc<Function>> {
+    let clock = Native {
+        arity: Arity::Fixed(0),
+        fun: Rc::new(clock),
+    };
+    let mut natives = vec![Rc::new(Function::new_native(clock, "clock".to_string()))];
+    for (name, native) in list_natives().into_iter().chain(map_natives()) {
+        natives.push(Rc::new(Function::new_native(native, name.to_string())));
+    }
+    natives
 }
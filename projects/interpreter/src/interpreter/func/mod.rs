@@ -1 +1 @@
-pub mod builtin;
\ No newline at end of file
+pub mod builtin;
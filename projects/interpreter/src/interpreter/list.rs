@@ -0,0 +1,125 @@
+use std::cell::RefCell;
+use std::fmt;
+use std::rc::Rc;
+
+use super::core::{interm_to_var_val, VarValue};
+use super::evaluate::IntermRepr;
+use super::func::builtin::Native;
+use crate::backend::{list_index, Arity};
+
+/// A list value. Lists are shared by reference, so every variable and closure
+/// holding one sees the changes made through the others.
+pub struct List {
+    pub items: Vec<VarValue>,
+}
+
+pub type SharedList = Rc<RefCell<List>>;
+
+impl List {
+    pub fn new(items: Vec<VarValue>) -> SharedList {
+        Rc::new(RefCell::new(Self { items }))
+    }
+}
+
+impl PartialEq for List {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
+
+impl fmt::Debug for List {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "List {{ len : {} }}", self.items.len())
+    }
+}
+
+impl fmt::Display for List {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let items: Vec<String> = self
+            .items
+            .iter()
+            .map(|v| v.to_interm().to_string())
+            .collect();
+        write!(f, "[{}]", items.join(", "))
+    }
+}
+
+fn list(v: &IntermRepr) -> Result<&SharedList, String> {
+    match v {
+        IntermRepr::List(l) => Ok(l),
+        _ => Err("Expected a list.".to_string()),
+    }
+}
+
+fn number(v: &IntermRepr) -> Result<f64, String> {
+    match v {
+        IntermRepr::Num(n) => Ok(*n),
+        _ => Err("List index must be a number.".to_string()),
+    }
+}
+
+fn len(args: &[IntermRepr]) -> Result<IntermRepr, String> {
+    match &args[0] {
+        IntermRepr::List(l) => Ok(IntermRepr::Num(l.borrow().items.len() as f64)),
+        IntermRepr::Map(m) => Ok(IntermRepr::Num(m.borrow().entries.len() as f64)),
+        IntermRepr::Str(s) => Ok(IntermRepr::Num(s.chars().count() as f64)),
+        _ => Err("Expected a list, a map or a string.".to_string()),
+    }
+}
+
+fn push(args: &[IntermRepr]) -> Result<IntermRepr, String> {
+    let val = interm_to_var_val(&args[1]);
+    list(&args[0])?.borrow_mut().items.push(val);
+    Ok(IntermRepr::Nil)
+}
+
+fn pop(args: &[IntermRepr]) -> Result<IntermRepr, String> {
+    let val = list(&args[0])?.borrow_mut().items.pop();
+    val.map(|v| v.to_interm())
+        .ok_or_else(|| "Can't pop from an empty list.".to_string())
+}
+
+fn insert(args: &[IntermRepr]) -> Result<IntermRepr, String> {
+    let mut list = list(&args[0])?.borrow_mut();
+    // Inserting right after the last element is allowed.
+    let i = list_index(number(&args[1])?, list.items.len() + 1)?;
+    list.items.insert(i, interm_to_var_val(&args[2]));
+    Ok(IntermRepr::Nil)
+}
+
+fn remove(args: &[IntermRepr]) -> Result<IntermRepr, String> {
+    let mut list = list(&args[0])?.borrow_mut();
+    let i = list_index(number(&args[1])?, list.items.len())?;
+    Ok(list.items.remove(i).to_interm())
+}
+
+fn slice(args: &[IntermRepr]) -> Result<IntermRepr, String> {
+    let list = list(&args[0])?.borrow();
+    let len = list.items.len();
+    let start = list_index(number(&args[1])?, len + 1)?;
+    let end = list_index(number(&args[2])?, len + 1)?;
+    let items = list
+        .items
+        .get(start..end)
+        .ok_or("List index out of range.")?;
+    Ok(IntermRepr::List(List::new(items.to_vec())))
+}
+
+fn native(arity: usize, fun: fn(&[IntermRepr]) -> Result<IntermRepr, String>) -> Native {
+    Native {
+        arity: Arity::Fixed(arity),
+        fun: Rc::new(fun),
+    }
+}
+
+/// The natives working on lists, with their names.
+pub fn list_natives() -> Vec<(&'static str, Native)> {
+    vec![
+        ("len", native(1, len)),
+        ("push", native(2, push)),
+        ("pop", native(1, pop)),
+        ("insert", native(3, insert)),
+        ("remove", native(2, remove)),
+        ("slice", native(3, slice)),
+    ]
+}
@@ -0,0 +1,114 @@
+use std::cell::RefCell;
+use std::fmt;
+use std::rc::Rc;
+
+use super::core::{interm_to_var_val, VarValue};
+use super::evaluate::IntermRepr;
+use super::func::builtin::Native;
+use super::list::List;
+use crate::backend::Arity;
+use crate::map::{MapKey, OrderedMap, KEY_ERROR};
+
+/// A map value, shared by reference like lists. Entries iterate in
+/// insertion order.
+pub struct Map {
+    pub entries: OrderedMap<VarValue>,
+}
+
+pub type SharedMap = Rc<RefCell<Map>>;
+
+impl Map {
+    pub fn new(entries: OrderedMap<VarValue>) -> SharedMap {
+        Rc::new(RefCell::new(Self { entries }))
+    }
+}
+
+impl PartialEq for Map {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
+
+impl fmt::Debug for Map {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Map {{ len : {} }}", self.entries.len())
+    }
+}
+
+impl fmt::Display for Map {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let entries: Vec<String> = self
+            .entries
+            .iter()
+            .map(|(k, v)| format!("{}: {}", k, v.to_interm()))
+            .collect();
+        write!(f, "{{{}}}", entries.join(", "))
+    }
+}
+
+pub fn key(v: &IntermRepr) -> Result<MapKey, String> {
+    match v {
+        IntermRepr::Bool(b) => Ok(MapKey::Bool(*b)),
+        IntermRepr::Num(n) => Ok(MapKey::num(*n)),
+        IntermRepr::Str(s) => Ok(MapKey::Str(s.clone())),
+        _ => Err(KEY_ERROR.to_string()),
+    }
+}
+
+fn key_value(key: &MapKey) -> VarValue {
+    let r = match key {
+        MapKey::Bool(b) => IntermRepr::Bool(*b),
+        MapKey::Num(n) => IntermRepr::Num(f64::from_bits(*n)),
+        MapKey::Str(s) => IntermRepr::Str(s.clone()),
+    };
+    interm_to_var_val(&r)
+}
+
+fn map(v: &IntermRepr) -> Result<&SharedMap, String> {
+    match v {
+        IntermRepr::Map(m) => Ok(m),
+        _ => Err("Expected a map.".to_string()),
+    }
+}
+
+fn keys(args: &[IntermRepr]) -> Result<IntermRepr, String> {
+    let map = map(&args[0])?.borrow();
+    let keys = map.entries.iter().map(|(k, _)| key_value(k)).collect();
+    Ok(IntermRepr::List(List::new(keys)))
+}
+
+fn values(args: &[IntermRepr]) -> Result<IntermRepr, String> {
+    let map = map(&args[0])?.borrow();
+    let values = map.entries.iter().map(|(_, v)| v.clone()).collect();
+    Ok(IntermRepr::List(List::new(values)))
+}
+
+fn has(args: &[IntermRepr]) -> Result<IntermRepr, String> {
+    let key = key(&args[1])?;
+    let found = map(&args[0])?.borrow().entries.contains_key(&key);
+    Ok(IntermRepr::Bool(found))
+}
+
+/// Returns whether the key was there.
+fn delete(args: &[IntermRepr]) -> Result<IntermRepr, String> {
+    let key = key(&args[1])?;
+    let removed = map(&args[0])?.borrow_mut().entries.remove(&key);
+    Ok(IntermRepr::Bool(removed.is_some()))
+}
+
+fn native(arity: usize, fun: fn(&[IntermRepr]) -> Result<IntermRepr, String>) -> Native {
+    Native {
+        arity: Arity::Fixed(arity),
+        fun: Rc::new(fun),
+    }
+}
+
+/// The natives working on maps, with their names.
+pub fn map_natives() -> Vec<(&'static str, Native)> {
+    vec![
+        ("keys", native(1, keys)),
+        ("values", native(1, values)),
+        ("has", native(2, has)),
+        ("delete", native(2, delete)),
+    ]
+}
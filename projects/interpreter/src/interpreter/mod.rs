@@ -1,3 +1,8 @@
+pub mod class;
+pub mod core;
+pub mod debug;
 pub mod evaluate;
 pub mod func;
-pub mod core;
\ No newline at end of file
+pub mod list;
+pub mod map;
+pub mod module;
@@ -0,0 +1,90 @@
+use std::fmt;
+use std::rc::Rc;
+
+use super::core::{builtin_globals, Interpreter, SharedGlobals, VarValue};
+use super::evaluate::IntermRepr;
+use crate::error::RuntimeError;
+use crate::lexer::Span;
+use crate::module::Import;
+use crate::parser::parser_ds::ImportExprAst;
+
+/// An imported module, giving access to its globals as properties.
+pub struct Module {
+    pub name: String,
+    pub globals: SharedGlobals,
+}
+
+pub type SharedModule = Rc<Module>;
+
+impl Module {
+    pub fn get(&self, name: &str) -> Option<VarValue> {
+        self.globals.borrow().get(name).cloned()
+    }
+}
+
+impl PartialEq for Module {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
+
+impl fmt::Debug for Module {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Module {{ name : {} }}", self.name)
+    }
+}
+
+fn is_native(val: &VarValue) -> bool {
+    matches!(val, VarValue::Func(f) if f.borrow().ptr.native.is_some())
+}
+
+impl Interpreter {
+    pub(super) fn eval_import(
+        &mut self,
+        expr_ast: &ImportExprAst,
+    ) -> Result<IntermRepr, RuntimeError> {
+        let module = self.import(&expr_ast.path, expr_ast.span)?;
+        match &expr_ast.name {
+            Some(name) => self.define_var(name.clone(), None, VarValue::Module(module)),
+            None => {
+                // Natives are already there, everything else the module defined
+                // is copied.
+                let globals = self.current_globals();
+                for (name, val) in module.globals.borrow().iter() {
+                    if !is_native(val) {
+                        globals.borrow_mut().insert(name.clone(), val.clone());
+                    }
+                }
+            }
+        }
+        Ok(IntermRepr::Nil)
+    }
+
+    /// Runs the module at `path` the first time it is imported.
+    fn import(&mut self, path: &str, span: Span) -> Result<SharedModule, RuntimeError> {
+        let stmts = match self.modules.start(path) {
+            Ok(Import::Loaded(module)) => return Ok(module),
+            Ok(Import::Run(stmts)) => stmts,
+            Err(msg) => return Err(RuntimeError::new(msg, span)),
+        };
+        let module = Rc::new(Module {
+            name: path.to_string(),
+            globals: builtin_globals(),
+        });
+        self.allocate_module_scope(module.globals.clone());
+        let r = stmts
+            .iter()
+            .try_for_each(|stmt| self.evaluate(stmt).map(|_| ()));
+        self.deallocate_func_scope();
+        match r {
+            Ok(()) => {
+                self.modules.finish(module.clone());
+                Ok(module)
+            }
+            Err(err) => {
+                self.modules.abort();
+                Err(err.unwind(&format!("module {}", path), span))
+            }
+        }
+    }
+}
@@ -1,12 +1,68 @@
+use crate::error::LexError;
+use std::fmt;
+
+/// A range of the source as byte offsets, `end` excluded, along with the
+/// line and column it starts at. Lines and columns are 1-based.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Span {
+    /// The span going from the start of `self` to the end of `other`.
+    pub fn to(self, other: Span) -> Span {
+        Span {
+            end: other.end.max(self.end),
+            ..self
+        }
+    }
+}
+
+/// A number literal as `tokenize` and `parse` print it, from its `lexeme`
+/// and `value`: integers as written, others as their value, with a decimal
+/// point either way.
+pub fn number_literal(lexeme: &str, value: f64) -> String {
+    let s = if lexeme.contains('.') {
+        value.to_string()
+    } else {
+        lexeme.to_string()
+    };
+    if s.contains('.') {
+        s
+    } else {
+        format!("{}.0", s)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpannedToken {
+    pub token: Token,
+    pub span: Span,
+}
+
+/// A `//` comment, kept out of the tokens since only the formatter needs
+/// it. `text` starts with the slashes and stops before the end of the line.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Comment {
+    pub text: String,
+    pub span: Span,
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum Token {
     LeftParen,
     RightParen,
     RightBraces,
     LeftBraces,
+    LeftBracket,
+    RightBracket,
     Star,
     Dot,
     Comma,
+    Colon,
     Plus,
     Minus,
     Equal,
@@ -18,15 +74,23 @@ pub enum Token {
     LessEqual,
     Greater,
     GreaterEqual,
+    Arrow,
     Slash,
-    Tab,
-    LoxString(String),
-    Number(String),
+    /// The text between the quotes as written, and the string with its
+    /// escape sequences decoded.
+    LoxString(String, String),
+    /// The lexeme as written, and its value.
+    Number(String, f64),
     Identifier(String),
     And,
+    Break,
+    Catch,
     Class,
+    Import,
+    Continue,
     Else,
     False,
+    Finally,
     For,
     Fun,
     If,
@@ -36,20 +100,94 @@ pub enum Token {
     Return,
     Super,
     This,
+    Throw,
     True,
+    Try,
     Var,
     While,
     Eof,
 }
 
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self {
+            Self::Number(lexeme, value) => {
+                write!(f, "NUMBER {} {}", lexeme, number_literal(lexeme, *value))
+            }
+            Self::LoxString(lexeme, s) => write!(f, "STRING \"{}\" {}", lexeme, s),
+            Self::Eof => f.write_str("EOF  null"),
+            token => write!(f, "{} {} null", token.name(), token.to_usefull_str()),
+        }
+    }
+}
+
 impl Token {
+    /// The type of the token, as `tokenize` prints it.
+    pub fn name(&self) -> &'static str {
+        match &self {
+            Self::LeftParen => "LEFT_PAREN",
+            Self::RightParen => "RIGHT_PAREN",
+            Self::RightBraces => "RIGHT_BRACE",
+            Self::LeftBraces => "LEFT_BRACE",
+            Self::LeftBracket => "LEFT_BRACKET",
+            Self::RightBracket => "RIGHT_BRACKET",
+            Self::Comma => "COMMA",
+            Self::Colon => "COLON",
+            Self::Plus => "PLUS",
+            Self::Minus => "MINUS",
+            Self::Star => "STAR",
+            Self::Dot => "DOT",
+            Self::Equal => "EQUAL",
+            Self::Bang => "BANG",
+            Self::BangEqual => "BANG_EQUAL",
+            Self::DoubleEqual => "EQUAL_EQUAL",
+            Self::Semicolon => "SEMICOLON",
+            Self::Less => "LESS",
+            Self::LessEqual => "LESS_EQUAL",
+            Self::Greater => "GREATER",
+            Self::GreaterEqual => "GREATER_EQUAL",
+            Self::Arrow => "ARROW",
+            Self::Slash => "SLASH",
+            Self::And => "AND",
+            Self::Break => "BREAK",
+            Self::Catch => "CATCH",
+            Self::Class => "CLASS",
+            Self::Import => "IMPORT",
+            Self::Continue => "CONTINUE",
+            Self::Else => "ELSE",
+            Self::False => "FALSE",
+            Self::Finally => "FINALLY",
+            Self::For => "FOR",
+            Self::Fun => "FUN",
+            Self::If => "IF",
+            Self::Nil => "NIL",
+            Self::Or => "OR",
+            Self::Print => "PRINT",
+            Self::Return => "RETURN",
+            Self::Super => "SUPER",
+            Self::This => "THIS",
+            Self::Throw => "THROW",
+            Self::True => "TRUE",
+            Self::Try => "TRY",
+            Self::Var => "VAR",
+            Self::While => "WHILE",
+            Self::Identifier(_) => "IDENTIFIER",
+            Self::Number(..) => "NUMBER",
+            Self::LoxString(..) => "STRING",
+            Self::Eof => "EOF",
+        }
+    }
+
     pub fn to_usefull_str(&self) -> String {
         match &self {
             Self::LeftParen => "(".to_string(),
             Self::RightParen => ")".to_string(),
             Self::RightBraces => "}".to_string(),
             Self::LeftBraces => "{".to_string(),
+            Self::LeftBracket => "[".to_string(),
+            Self::RightBracket => "]".to_string(),
             Self::Comma => ",".to_string(),
+            Self::Colon => ":".to_string(),
             Self::Plus => "+".to_string(),
             Self::Minus => "-".to_string(),
             Self::Star => "*".to_string(),
@@ -63,26 +201,19 @@ impl Token {
             Self::LessEqual => "<=".to_string(),
             Self::Greater => ">".to_string(),
             Self::GreaterEqual => ">=".to_string(),
+            Self::Arrow => "=>".to_string(),
             Self::Slash => "/".to_string(),
-            Self::Tab => "".to_string(),
-            Self::Identifier(id) => format!("{}", id),
-            Self::Number(s) => {
-                if s.contains('.') {
-                    let f: f64 = s.parse().expect("can't parse float");
-                    let parsed = f.to_string();
-                    if parsed.contains('.') {
-                        format!("{} {}", s, parsed)
-                    } else {
-                        format!("{} {}.0", s, parsed)
-                    }
-                } else {
-                    format!("{} {}.0", s, s)
-                }
-            }
+            Self::Identifier(id) => id.to_string(),
+            Self::Number(lexeme, _) => lexeme.clone(),
             Self::And => "and".to_string(),
+            Self::Break => "break".to_string(),
+            Self::Catch => "catch".to_string(),
             Self::Class => "class".to_string(),
+            Self::Import => "import".to_string(),
+            Self::Continue => "continue".to_string(),
             Self::Else => "else".to_string(),
             Self::False => "false".to_string(),
+            Self::Finally => "finally".to_string(),
             Self::For => "for".to_string(),
             Self::Fun => "fun".to_string(),
             Self::If => "if".to_string(),
@@ -92,77 +223,28 @@ impl Token {
             Self::Return => "return".to_string(),
             Self::Super => "super".to_string(),
             Self::This => "this".to_string(),
+            Self::Throw => "throw".to_string(),
             Self::True => "true".to_string(),
+            Self::Try => "try".to_string(),
             Self::Var => "var".to_string(),
             Self::While => "while".to_string(),
-            Self::LoxString(s) => format!("\"{}\" {}", s, s),
+            Self::LoxString(lexeme, s) => format!("\"{}\" {}", lexeme, s),
             Self::Eof => "EOF".to_string(),
         }
     }
 
-    pub fn to_string(&self) -> String {
-        match &self {
-            Self::LeftParen => "LEFT_PAREN ( null".to_string(),
-            Self::RightParen => "RIGHT_PAREN ) null".to_string(),
-            Self::RightBraces => "RIGHT_BRACE } null".to_string(),
-            Self::LeftBraces => "LEFT_BRACE { null".to_string(),
-            Self::Comma => "COMMA , null".to_string(),
-            Self::Plus => "PLUS + null".to_string(),
-            Self::Minus => "MINUS - null".to_string(),
-            Self::Star => "STAR * null".to_string(),
-            Self::Dot => "DOT . null".to_string(),
-            Self::Equal => "EQUAL = null".to_string(),
-            Self::Bang => "BANG ! null".to_string(),
-            Self::BangEqual => "BANG_EQUAL != null".to_string(),
-            Self::DoubleEqual => "EQUAL_EQUAL == null".to_string(),
-            Self::Semicolon => "SEMICOLON ; null".to_string(),
-            Self::Less => "LESS < null".to_string(),
-            Self::LessEqual => "LESS_EQUAL <= null".to_string(),
-            Self::Greater => "GREATER > null".to_string(),
-            Self::GreaterEqual => "GREATER_EQUAL >= null".to_string(),
-            Self::Slash => "SLASH / null".to_string(),
-            Self::Tab => "".to_string(),
-            Self::Identifier(id) => format!("IDENTIFIER {} null", id),
-            Self::Number(s) => {
-                if s.contains('.') {
-                    let f: f64 = s.parse().expect("can't parse float");
-                    let parsed = f.to_string();
-                    if parsed.contains('.') {
-                        format!("NUMBER {} {}", s, parsed)
-                    } else {
-                        format!("NUMBER {} {}.0", s, parsed)
-                    }
-                } else {
-                    format!("NUMBER {} {}.0", s, s)
-                }
-            }
-            Self::And => "AND and null".to_string(),
-            Self::Class => "CLASS class null".to_string(),
-            Self::Else => "ELSE else null".to_string(),
-            Self::False => "FALSE false null".to_string(),
-            Self::For => "FOR for null".to_string(),
-            Self::Fun => "FUN fun null".to_string(),
-            Self::If => "IF if null".to_string(),
-            Self::Nil => "NIL nil null".to_string(),
-            Self::Or => "OR or null".to_string(),
-            Self::Print => "PRINT print null".to_string(),
-            Self::Return => "RETURN return null".to_string(),
-            Self::Super => "SUPER super null".to_string(),
-            Self::This => "THIS this null".to_string(),
-            Self::True => "TRUE true null".to_string(),
-            Self::Var => "VAR var null".to_string(),
-            Self::While => "WHILE while null".to_string(),
-            Self::LoxString(s) => format!("STRING \"{}\" {}", s, s),
-            Self::Eof => "EOF  null".to_string(),
-        }
-    }
-
-    fn is_reserved(s: &str) -> Option<Self> {
+    /// The keyword `s` is, if it is a reserved word.
+    pub fn is_reserved(s: &str) -> Option<Self> {
         match s {
             "and" => Some(Self::And),
+            "break" => Some(Self::Break),
+            "catch" => Some(Self::Catch),
             "class" => Some(Self::Class),
+            "import" => Some(Self::Import),
+            "continue" => Some(Self::Continue),
             "else" => Some(Self::Else),
             "false" => Some(Self::False),
+            "finally" => Some(Self::Finally),
             "for" => Some(Self::For),
             "fun" => Some(Self::Fun),
             "if" => Some(Self::If),
@@ -172,7 +254,9 @@ impl Token {
             "return" => Some(Self::Return),
             "super" => Some(Self::Super),
             "this" => Some(Self::This),
+            "throw" => Some(Self::Throw),
             "true" => Some(Self::True),
+            "try" => Some(Self::Try),
             "var" => Some(Self::Var),
             "while" => Some(Self::While),
             _ => None,
@@ -180,14 +264,37 @@ impl Token {
     }
 }
 
+/// The reserved words, those `Token::is_reserved` reads as keywords.
+pub const KEYWORDS: [&str; 23] = [
+    "and", "break", "catch", "class", "continue", "else", "false", "finally", "for", "fun", "if",
+    "import", "nil", "or", "print", "return", "super", "this", "throw", "true", "try", "var",
+    "while",
+];
+
 #[derive(Debug, Clone)]
 pub struct ReadTokenStatus {
     prev: char,
     pub err: bool,
     line_number: u32,
+    column: usize,
+    /// Byte offsets of the current char and of the one after it.
+    offset: usize,
+    next_offset: usize,
+    /// Where the number, identifier or string being read started.
+    start: Span,
     number: String,
     identifier: String,
-    pub tokens: Vec<Token>,
+    /// Decoded value of the string being read.
+    string: String,
+    /// What follows the backslash of an escape sequence being read, and
+    /// where the backslash is.
+    escape: Option<String>,
+    escape_start: Span,
+    pub tokens: Vec<SpannedToken>,
+    /// Comments in source order.
+    pub comments: Vec<Comment>,
+    /// Lexical errors in source order, left for the caller to report.
+    pub errors: Vec<LexError>,
     state: LexerState,
     pub braces_depth: i32,
 }
@@ -207,31 +314,94 @@ impl ReadTokenStatus {
             prev: '0',
             err: false,
             line_number: 1,
+            column: 0,
+            offset: 0,
+            next_offset: 0,
+            start: Span::default(),
             number: String::new(),
             tokens: Vec::new(),
+            comments: Vec::new(),
+            errors: Vec::new(),
             state: LexerState::DontKnow,
             identifier: String::new(),
+            string: String::new(),
+            escape: None,
+            escape_start: Span::default(),
             braces_depth: 0,
         }
     }
+    /// Span of the current char.
+    fn span(&self) -> Span {
+        Span {
+            start: self.offset,
+            end: self.next_offset,
+            line: self.line_number as usize,
+            column: self.column,
+        }
+    }
+
+    /// Span of the token being read, up to the current char excluded.
+    fn pending_span(&self) -> Span {
+        Span {
+            end: self.offset,
+            ..self.start
+        }
+    }
+
+    fn last_token(&self) -> Option<&Token> {
+        self.tokens.last().map(|t| &t.token)
+    }
+
+    fn push(&mut self, token: Token) {
+        self.push_at(token, self.span());
+    }
+
+    fn push_at(&mut self, token: Token, span: Span) {
+        self.tokens.push(SpannedToken { token, span });
+    }
+
+    /// Drops the last token, returning its span.
+    fn pop(&mut self) -> Span {
+        self.tokens.pop().map(|t| t.span).unwrap_or_default()
+    }
+
+    /// Replaces the last token by `token`, which extends it with the current
+    /// char.
+    fn merge(&mut self, token: Token) {
+        let span = self.pop().to(self.span());
+        self.push_at(token, span);
+    }
+
+    fn error(&mut self, message: String, span: Span) {
+        self.err = true;
+        self.errors.push(LexError { message, span });
+    }
+
     fn set_error_flag(&mut self) {
         self.err |= matches!(self.state, LexerState::ParsingString);
     }
 
     fn endof_number(&mut self) {
         if self.state == LexerState::ReadNumber {
-            self.tokens.push(Token::Number(self.number.clone()));
+            match self.number.parse() {
+                Ok(value) => self.push_at(
+                    Token::Number(self.number.clone(), value),
+                    self.pending_span(),
+                ),
+                Err(_) => {
+                    let message = format!("Invalid number: {}", self.number);
+                    self.error(message, self.pending_span());
+                }
+            }
             self.number.clear();
         }
     }
 
     fn endof_identifier(&mut self) {
         if self.state == LexerState::ReadIdentifier {
-            if let Some(t) = Token::is_reserved(&self.identifier) {
-                self.tokens.push(t);
-            } else {
-                self.tokens.push(Token::Identifier(self.identifier.clone()));
-            }
+            let token = Token::is_reserved(&self.identifier)
+                .unwrap_or_else(|| Token::Identifier(self.identifier.clone()));
+            self.push_at(token, self.pending_span());
             self.identifier.clear();
         }
     }
@@ -242,24 +412,128 @@ impl ReadTokenStatus {
         self.endof_identifier();
     }
 
+    /// Ends the comment being read before the current char, `source` being
+    /// the whole text read.
+    fn endof_comment(&mut self, source: &str) {
+        if self.state == LexerState::IgnoreComment {
+            let span = self.pending_span();
+            let text = source[span.start..span.end].trim_end();
+            self.comments.push(Comment {
+                text: text.to_string(),
+                span: Span {
+                    end: span.start + text.len(),
+                    ..span
+                },
+            });
+        }
+    }
+
+    /// Reports the escape sequence `escape`, which ends at `end`.
+    fn escape_error(&mut self, escape: &str, end: Span) {
+        let message = format!("Invalid escape sequence: \\{}", escape);
+        self.error(message, self.escape_start.to(end));
+    }
+
+    /// The end of an escape sequence cut short by the current char.
+    fn escape_end(&self) -> Span {
+        Span {
+            end: self.offset,
+            ..self.escape_start
+        }
+    }
+
+    /// Adds `c`, read inside a string literal, to the decoded string.
+    fn string_char(&mut self, c: char) {
+        let Some(mut escape) = self.escape.take() else {
+            if c == '\\' {
+                self.escape = Some(String::new());
+                self.escape_start = self.span();
+            } else {
+                self.string.push(c);
+            }
+            return;
+        };
+        escape.push(c);
+        let decoded = match escape.as_str() {
+            "n" => '\n',
+            "t" => '\t',
+            "\\" => '\\',
+            "\"" => '"',
+            "u" | "u{" => {
+                self.escape = Some(escape);
+                return;
+            }
+            // Read up to the closing brace, so that a bad escape is reported
+            // whole. It can't run past a space or a line break.
+            s if s.starts_with("u{") && c != '}' => {
+                if c.is_whitespace() {
+                    escape.pop();
+                    self.escape_error(&escape, self.escape_end());
+                    self.string.push(c);
+                } else {
+                    self.escape = Some(escape);
+                }
+                return;
+            }
+            // `\u{...}`, with one to six hex digits naming a valid char.
+            s if s.starts_with("u{") && s.len() <= 9 => {
+                let digits = &s[2..s.len() - 1];
+                let code = digits
+                    .chars()
+                    .all(|c| c.is_ascii_hexdigit())
+                    .then(|| u32::from_str_radix(digits, 16).ok())
+                    .flatten();
+                match code.and_then(char::from_u32) {
+                    Some(c) => c,
+                    None => return self.escape_error(&escape, self.span()),
+                }
+            }
+            _ => return self.escape_error(&escape, self.span()),
+        };
+        self.string.push(decoded);
+    }
+
+    /// Ends the string literal at the current char, a closing quote.
+    fn endof_string(&mut self, lexeme: String) {
+        if let Some(escape) = self.escape.take() {
+            self.escape_error(&escape, self.escape_end());
+        }
+        let span = self.start.to(self.span());
+        let value = std::mem::take(&mut self.string);
+        self.push_at(Token::LoxString(lexeme, value), span);
+        self.state = LexerState::DontKnow;
+    }
+
     fn endof_file(&mut self) {
         if self.state == LexerState::ParsingString {
-            let err = format!("[line {}] Error: Unterminated string.", self.line_number);
-            eprintln!("{}", err);
+            // Reported where the input ends, like a missing token.
+            self.error("Unterminated string.".to_string(), self.span());
         }
         self.endof();
-        self.tokens.push(Token::Eof);
+        self.push(Token::Eof);
     }
 }
 
-pub fn read_token(file_contents: String) -> ReadTokenStatus {
+pub fn read_token(file_contents: &str) -> ReadTokenStatus {
     let mut status = ReadTokenStatus::new();
     let mut temp_str = String::new();
-    for c in file_contents.chars() {
+    for (offset, c) in file_contents.char_indices() {
+        status.offset = offset;
+        status.next_offset = offset + c.len_utf8();
+        status.column += 1;
         if status.state == LexerState::IgnoreComment && c != '\n' {
             continue;
-        } else if status.state == LexerState::ParsingString && c != '"' {
+        } else if status.state == LexerState::IgnoreComment {
+            status.endof_comment(file_contents);
+        } else if status.state == LexerState::ParsingString
+            && (c != '"' || status.escape.as_deref() == Some(""))
+        {
+            if c == '\n' {
+                status.line_number += 1;
+                status.column = 0;
+            }
             temp_str.push(c);
+            status.string_char(c);
             continue;
         }
 
@@ -267,8 +541,9 @@ pub fn read_token(file_contents: String) -> ReadTokenStatus {
             'a'..='z' | 'A'..='Z' | '_' => {
                 // Dans le cas d'un commentaire, deja ignore, idem pour string
                 // assert!(matches!(status.state, LexerState::DontKnow|LexerState::ReadIdentifier));
-                if status.state == LexerState::ReadNumber {
+                if status.state != LexerState::ReadIdentifier {
                     status.endof_number();
+                    status.start = status.span();
                 }
                 status.state = LexerState::ReadIdentifier;
                 status.identifier.push(c);
@@ -278,9 +553,8 @@ pub fn read_token(file_contents: String) -> ReadTokenStatus {
                     status.identifier.push(c);
                 }
                 _ => {
-                    if status.state != LexerState::ReadNumber && status.prev == '-' {
-                        status.tokens.pop();
-                        status.number.push('-');
+                    if status.state != LexerState::ReadNumber {
+                        status.start = status.span();
                     }
                     status.number.push(c);
                     status.state = LexerState::ReadNumber;
@@ -290,146 +564,160 @@ pub fn read_token(file_contents: String) -> ReadTokenStatus {
                 LexerState::ReadNumber => {
                     status.number.push(c);
                 }
-                _ => status.tokens.push(Token::Dot),
+                _ => {
+                    status.endof();
+                    status.push(Token::Dot);
+                    status.state = LexerState::DontKnow;
+                }
             },
             '(' => {
                 status.endof();
-                status.tokens.push(Token::LeftParen);
+                status.push(Token::LeftParen);
                 status.state = LexerState::DontKnow;
             }
             ')' => {
                 status.endof();
-                status.tokens.push(Token::RightParen);
+                status.push(Token::RightParen);
+                status.state = LexerState::DontKnow;
+            }
+            '[' => {
+                status.endof();
+                status.push(Token::LeftBracket);
+                status.state = LexerState::DontKnow;
+            }
+            ']' => {
+                status.endof();
+                status.push(Token::RightBracket);
                 status.state = LexerState::DontKnow;
             }
             '}' => {
                 status.endof();
                 status.braces_depth -= 1;
-                status.tokens.push(Token::RightBraces);
+                status.push(Token::RightBraces);
                 status.state = LexerState::DontKnow;
             }
             '{' => {
                 status.endof();
                 status.braces_depth += 1;
-                status.tokens.push(Token::LeftBraces);
+                status.push(Token::LeftBraces);
                 status.state = LexerState::DontKnow;
             }
             '*' => {
                 status.endof();
-                status.tokens.push(Token::Star);
+                status.push(Token::Star);
                 status.state = LexerState::DontKnow;
             }
             ',' => {
                 status.endof();
-                status.tokens.push(Token::Comma);
+                status.push(Token::Comma);
+                status.state = LexerState::DontKnow;
+            }
+            ':' => {
+                status.endof();
+                status.push(Token::Colon);
                 status.state = LexerState::DontKnow;
             }
             '+' => {
                 status.endof();
-                status.tokens.push(Token::Plus);
+                status.push(Token::Plus);
                 status.state = LexerState::DontKnow;
             }
             '-' => {
                 status.endof();
-                status.tokens.push(Token::Minus);
+                status.push(Token::Minus);
                 status.state = LexerState::DontKnow;
             }
             ';' => {
                 status.endof();
-                status.tokens.push(Token::Semicolon);
+                status.push(Token::Semicolon);
                 status.state = LexerState::DontKnow;
             }
             '<' => {
                 status.endof();
-                status.tokens.push(Token::Less);
+                status.push(Token::Less);
                 status.state = LexerState::DontKnow;
             }
             '>' => {
                 status.endof();
-                status.tokens.push(Token::Greater);
+                if status.prev == '=' && matches!(status.last_token().unwrap(), Token::Equal) {
+                    status.merge(Token::Arrow);
+                } else {
+                    status.push(Token::Greater);
+                }
                 status.state = LexerState::DontKnow;
             }
             '!' => {
                 status.endof();
-                status.tokens.push(Token::Bang);
+                status.push(Token::Bang);
                 status.state = LexerState::DontKnow;
             }
-            '"' => {
-                if status.prev == '\\' {
-                    todo!()
+            '"' => match status.state {
+                LexerState::IgnoreComment => {}
+                LexerState::ParsingString => {
+                    status.endof_string(std::mem::take(&mut temp_str));
                 }
-                match status.state {
-                    LexerState::IgnoreComment => {}
-                    LexerState::ParsingString => {
-                        status.tokens.push(Token::LoxString(temp_str.clone()));
-                        temp_str.clear();
-                        status.state = LexerState::DontKnow;
-                    }
-                    _ => {
-                        status.state = LexerState::ParsingString;
-                    }
+                _ => {
+                    status.start = status.span();
+                    status.state = LexerState::ParsingString;
                 }
-            }
-            '\t' => status.tokens.push(Token::Tab),
+            },
             '/' => {
-                if status.prev == '/' && status.state != LexerState::IgnoreComment {
+                status.endof();
+                if status.prev == '/' && matches!(status.last_token(), Some(Token::Slash)) {
+                    status.start = status.pop();
                     status.state = LexerState::IgnoreComment;
-                    status.tokens.pop();
-                } else if status.state != LexerState::IgnoreComment {
-                    status.tokens.push(Token::Slash);
+                } else {
+                    status.push(Token::Slash);
+                    status.state = LexerState::DontKnow;
                 }
             }
             '=' => {
                 status.endof();
                 if status.prev == '='
                     && !matches!(
-                        status.tokens.last().unwrap(),
+                        status.last_token().unwrap(),
                         Token::DoubleEqual
                             | Token::BangEqual
                             | Token::GreaterEqual
                             | Token::LessEqual
                     )
                 {
-                    status.tokens.pop();
-                    status.tokens.push(Token::DoubleEqual);
+                    status.merge(Token::DoubleEqual);
                 } else if status.prev == '!'
-                    && !matches!(status.tokens.last().unwrap(), Token::BangEqual)
+                    && !matches!(status.last_token().unwrap(), Token::BangEqual)
                 {
-                    status.tokens.pop();
-                    status.tokens.push(Token::BangEqual);
+                    status.merge(Token::BangEqual);
                 } else if status.prev == '>'
-                    && !matches!(status.tokens.last().unwrap(), Token::GreaterEqual)
+                    && !matches!(
+                        status.last_token().unwrap(),
+                        Token::GreaterEqual | Token::Arrow
+                    )
                 {
-                    status.tokens.pop();
-                    status.tokens.push(Token::GreaterEqual);
+                    status.merge(Token::GreaterEqual);
                 } else if status.prev == '<'
-                    && !matches!(status.tokens.last().unwrap(), Token::GreaterEqual)
+                    && !matches!(status.last_token().unwrap(), Token::GreaterEqual)
                 {
-                    status.tokens.pop();
-                    status.tokens.push(Token::LessEqual);
+                    status.merge(Token::LessEqual);
                 } else {
-                    status.tokens.push(Token::Equal);
+                    status.push(Token::Equal);
                 }
                 status.state = LexerState::DontKnow;
             }
-            '\n' | '\r' => {
+            '\n' => {
                 status.set_error_flag();
                 status.line_number += 1;
+                status.column = 0;
                 status.endof();
                 status.state = LexerState::DontKnow;
             }
-            ' ' => {
+            ' ' | '\t' | '\r' => {
                 status.endof();
                 status.state = LexerState::DontKnow;
             }
             _ => {
                 status.state = LexerState::DontKnow;
-                status.err = true;
-                let err = format!(
-                    "[line {}] Error: Unexpected character: {}",
-                    status.line_number, c
-                );
-                eprintln!("{}", err);
+                let message = format!("Unexpected character: {}", c);
+                status.error(message, status.span());
             }
         }
         status.prev = c;
@@ -437,6 +725,10 @@ pub fn read_token(file_contents: String) -> ReadTokenStatus {
 
     status.set_error_flag();
 
+    status.offset = file_contents.len();
+    status.next_offset = file_contents.len();
+    status.column += 1;
+    status.endof_comment(file_contents);
     status.endof_file();
     status
 }
@@ -0,0 +1,34 @@
+//! A Lox interpreter. [`Lox`] embeds it in Rust programs; the other modules
+//! back the command line tool and are not part of the stable API.
+
+#[doc(hidden)]
+pub mod backend;
+#[doc(hidden)]
+pub mod error;
+#[doc(hidden)]
+pub mod interpreter;
+#[doc(hidden)]
+pub mod json;
+#[doc(hidden)]
+pub mod lexer;
+#[doc(hidden)]
+pub mod limits;
+mod lox;
+#[doc(hidden)]
+pub mod lsp;
+#[doc(hidden)]
+pub mod map;
+#[doc(hidden)]
+pub mod module;
+#[doc(hidden)]
+pub mod parser;
+#[doc(hidden)]
+pub mod resolver;
+#[doc(hidden)]
+pub mod vm;
+
+pub use backend::Arity;
+pub use error::{Diagnostic, LexError, LoxError, RuntimeError, TraceFrame};
+pub use interpreter::debug::{DebugHooks, Frame, Paused, Resume, Stop};
+pub use limits::{Limit, Limits};
+pub use lox::{Lox, Object, Value};
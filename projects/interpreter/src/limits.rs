@@ -0,0 +1,134 @@
+use std::time::{Duration, Instant};
+
+/// Bounds on what a program may use, to run scripts that are not trusted.
+/// `None` leaves a resource unbounded.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Limits {
+    /// Evaluation steps: expressions and statements for the tree-walking
+    /// interpreter, instructions for the VM.
+    pub steps: Option<u64>,
+    /// Calls in progress at once.
+    pub call_depth: Option<usize>,
+    /// Wall-clock time.
+    pub time: Option<Duration>,
+    /// Memory the program allocates, in units: one per instance, function
+    /// or class, one per byte of a concatenated string and one per item of
+    /// a list or map literal. Items added to a list or map later are not
+    /// counted.
+    pub allocations: Option<u64>,
+}
+
+/// Calls the tree-walking interpreter can nest without overflowing the
+/// 8 MiB stack of a main thread, in debug builds too, where its calls take
+/// about four times as much of it. Threads with a smaller stack need a lower
+/// limit.
+pub const DEFAULT_CALL_DEPTH: usize = 100;
+
+/// Only the call depth is bounded by default, since running out of native
+/// stack aborts the whole process.
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            steps: None,
+            call_depth: Some(DEFAULT_CALL_DEPTH),
+            time: None,
+            allocations: None,
+        }
+    }
+}
+
+/// The limit a program ran into.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Limit {
+    Steps,
+    CallDepth,
+    Time,
+    Allocations,
+}
+
+impl Limit {
+    /// The message of the runtime error stopping the program.
+    pub fn message(self) -> &'static str {
+        match self {
+            Limit::Steps => "Step limit exceeded.",
+            Limit::CallDepth => "Stack overflow.",
+            Limit::Time => "Time limit exceeded.",
+            Limit::Allocations => "Allocation limit exceeded.",
+        }
+    }
+}
+
+/// Steps between two looks at the clock, reading it is not free.
+const CLOCK_INTERVAL: u64 = 1024;
+
+/// What a backend used of its limits so far.
+#[derive(Debug)]
+pub struct Budget {
+    limits: Limits,
+    steps: u64,
+    allocations: u64,
+    deadline: Option<Instant>,
+}
+
+impl Budget {
+    /// Starts counting against `limits`, the clock starts now.
+    pub fn new(limits: Limits) -> Self {
+        Self {
+            limits,
+            steps: 0,
+            allocations: 0,
+            deadline: limits
+                .time
+                .and_then(|time| Instant::now().checked_add(time)),
+        }
+    }
+
+    /// Counts from zero again, the clock starting now.
+    pub fn restart(&mut self) {
+        *self = Self::new(self.limits);
+    }
+
+    /// Counts one evaluation step.
+    pub fn step(&mut self) -> Result<(), Limit> {
+        self.steps += 1;
+        if self.limits.steps.is_some_and(|max| self.steps > max) {
+            return Err(Limit::Steps);
+        }
+        if self.steps % CLOCK_INTERVAL == 0
+            && self
+                .deadline
+                .is_some_and(|deadline| Instant::now() >= deadline)
+        {
+            return Err(Limit::Time);
+        }
+        Ok(())
+    }
+
+    /// Counts a value of `size` units created, see [`Limits::allocations`].
+    /// Even an empty one counts for a unit.
+    pub fn allocate(&mut self, size: usize) -> Result<(), Limit> {
+        self.allocations = self.allocations.saturating_add(size.max(1) as u64);
+        if self
+            .limits
+            .allocations
+            .is_some_and(|max| self.allocations > max)
+        {
+            return Err(Limit::Allocations);
+        }
+        Ok(())
+    }
+
+    /// Checks that a call can start while `depth` others are in progress.
+    pub fn call(&self, depth: usize) -> Result<(), Limit> {
+        match self.limits.call_depth {
+            Some(max) if depth >= max => Err(Limit::CallDepth),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl Default for Budget {
+    fn default() -> Self {
+        Self::new(Limits::default())
+    }
+}
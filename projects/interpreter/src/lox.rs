@@ -0,0 +1,227 @@
+use crate::backend::Arity;
+use crate::error::{LoxError, RuntimeError};
+use crate::interpreter::core::{interm_to_var_val, Interpreter, VarValue};
+use crate::interpreter::debug::DebugHooks;
+use crate::interpreter::evaluate::IntermRepr;
+use crate::interpreter::func::builtin::Native;
+use crate::lexer::Span;
+use crate::limits::Limits;
+use crate::parser::core::compile;
+use crate::parser::parser_ds::ExprAst;
+use std::fmt;
+use std::rc::Rc;
+
+/// A Lox value handed to or received from the engine.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Nil,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    /// A function, class, instance, list, map, module or caught error. Rust
+    /// code can only print it or pass it back to Lox.
+    Object(Object),
+}
+
+/// A Lox value with identity, see [`Value::Object`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Object(VarValue);
+
+impl Value {
+    fn to_interm(&self) -> IntermRepr {
+        match self {
+            Value::Nil => IntermRepr::Nil,
+            Value::Bool(b) => IntermRepr::Bool(*b),
+            Value::Number(n) => IntermRepr::Num(*n),
+            Value::String(s) => IntermRepr::Str(s.clone()),
+            Value::Object(o) => o.0.to_interm(),
+        }
+    }
+}
+
+impl From<IntermRepr> for Value {
+    fn from(r: IntermRepr) -> Self {
+        match r {
+            IntermRepr::Nil | IntermRepr::Break | IntermRepr::Continue => Value::Nil,
+            IntermRepr::Bool(b) => Value::Bool(b),
+            IntermRepr::Num(n) => Value::Number(n),
+            IntermRepr::Str(s) | IntermRepr::Op(s) => Value::String(s),
+            IntermRepr::Ret(r) => Value::from(*r),
+            r @ (IntermRepr::Func(_)
+            | IntermRepr::Class(_)
+            | IntermRepr::Instance(_)
+            | IntermRepr::List(_)
+            | IntermRepr::Map(_)
+            | IntermRepr::Module(_)
+            | IntermRepr::Error(_)) => Value::Object(Object(interm_to_var_val(&r))),
+        }
+    }
+}
+
+impl From<bool> for Value {
+    fn from(b: bool) -> Self {
+        Value::Bool(b)
+    }
+}
+
+impl From<f64> for Value {
+    fn from(n: f64) -> Self {
+        Value::Number(n)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(s: &str) -> Self {
+        Value::String(s.to_string())
+    }
+}
+
+impl From<String> for Value {
+    fn from(s: String) -> Self {
+        Value::String(s)
+    }
+}
+
+/// Prints the value the way Lox's `print` does.
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_interm())
+    }
+}
+
+/// An embedded Lox interpreter. Globals defined by one call to [`Lox::eval`]
+/// are visible to the next ones.
+///
+/// ```
+/// # use codecrafters_interpreter::{Lox, Value};
+/// # fn main() -> Result<(), codecrafters_interpreter::LoxError> {
+/// let mut lox = Lox::new();
+/// lox.set_global("base", 40.0);
+/// lox.eval("fun add(n) { return base + n; }")?;
+/// assert_eq!(lox.call("add", &[2.0.into()])?, Value::Number(42.0));
+/// # Ok(())
+/// # }
+/// ```
+pub struct Lox {
+    interpreter: Interpreter,
+}
+
+impl Lox {
+    pub fn new() -> Self {
+        Self {
+            interpreter: Interpreter::new(),
+        }
+    }
+
+    /// Runs `source` and returns the value of its last statement when it is
+    /// an expression, `nil` otherwise. Nothing runs if it does not compile.
+    pub fn eval(&mut self, source: &str) -> Result<Value, LoxError> {
+        let stmts = compile(source)?;
+        self.interpreter.restart_budget();
+        let mut last = IntermRepr::Nil;
+        for stmt in &stmts {
+            let r = self.interpreter.statement(stmt).map_err(|err| {
+                self.interpreter.clear_thrown();
+                err.in_script()
+            })?;
+            last = if stmt.is_expression() {
+                r
+            } else {
+                IntermRepr::Nil
+            };
+        }
+        Ok(last.into())
+    }
+
+    /// Bounds what each later [`Lox::eval`] or [`Lox::call`] may use, on its
+    /// own: steps, allocations and time count from zero at the start of
+    /// every one. Running into a limit fails with [`LoxError::Limit`], which
+    /// `catch` clauses can't stop.
+    pub fn set_limits(&mut self, limits: Limits) {
+        self.interpreter.set_limits(limits);
+    }
+
+    /// Asks `hooks` before every statement run from now on whether to stop
+    /// there. The first statement always stops, so breakpoints can be set
+    /// before anything runs.
+    pub fn set_debugger(&mut self, hooks: impl DebugHooks + 'static) {
+        self.interpreter.set_debugger(Box::new(hooks));
+    }
+
+    pub fn get_global(&self, name: &str) -> Option<Value> {
+        let val = self.interpreter.get_var(name, None)?;
+        Some(val.to_interm().into())
+    }
+
+    /// Defines or overwrites the global `name`.
+    pub fn set_global(&mut self, name: &str, value: impl Into<Value>) {
+        let val = interm_to_var_val(&value.into().to_interm());
+        self.interpreter.define_var(name.to_string(), None, val);
+    }
+
+    /// Defines the global function `name`, implemented in Rust. `fun` gets
+    /// the evaluated arguments, already checked against `arity`, and an `Err`
+    /// becomes a Lox runtime error at the call site. It can capture host
+    /// state; use a `Cell` or `RefCell` to mutate it.
+    ///
+    /// ```
+    /// # use codecrafters_interpreter::{Arity, Lox, Value};
+    /// # fn main() -> Result<(), codecrafters_interpreter::LoxError> {
+    /// let mut lox = Lox::new();
+    /// lox.define_native("max", Arity::Variadic, |args| {
+    ///     let mut max = f64::NEG_INFINITY;
+    ///     for arg in args {
+    ///         match arg {
+    ///             Value::Number(n) => max = max.max(*n),
+    ///             _ => return Err("Arguments must be numbers.".to_string()),
+    ///         }
+    ///     }
+    ///     Ok(Value::Number(max))
+    /// });
+    /// assert_eq!(lox.eval("max(3, 7, 5)")?, Value::Number(7.0));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn define_native<F>(&mut self, name: &str, arity: Arity, fun: F)
+    where
+        F: Fn(&[Value]) -> Result<Value, String> + 'static,
+    {
+        let native = Native {
+            arity,
+            fun: Rc::new(move |args: &[IntermRepr]| {
+                let args: Vec<Value> = args.iter().cloned().map(Value::from).collect();
+                fun(&args).map(|v| v.to_interm())
+            }),
+        };
+        self.interpreter.define_native(name.to_string(), native);
+    }
+
+    /// Calls the global function or class `name`. Errors raised by the call
+    /// itself, like a wrong number of arguments, are reported where the
+    /// callee is declared.
+    pub fn call(&mut self, name: &str, args: &[Value]) -> Result<Value, LoxError> {
+        let Some(callee) = self.interpreter.get_var(name, None) else {
+            let msg = format!("Undefined variable '{}'.", name);
+            return Err(RuntimeError::new(msg, Span::default()).into());
+        };
+        let callee = callee.to_interm();
+        let span = match &callee {
+            IntermRepr::Func(f) => f.borrow().ptr.body.as_ref().map(ExprAst::span),
+            IntermRepr::Class(c) => Some(c.span),
+            _ => None,
+        };
+        let args = args.iter().map(Value::to_interm).collect();
+        self.interpreter.restart_budget();
+        let r = self
+            .interpreter
+            .call(&callee, args, span.unwrap_or_default())
+            .inspect_err(|_| self.interpreter.clear_thrown())?;
+        Ok(r.into())
+    }
+}
+
+impl Default for Lox {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,437 @@
+//! A language server speaking the Language Server Protocol over a pair of
+//! streams, stdin and stdout for the `lsp` command.
+
+mod symbols;
+
+use crate::backend::Arity;
+use crate::json::Json;
+use crate::lexer::{read_token, Span, KEYWORDS};
+use crate::parser::core::parse_partial;
+use crate::resolver::Resolver;
+use std::collections::HashMap;
+use std::io::{self, BufRead, Write};
+use symbols::{Definition, Kind, Symbols};
+
+/// Error codes of JSON-RPC.
+const PARSE_ERROR: i32 = -32700;
+const INVALID_PARAMS: i32 = -32602;
+const METHOD_NOT_FOUND: i32 = -32601;
+
+/// The longest message content read, in bytes. A longer one is taken for a
+/// broken header rather than allocated.
+const MAX_MESSAGE: usize = 64 << 20;
+
+/// Where the lines of a text start, to convert between byte offsets and
+/// LSP positions, which count UTF-16 code units.
+struct LineIndex {
+    starts: Vec<usize>,
+}
+
+impl LineIndex {
+    fn new(text: &str) -> Self {
+        let lines = text.match_indices('\n').map(|(at, _)| at + 1);
+        Self {
+            starts: std::iter::once(0).chain(lines).collect(),
+        }
+    }
+
+    fn position(&self, text: &str, offset: usize) -> Json {
+        let line = self.starts.partition_point(|&start| start <= offset) - 1;
+        let start = self.starts[line];
+        let character = text
+            .get(start..offset)
+            .unwrap_or_default()
+            .encode_utf16()
+            .count();
+        Json::object([("line", line.into()), ("character", character.into())])
+    }
+
+    fn range(&self, text: &str, span: Span) -> Json {
+        Json::object([
+            ("start", self.position(text, span.start)),
+            ("end", self.position(text, span.end)),
+        ])
+    }
+
+    /// The offset of an LSP position, clamped to the end of its line.
+    fn offset(&self, text: &str, position: &Json) -> Option<usize> {
+        let line = position.get("line")?.as_f64()? as usize;
+        let character = position.get("character")?.as_f64()? as usize;
+        let start = *self.starts.get(line)?;
+        let mut units = 0;
+        for (at, c) in text[start..].char_indices() {
+            if units >= character || c == '\n' {
+                return Some(start + at);
+            }
+            units += c.len_utf16();
+        }
+        Some(text.len())
+    }
+}
+
+/// An open file, analysed again whenever it changes.
+struct Document {
+    text: String,
+    lines: LineIndex,
+    symbols: Symbols,
+    diagnostics: Vec<Json>,
+}
+
+fn diagnostic(range: Json, message: &str) -> Json {
+    Json::object([
+        ("range", range),
+        ("severity", 1.into()),
+        ("source", Json::str("lox")),
+        ("message", Json::str(message)),
+    ])
+}
+
+impl Document {
+    fn new(text: String) -> Self {
+        let lines = LineIndex::new(&text);
+        let mut doc = Self {
+            text,
+            lines,
+            symbols: Symbols::default(),
+            diagnostics: Vec::new(),
+        };
+        let out = read_token(&doc.text);
+        if !out.errors.is_empty() {
+            for err in &out.errors {
+                let range = doc.lines.range(&doc.text, err.span);
+                doc.diagnostics.push(diagnostic(range, &err.message));
+            }
+            return doc;
+        }
+        let (mut stmts, mut errors) = parse_partial(&doc.text, out.tokens);
+        if errors.is_empty() {
+            errors = Resolver::new()
+                .resolve(&mut stmts)
+                .err()
+                .unwrap_or_default();
+        }
+        for err in &errors {
+            let range = doc.lines.range(&doc.text, err.span);
+            doc.diagnostics.push(diagnostic(range, &err.message));
+        }
+        doc.symbols = Symbols::new(&doc.text, &stmts);
+        doc
+    }
+
+    fn location(&self, uri: &str, span: Span) -> Json {
+        Json::object([
+            ("uri", Json::str(uri)),
+            ("range", self.lines.range(&self.text, span)),
+        ])
+    }
+}
+
+/// How a definition reads in hovers and completions.
+fn signature(def: &Definition) -> String {
+    let params = def.params.as_ref().map(|p| p.join(", "));
+    match (def.kind, params) {
+        (Kind::Function, Some(params)) => format!("fun {}({})", def.name, params),
+        (Kind::Class, Some(params)) => format!("class {}({})", def.name, params),
+        (Kind::Variable, Some(params)) => format!("var {} = fun ({})", def.name, params),
+        (Kind::Native, _) => format!("native fun {}", def.name),
+        (Kind::Parameter, _) => format!("parameter {}", def.name),
+        _ => format!("var {}", def.name),
+    }
+}
+
+fn hover(def: &Definition) -> String {
+    let mut text = format!("```lox\n{}\n```", signature(def));
+    match def.arity {
+        Some(Arity::Fixed(n)) => text.push_str(&format!("\n\nArity: {}", n)),
+        Some(Arity::Variadic) => text.push_str("\n\nArity: any number of arguments"),
+        None => (),
+    }
+    text
+}
+
+/// `CompletionItemKind` of the protocol.
+fn completion_kind(kind: Kind) -> usize {
+    match kind {
+        Kind::Function | Kind::Native => 3,
+        Kind::Variable | Kind::Parameter => 6,
+        Kind::Class => 7,
+    }
+}
+
+fn capabilities() -> Json {
+    Json::object([
+        (
+            "capabilities",
+            Json::object([
+                // Full text on every change.
+                ("textDocumentSync", 1.into()),
+                ("definitionProvider", Json::Bool(true)),
+                ("referencesProvider", Json::Bool(true)),
+                ("hoverProvider", Json::Bool(true)),
+                ("completionProvider", Json::object([])),
+            ]),
+        ),
+        (
+            "serverInfo",
+            Json::object([
+                ("name", Json::str("lox")),
+                ("version", Json::str(env!("CARGO_PKG_VERSION"))),
+            ]),
+        ),
+    ])
+}
+
+type Response = Result<Json, (i32, String)>;
+
+fn invalid_params() -> (i32, String) {
+    (INVALID_PARAMS, "Invalid params.".to_string())
+}
+
+/// The open documents and what the client asked so far.
+#[derive(Default)]
+pub struct Server {
+    documents: HashMap<String, Document>,
+    shut_down: bool,
+}
+
+impl Server {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The document and offset a `TextDocumentPositionParams` points at.
+    fn at<'a>(
+        &'a self,
+        params: Option<&'a Json>,
+    ) -> Result<(&'a str, &'a Document, usize), (i32, String)> {
+        let params = params.ok_or_else(invalid_params)?;
+        let uri = params
+            .get("textDocument")
+            .and_then(|d| d.get("uri"))
+            .and_then(Json::as_str)
+            .ok_or_else(invalid_params)?;
+        let doc = self.documents.get(uri).ok_or_else(invalid_params)?;
+        let offset = params
+            .get("position")
+            .and_then(|p| doc.lines.offset(&doc.text, p))
+            .ok_or_else(invalid_params)?;
+        Ok((uri, doc, offset))
+    }
+
+    fn request(&mut self, method: &str, params: Option<&Json>) -> Response {
+        match method {
+            "initialize" => Ok(capabilities()),
+            "shutdown" => {
+                self.shut_down = true;
+                Ok(Json::Null)
+            }
+            "textDocument/definition" => {
+                let (uri, doc, offset) = self.at(params)?;
+                let span = doc
+                    .symbols
+                    .definition_at(offset)
+                    .and_then(|id| doc.symbols.definitions[id].span);
+                Ok(span.map(|span| doc.location(uri, span)).into())
+            }
+            "textDocument/references" => {
+                let (uri, doc, offset) = self.at(params)?;
+                let declaration = params
+                    .and_then(|p| p.get("context"))
+                    .and_then(|c| c.get("includeDeclaration"))
+                    .and_then(Json::as_bool)
+                    .unwrap_or(true);
+                let Some(id) = doc.symbols.definition_at(offset) else {
+                    return Ok(Json::Array(Vec::new()));
+                };
+                let declared = doc.symbols.definitions[id].span;
+                let locations = doc
+                    .symbols
+                    .references_to(id)
+                    .filter(|&span| declaration || Some(span) != declared)
+                    .map(|span| doc.location(uri, span))
+                    .collect();
+                Ok(Json::Array(locations))
+            }
+            "textDocument/hover" => {
+                let (_, doc, offset) = self.at(params)?;
+                let Some(id) = doc.symbols.definition_at(offset) else {
+                    return Ok(Json::Null);
+                };
+                let span = doc.symbols.references.iter().find_map(|&(span, def)| {
+                    (def == id && span.start <= offset && offset <= span.end).then_some(span)
+                });
+                let contents = Json::object([
+                    ("kind", Json::str("markdown")),
+                    ("value", Json::Str(hover(&doc.symbols.definitions[id]))),
+                ]);
+                let range = span.map(|span| doc.lines.range(&doc.text, span));
+                Ok(Json::object([
+                    ("contents", contents),
+                    ("range", range.into()),
+                ]))
+            }
+            "textDocument/completion" => {
+                let (_, doc, offset) = self.at(params)?;
+                let keywords = KEYWORDS.iter().map(|keyword| {
+                    Json::object([("label", Json::str(*keyword)), ("kind", 14.into())])
+                });
+                let names = doc.symbols.in_scope(offset).into_iter().map(|def| {
+                    Json::object([
+                        ("label", Json::str(&def.name)),
+                        ("kind", completion_kind(def.kind).into()),
+                        ("detail", Json::Str(signature(def))),
+                    ])
+                });
+                Ok(Json::Array(names.chain(keywords).collect()))
+            }
+            _ => Err((METHOD_NOT_FOUND, format!("Method not found: {}", method))),
+        }
+    }
+
+    fn publish(&self, uri: &str) -> Json {
+        let diagnostics = self
+            .documents
+            .get(uri)
+            .map_or_else(Vec::new, |doc| doc.diagnostics.clone());
+        Json::object([
+            ("jsonrpc", Json::str("2.0")),
+            ("method", Json::str("textDocument/publishDiagnostics")),
+            (
+                "params",
+                Json::object([
+                    ("uri", Json::str(uri)),
+                    ("diagnostics", Json::Array(diagnostics)),
+                ]),
+            ),
+        ])
+    }
+
+    /// Handles a notification, returns those to send back.
+    fn notification(&mut self, method: &str, params: Option<&Json>) -> Vec<Json> {
+        let doc = params.and_then(|p| p.get("textDocument"));
+        let Some(uri) = doc.and_then(|d| d.get("uri")).and_then(Json::as_str) else {
+            return Vec::new();
+        };
+        let text = match method {
+            "textDocument/didOpen" => doc.and_then(|d| d.get("text")),
+            "textDocument/didChange" => params
+                .and_then(|p| p.get("contentChanges"))
+                .and_then(Json::as_array)
+                .and_then(|changes| changes.last())
+                .and_then(|change| change.get("text")),
+            "textDocument/didClose" => {
+                self.documents.remove(uri);
+                return vec![self.publish(uri)];
+            }
+            _ => return Vec::new(),
+        };
+        let Some(text) = text.and_then(Json::as_str) else {
+            return Vec::new();
+        };
+        self.documents
+            .insert(uri.to_string(), Document::new(text.to_string()));
+        vec![self.publish(uri)]
+    }
+
+    /// Handles a message from the client, returns the messages to send back.
+    pub fn handle(&mut self, message: &Json) -> Vec<Json> {
+        let Some(method) = message.get("method").and_then(Json::as_str) else {
+            // A response to a request of ours, we send none.
+            return Vec::new();
+        };
+        let params = message.get("params");
+        let Some(id) = message.get("id") else {
+            return self.notification(method, params);
+        };
+        let response = match self.request(method, params) {
+            Ok(result) => ("result", result),
+            Err((code, message)) => (
+                "error",
+                Json::object([("code", code.into()), ("message", Json::Str(message))]),
+            ),
+        };
+        vec![Json::object([
+            ("jsonrpc", Json::str("2.0")),
+            ("id", id.clone()),
+            response,
+        ])]
+    }
+}
+
+/// Reads the content of the next message, `None` at the end of the input.
+fn read_message(input: &mut impl BufRead) -> io::Result<Option<String>> {
+    let mut length = None;
+    loop {
+        let mut header = String::new();
+        if input.read_line(&mut header)? == 0 {
+            return Ok(None);
+        }
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("Content-Length") {
+                length = value.trim().parse().ok();
+            }
+        }
+    }
+    let Some(length) = length else {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "missing Content-Length header",
+        ));
+    };
+    if length > MAX_MESSAGE {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "Content-Length of {} is over the limit of {} bytes",
+                length, MAX_MESSAGE
+            ),
+        ));
+    }
+    let mut content = vec![0; length];
+    input.read_exact(&mut content)?;
+    String::from_utf8(content)
+        .map(Some)
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+}
+
+fn write_message(output: &mut impl Write, message: &Json) -> io::Result<()> {
+    let content = message.to_string();
+    write!(
+        output,
+        "Content-Length: {}\r\n\r\n{}",
+        content.len(),
+        content
+    )?;
+    output.flush()
+}
+
+/// Serves the client on the other end of `input` and `output` until it
+/// sends `exit` or closes `input`. Returns whether it asked to shut down
+/// first, as it should have.
+pub fn serve(mut input: impl BufRead, mut output: impl Write) -> io::Result<bool> {
+    let mut server = Server::new();
+    while let Some(content) = read_message(&mut input)? {
+        let replies = match Json::parse(&content) {
+            Ok(message) if message.get("method").and_then(Json::as_str) == Some("exit") => {
+                return Ok(server.shut_down);
+            }
+            Ok(message) => server.handle(&message),
+            Err(err) => vec![Json::object([
+                ("jsonrpc", Json::str("2.0")),
+                ("id", Json::Null),
+                (
+                    "error",
+                    Json::object([("code", PARSE_ERROR.into()), ("message", Json::Str(err))]),
+                ),
+            ])],
+        };
+        for reply in &replies {
+            write_message(&mut output, reply)?;
+        }
+    }
+    Ok(server.shut_down)
+}
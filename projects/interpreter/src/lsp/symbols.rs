@@ -0,0 +1,326 @@
+use crate::backend::Arity;
+use crate::interpreter::func::builtin::get_fn_builtin;
+use crate::lexer::Span;
+use crate::parser::parser_ds::*;
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Kind {
+    Variable,
+    Parameter,
+    Function,
+    Class,
+    Native,
+}
+
+/// A name the program declares, or one of the native functions.
+#[derive(Debug, Clone)]
+pub struct Definition {
+    pub name: String,
+    pub kind: Kind,
+    /// Where the name is declared, `None` for natives.
+    pub span: Option<Span>,
+    /// The parameters of the function it names, when it is one: those of
+    /// `init` for a class, of the lambda a variable is declared with.
+    pub params: Option<Vec<String>>,
+    pub arity: Option<Arity>,
+    /// The offsets between which the name can be used.
+    pub visible: (usize, usize),
+}
+
+/// The names declared in a source and every use of them, bound like the
+/// resolver binds them.
+#[derive(Debug, Default)]
+pub struct Symbols {
+    pub definitions: Vec<Definition>,
+    /// Every occurrence of a name with the definition it refers to, the
+    /// declarations included.
+    pub references: Vec<(Span, usize)>,
+}
+
+impl Symbols {
+    pub fn new(source: &str, stmts: &[ExprAst]) -> Self {
+        let mut walker = Walker {
+            source,
+            symbols: Symbols::default(),
+            scopes: Vec::new(),
+            globals: HashMap::new(),
+            unbound: Vec::new(),
+        };
+        for native in get_fn_builtin() {
+            let id = walker.symbols.definitions.len();
+            walker.symbols.definitions.push(Definition {
+                name: native.name.clone(),
+                kind: Kind::Native,
+                span: None,
+                params: None,
+                arity: Some(native.arity()),
+                visible: (0, usize::MAX),
+            });
+            walker.globals.insert(native.name.clone(), id);
+        }
+        for stmt in stmts {
+            walker.expr(stmt);
+        }
+        // Globals are looked up when the code using them runs, they can be
+        // declared after it.
+        for (span, name) in std::mem::take(&mut walker.unbound) {
+            if let Some(&id) = walker.globals.get(&name) {
+                walker.symbols.references.push((span, id));
+            }
+        }
+        walker
+            .symbols
+            .references
+            .sort_by_key(|(span, _)| span.start);
+        walker.symbols
+    }
+
+    /// The definition of the name at `offset`, a cursor on it or right after
+    /// it.
+    pub fn definition_at(&self, offset: usize) -> Option<usize> {
+        self.references
+            .iter()
+            .find(|(span, _)| span.start <= offset && offset <= span.end)
+            .map(|&(_, id)| id)
+    }
+
+    /// The occurrences of the definition `id`, in source order.
+    pub fn references_to(&self, id: usize) -> impl Iterator<Item = Span> + '_ {
+        self.references
+            .iter()
+            .filter(move |&&(_, def)| def == id)
+            .map(|&(span, _)| span)
+    }
+
+    /// The names that can be used at `offset`, those of the innermost scope
+    /// hiding the others.
+    pub fn in_scope(&self, offset: usize) -> Vec<&Definition> {
+        let mut names: HashMap<&str, &Definition> = HashMap::new();
+        for def in &self.definitions {
+            if def.visible.0 > offset || offset > def.visible.1 {
+                continue;
+            }
+            match names.get(def.name.as_str()) {
+                Some(other) if other.visible.0 > def.visible.0 => (),
+                _ => {
+                    names.insert(&def.name, def);
+                }
+            }
+        }
+        let mut defs: Vec<_> = names.into_values().collect();
+        defs.sort_by(|a, b| a.name.cmp(&b.name));
+        defs
+    }
+}
+
+struct Walker<'a> {
+    source: &'a str,
+    symbols: Symbols,
+    /// The local scopes, with the offset where each ends.
+    scopes: Vec<(HashMap<String, usize>, usize)>,
+    globals: HashMap<String, usize>,
+    /// Uses of names not declared in an enclosing local scope.
+    unbound: Vec<(Span, String)>,
+}
+
+fn params(args: &[ExprAst]) -> Vec<String> {
+    args.iter().map(|arg| arg.to_string()).collect()
+}
+
+impl Walker<'_> {
+    /// The span of the first `name` in `span`, that of a declaration whose
+    /// name is not a node of its own.
+    fn name_span(&self, span: Span, name: &str) -> Span {
+        let text = self.source.get(span.start..span.end).unwrap_or_default();
+        let word = |c: char| c.is_ascii_alphanumeric() || c == '_';
+        let found = text.match_indices(name).find(|&(at, _)| {
+            !text[..at].ends_with(word) && !text[at + name.len()..].starts_with(word)
+        });
+        match found {
+            Some((at, _)) => Span {
+                start: span.start + at,
+                end: span.start + at + name.len(),
+                ..span
+            },
+            None => span,
+        }
+    }
+
+    fn declare(&mut self, name: &str, span: Span, kind: Kind, params: Option<Vec<String>>) {
+        let id = self.symbols.definitions.len();
+        let end = self.scopes.last().map_or(usize::MAX, |(_, end)| *end);
+        let start = if self.scopes.is_empty() {
+            0
+        } else {
+            span.start
+        };
+        self.symbols.definitions.push(Definition {
+            name: name.to_string(),
+            kind,
+            span: Some(span),
+            arity: params.as_ref().map(|p| Arity::Fixed(p.len())),
+            params,
+            visible: (start, end),
+        });
+        self.symbols.references.push((span, id));
+        match self.scopes.last_mut() {
+            Some((scope, _)) => {
+                scope.insert(name.to_string(), id);
+            }
+            // The first declaration of a global wins, as a native's name is
+            // taken by the program's.
+            None => match self.globals.get(name) {
+                Some(&other) if self.symbols.definitions[other].kind != Kind::Native => (),
+                _ => {
+                    self.globals.insert(name.to_string(), id);
+                }
+            },
+        }
+    }
+
+    fn bind(&mut self, name: &str, span: Span) {
+        let local = self.scopes.iter().rev().find_map(|(s, _)| s.get(name));
+        match local {
+            Some(&id) => self.symbols.references.push((span, id)),
+            None => self.unbound.push((span, name.to_string())),
+        }
+    }
+
+    fn scoped(&mut self, end: usize, f: impl FnOnce(&mut Self)) {
+        self.scopes.push((HashMap::new(), end));
+        f(self);
+        self.scopes.pop();
+    }
+
+    /// The parameters and body of a function, in the same scope.
+    fn function(&mut self, f: &FnDeclExprAst) {
+        self.scoped(f.span.end, |w| {
+            for arg in &f.args {
+                w.declare(&arg.to_string(), arg.span(), Kind::Parameter, None);
+            }
+            match f.body.as_ref() {
+                ExprAst::BlockAst(b) => w.exprs(&b.cont),
+                body => w.expr(body),
+            }
+        });
+    }
+
+    fn exprs(&mut self, exprs: &[ExprAst]) {
+        for expr in exprs {
+            self.expr(expr);
+        }
+    }
+
+    fn expr(&mut self, expr: &ExprAst) {
+        match expr {
+            ExprAst::BoolAst(_)
+            | ExprAst::NumAst(_)
+            | ExprAst::StrAst(_)
+            | ExprAst::NilAst(_)
+            | ExprAst::OpAst(_)
+            | ExprAst::ThisAst(_)
+            | ExprAst::SuperAst(_)
+            | ExprAst::ImportAst(_)
+            | ExprAst::BreakAst(_)
+            | ExprAst::ContinueAst(_) => (),
+            ExprAst::IdenAst(id) => self.bind(&id.val, id.span),
+            ExprAst::ParAst(p) => self.expr(&p.val),
+            ExprAst::NegativeAst(n) => self.expr(&n.rhs),
+            ExprAst::UnaryAst(u) => self.expr(&u.rhs),
+            ExprAst::BinaryAst(b) => {
+                self.expr(&b.lhs);
+                self.expr(&b.rhs);
+            }
+            ExprAst::PrintAst(p) => self.expr(&p.expr),
+            ExprAst::IfAst(i) => {
+                self.expr(&i.cond);
+                self.expr(&i.then);
+                self.expr(&i.default);
+            }
+            ExprAst::WhileAst(w) => {
+                self.expr(&w.cond);
+                self.expr(&w.then);
+            }
+            ExprAst::ForAst(f) => self.scoped(f.span.end, |w| {
+                w.expr(&f.lhs);
+                w.expr(&f.cond);
+                w.expr(&f.rhs);
+                w.expr(&f.then);
+            }),
+            ExprAst::AssignAst(a) => {
+                self.expr(&a.rhs);
+                self.bind(&a.lhs.val, a.lhs.span);
+            }
+            ExprAst::VarAssignAst(a) => {
+                let params = match a.rhs.as_ref() {
+                    ExprAst::LambdaAst(f) => Some(params(&f.args)),
+                    _ => None,
+                };
+                self.declare(&a.lhs.val, a.lhs.span, Kind::Variable, params);
+                self.expr(&a.rhs);
+            }
+            ExprAst::BlockAst(b) => self.scoped(b.span.end, |w| w.exprs(&b.cont)),
+            ExprAst::FnCallAst(f) => {
+                self.expr(&f.lhs);
+                self.exprs(&f.args);
+            }
+            ExprAst::FnDeclAst(f) => {
+                let span = self.name_span(f.span, &f.name);
+                self.declare(&f.name, span, Kind::Function, Some(params(&f.args)));
+                self.function(f);
+            }
+            ExprAst::LambdaAst(f) => self.function(f),
+            ExprAst::RetAst(r) => self.expr(&r.val),
+            ExprAst::ClassDeclAst(c) => {
+                let span = self.name_span(c.span, &c.name);
+                let init = c.methods.iter().find(|m| m.name == "init");
+                let params = init.map_or_else(Vec::new, |m| params(&m.args));
+                self.declare(&c.name, span, Kind::Class, Some(params));
+                if let Some(superclass) = &c.superclass {
+                    self.expr(superclass);
+                }
+                for method in &c.methods {
+                    self.function(method);
+                }
+            }
+            ExprAst::ThrowAst(t) => self.expr(&t.val),
+            ExprAst::TryAst(t) => {
+                self.expr(&t.body);
+                if let Some(catch) = &t.catch {
+                    self.scoped(catch.body.span().end, |w| {
+                        w.declare(&catch.name.val, catch.name.span, Kind::Variable, None);
+                        match catch.body.as_ref() {
+                            ExprAst::BlockAst(b) => w.exprs(&b.cont),
+                            body => w.expr(body),
+                        }
+                    });
+                }
+                if let Some(finally) = &t.finally {
+                    self.expr(finally);
+                }
+            }
+            ExprAst::ListAst(l) => self.exprs(&l.items),
+            ExprAst::MapAst(m) => {
+                for (key, val) in &m.entries {
+                    self.expr(key);
+                    self.expr(val);
+                }
+            }
+            ExprAst::IndexAst(i) => {
+                self.expr(&i.object);
+                self.expr(&i.index);
+            }
+            ExprAst::SetIndexAst(s) => {
+                self.expr(&s.object);
+                self.expr(&s.index);
+                self.expr(&s.val);
+            }
+            ExprAst::GetAst(g) => self.expr(&g.object),
+            ExprAst::SetAst(s) => {
+                self.expr(&s.val);
+                self.expr(&s.object);
+            }
+        }
+    }
+}
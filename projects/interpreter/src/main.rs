@@ -1,53 +1,257 @@
 use std::env;
 use std::fs;
-use std::io::{self, Write};
+use std::io;
+use std::path::Path;
 use std::process::exit;
+use std::str::FromStr;
+use std::thread;
+use std::time::Duration;
 
-mod interpreter;
-mod lexer;
-mod parser;
-use interpreter::core::*;
-use parser::core::parse_token;
-use parser::core::scan_token;
-use parser::parser_ds::ParserOptions;
-mod error;
+mod debugger;
+mod golden;
+mod repl;
+use codecrafters_interpreter::backend::{new_backend, Backend, BackendKind};
+use codecrafters_interpreter::interpreter::core::Interpreter;
+use codecrafters_interpreter::limits::Limits;
+use codecrafters_interpreter::lsp;
+use codecrafters_interpreter::parser::core::opti_run;
+use codecrafters_interpreter::parser::core::parse_token;
+use codecrafters_interpreter::parser::core::scan_token;
+use codecrafters_interpreter::parser::format::format_source;
+use codecrafters_interpreter::parser::json::{ast_json, tokens_json};
+use codecrafters_interpreter::parser::parser_ds::ParserOptions;
+use debugger::Debugger;
+use repl::Repl;
 use std::cell::RefCell;
 use std::rc::Rc;
 
+const OPTIONS: &str = "\
+Options:
+  --backend=vm|tree       execution backend, tree by default
+  --format=text|json      output of tokenize and parse, text by default
+  --check                 with fmt, list the files it would change and fail
+  --write                 with fmt, rewrite the files instead of printing them
+  --max-steps=N           stop after N evaluation steps
+  --max-call-depth=N      raise \"Stack overflow.\" past N nested calls
+  --max-time=SECONDS      stop after this much wall-clock time
+  --max-allocations=N     stop after allocating N units: objects, string
+                          bytes and list or map literal items";
+
+/// How `tokenize` and `parse` print their result.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Format {
+    Text,
+    Json,
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(Self::Text),
+            "json" => Ok(Self::Json),
+            _ => Err(format!("Unknown format: {}", s)),
+        }
+    }
+}
+
+/// Exit code of a command line that could not be understood.
+const EX_USAGE: i32 = 64;
+
+fn usage(program: &str) -> String {
+    format!(
+        "Usage: {0} tokenize|parse|evaluate|run|debug <filename> [options]\n       {0} test <file or directory>... [options]\n       {0} fmt <filename>... [--check|--write]\n       {0} repl [options]\n       {0} lsp\n\n{1}",
+        program, OPTIONS
+    )
+}
+
+fn parse_value<T: FromStr>(name: &str, value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("Invalid value for {}: {}", name, value))
+}
+
+/// Native stack of the thread running the program, enough for the
+/// tree-walking interpreter to reach `CALL_DEPTH`.
+const STACK_SIZE: usize = 256 * 1024 * 1024;
+
+/// Calls a program can nest unless `--max-call-depth` says otherwise,
+/// deeper than the library allows on a default stack.
+const CALL_DEPTH: usize = 1000;
+
 fn main() {
-    let args: Vec<String> = env::args().collect();
+    let runner = thread::Builder::new()
+        .stack_size(STACK_SIZE)
+        .spawn(cli)
+        .expect("failed to start the interpreter thread");
+    if runner.join().is_err() {
+        exit(101);
+    }
+}
+
+/// Formats the files at `paths`, printing them unless `write` or `check` is
+/// set. Returns the exit code: 65 if one could not be parsed, 1 if `check`
+/// found one to reformat.
+fn fmt(paths: &[String], check: bool, write: bool) -> i32 {
+    let mut code = 0;
+    for path in paths {
+        let Ok(source) = fs::read_to_string(path) else {
+            eprintln!("Failed to read file {}", path);
+            code = 65;
+            continue;
+        };
+        let formatted = match format_source(&source) {
+            Ok(formatted) => formatted,
+            Err(err) => {
+                code = err.to_i32();
+                continue;
+            }
+        };
+        if check {
+            if formatted != source {
+                println!("Would reformat: {}", path);
+                code = code.max(1);
+            }
+        } else if write {
+            if formatted != source {
+                if let Err(err) = fs::write(path, formatted) {
+                    eprintln!("Failed to write file {}: {}", path, err);
+                    code = 65;
+                }
+            }
+        } else {
+            print!("{}", formatted);
+        }
+    }
+    code
+}
+
+fn cli() {
+    let (flags, args): (Vec<String>, Vec<String>) =
+        env::args().partition(|arg| arg.starts_with("--"));
+    let mut backend = BackendKind::Tree;
+    let mut limits = Limits {
+        call_depth: Some(CALL_DEPTH),
+        ..Limits::default()
+    };
+    let mut format = Format::Text;
+    let (mut check, mut write) = (false, false);
+    for flag in &flags {
+        let (name, value) = flag.split_once('=').unwrap_or((flag, ""));
+        let r = match name {
+            "--backend" => value.parse().map(|kind| backend = kind),
+            "--format" => value.parse().map(|f| format = f),
+            "--check" => {
+                check = true;
+                Ok(())
+            }
+            "--write" => {
+                write = true;
+                Ok(())
+            }
+            "--max-steps" => parse_value(name, value).map(|n| limits.steps = Some(n)),
+            "--max-call-depth" => parse_value(name, value).map(|n| limits.call_depth = Some(n)),
+            "--max-time" => parse_value(name, value)
+                .and_then(|secs| {
+                    Duration::try_from_secs_f64(secs)
+                        .map_err(|_| format!("Invalid value for {}: {}", name, value))
+                })
+                .map(|time| limits.time = Some(time)),
+            "--max-allocations" => parse_value(name, value).map(|n| limits.allocations = Some(n)),
+            _ => Err(format!("Unknown option: {}", flag)),
+        };
+        if let Err(err) = r {
+            eprintln!("{}\n\n{}", err, usage(&args[0]));
+            exit(EX_USAGE);
+        }
+    }
+
+    if args.get(1).map(String::as_str) == Some("repl") {
+        Repl::new(backend, limits).run();
+        return;
+    }
+    if args.get(1).map(String::as_str) == Some("lsp") {
+        // Exits with 1 when the client did not shut the server down first.
+        match lsp::serve(io::stdin().lock(), io::stdout().lock()) {
+            Ok(true) => return,
+            Ok(false) => exit(1),
+            Err(err) => {
+                eprintln!("{}", err);
+                exit(1);
+            }
+        }
+    }
     if args.len() < 3 {
-        writeln!(io::stderr(), "Usage: {} tokenize <filename>", args[0]).unwrap();
+        eprintln!("{}", usage(&args[0]));
+        exit(EX_USAGE);
+    }
+    if args[1] == "test" {
+        if !golden::run(&args[2..], &flags) {
+            exit(1);
+        }
         return;
     }
+    if args[1] == "fmt" {
+        exit(fmt(&args[2..], check, write));
+    }
 
     let command = &args[1];
     let filename = &args[2];
 
     let file_contents = fs::read_to_string(filename).unwrap_or_else(|_| {
-        writeln!(io::stderr(), "Failed to read file {}", filename).unwrap();
+        eprintln!("Failed to read file {}", filename);
         String::new()
     });
     match command.as_str() {
         "run" => {
-            let interpreter = Rc::new(RefCell::new(Interpreter::new()));
-            if let Err(err) = Interpreter::exec(interpreter, file_contents) {
+            let backend = new_backend(backend);
+            backend.borrow_mut().set_script(Path::new(filename));
+            backend.borrow_mut().set_limits(limits);
+            if let Err(err) = opti_run(file_contents, Some(backend), ParserOptions::RUN) {
+                exit(err.to_i32());
+            }
+        }
+        "debug" => {
+            if backend != BackendKind::Tree {
+                eprintln!("The debugger only runs on the tree backend.");
+                exit(1);
+            }
+            let mut interpreter = Interpreter::new();
+            interpreter.set_script(Path::new(filename));
+            interpreter.set_limits(limits);
+            interpreter.set_debugger(Box::new(Debugger::new(&file_contents)));
+            let backend = Rc::new(RefCell::new(interpreter));
+            if let Err(err) = opti_run(file_contents, Some(backend), ParserOptions::RUN) {
                 exit(err.to_i32());
             }
         }
         "evaluate" => {
-            let interpreter = Rc::new(RefCell::new(Interpreter::new()));
-            if let Err(err) = Interpreter::eval(interpreter, file_contents) {
+            let backend = new_backend(backend);
+            backend.borrow_mut().set_script(Path::new(filename));
+            backend.borrow_mut().set_limits(limits);
+            if let Err(err) = parse_token(file_contents, Some(backend), ParserOptions::EVALUATE) {
+                exit(err.to_i32());
+            }
+        }
+        "parse" if format == Format::Json => {
+            let (doc, r) = ast_json(&file_contents);
+            println!("{}", doc);
+            if let Err(err) = r {
+                exit(err.to_i32());
+            }
+        }
+        "tokenize" if format == Format::Json => {
+            let (doc, r) = tokens_json(&file_contents);
+            println!("{}", doc);
+            if let Err(err) = r {
                 exit(err.to_i32());
             }
         }
         "parse" => {
             if !file_contents.is_empty() {
-                match parse_token(file_contents, None, ParserOptions::DEBUG) {
-                    Err(err) => {
-                        exit(err.to_i32());
-                    }
-                    _ => (),
+                if let Err(err) = parse_token(file_contents, None, ParserOptions::DEBUG) {
+                    exit(err.to_i32());
                 }
             } else {
                 println!("EOF  null"); // Placeholder, remove this line when implementing the scanner
@@ -58,10 +262,7 @@ fn main() {
                 let (tokens, err) = scan_token(file_contents);
 
                 for token in &tokens {
-                    let s = token.to_string();
-                    if s != "" {
-                        println!("{}", token.to_string());
-                    }
+                    println!("{}", token.token);
                 }
                 if err {
                     exit(65);
@@ -71,8 +272,8 @@ fn main() {
             }
         }
         _ => {
-            writeln!(io::stderr(), "Unknown command: {}", command).unwrap();
-            return;
+            eprintln!("Unknown command: {}\n\n{}", command, usage(&args[0]));
+            exit(EX_USAGE);
         }
     }
 }
@@ -0,0 +1,100 @@
+use std::collections::HashMap;
+use std::fmt;
+
+/// A map key. Only strings, numbers and booleans can be keys; numbers are
+/// compared by value, so `0` and `-0` are the same key.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum MapKey {
+    Bool(bool),
+    Num(u64),
+    Str(String),
+}
+
+pub const KEY_ERROR: &str = "Map keys must be strings, numbers or booleans.";
+
+impl MapKey {
+    pub fn num(n: f64) -> Self {
+        // Adding zero turns -0 into 0.
+        MapKey::Num((n + 0.0).to_bits())
+    }
+}
+
+impl fmt::Display for MapKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MapKey::Bool(b) => write!(f, "{}", b),
+            MapKey::Num(n) => write!(f, "{}", f64::from_bits(*n)),
+            MapKey::Str(s) => write!(f, "{}", s),
+        }
+    }
+}
+
+/// Map entries, kept in insertion order. Both backends store their maps in
+/// it so they iterate and print them the same way.
+#[derive(Debug, Clone)]
+pub struct OrderedMap<V> {
+    entries: Vec<(MapKey, V)>,
+    index: HashMap<MapKey, usize>,
+}
+
+impl<V> OrderedMap<V> {
+    pub fn new() -> Self {
+        Self {
+            entries: Vec::new(),
+            index: HashMap::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn get(&self, key: &MapKey) -> Option<&V> {
+        self.index.get(key).map(|&i| &self.entries[i].1)
+    }
+
+    pub fn contains_key(&self, key: &MapKey) -> bool {
+        self.index.contains_key(key)
+    }
+
+    /// Overwriting a key keeps its place in the iteration order.
+    pub fn insert(&mut self, key: MapKey, val: V) {
+        match self.index.get(&key) {
+            Some(&i) => self.entries[i].1 = val,
+            None => {
+                self.index.insert(key.clone(), self.entries.len());
+                self.entries.push((key, val));
+            }
+        }
+    }
+
+    pub fn remove(&mut self, key: &MapKey) -> Option<V> {
+        let i = self.index.remove(key)?;
+        let (_, val) = self.entries.remove(i);
+        for (k, _) in &self.entries[i..] {
+            *self.index.get_mut(k).unwrap() -= 1;
+        }
+        Some(val)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&MapKey, &V)> {
+        self.entries.iter().map(|(k, v)| (k, v))
+    }
+}
+
+impl<V> Default for OrderedMap<V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<V: fmt::Display> fmt::Display for OrderedMap<V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let entries: Vec<String> = self.iter().map(|(k, v)| format!("{}: {}", k, v)).collect();
+        write!(f, "{{{}}}", entries.join(", "))
+    }
+}
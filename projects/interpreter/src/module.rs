@@ -0,0 +1,84 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::parser::core::compile;
+use crate::parser::parser_ds::ExprAst;
+
+/// What importing a module requires from the backend.
+pub enum Import<M> {
+    /// The module already ran, here is its value.
+    Loaded(M),
+    /// The module's statements, to run in a fresh global namespace before
+    /// calling [`Loader::finish`], or [`Loader::abort`] if they fail.
+    Run(Vec<ExprAst>),
+}
+
+/// Finds the files `import` statements refer to, and remembers the modules
+/// already loaded so that each one runs once. Both backends keep one, with
+/// their own module values.
+#[derive(Debug)]
+pub struct Loader<M> {
+    /// The files being run, outermost first, with the name they were
+    /// imported under. Imports resolve relative to the last one.
+    running: Vec<(PathBuf, String)>,
+    loaded: HashMap<PathBuf, M>,
+}
+
+impl<M: Clone> Loader<M> {
+    pub fn new() -> Self {
+        Self {
+            running: Vec::new(),
+            loaded: HashMap::new(),
+        }
+    }
+
+    /// Records the file the top-level script was read from. Without one,
+    /// imports resolve relative to the working directory.
+    pub fn set_script(&mut self, path: &Path) {
+        let full = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+        self.running = vec![(full, path.display().to_string())];
+    }
+
+    pub fn start(&mut self, path: &str) -> Result<Import<M>, String> {
+        let dir = self
+            .running
+            .last()
+            .and_then(|(file, _)| file.parent())
+            .unwrap_or(Path::new(""));
+        let full = fs::canonicalize(dir.join(path))
+            .map_err(|err| format!("Can't open module '{}': {}.", path, err))?;
+        if let Some(module) = self.loaded.get(&full) {
+            return Ok(Import::Loaded(module.clone()));
+        }
+        if let Some(i) = self.running.iter().position(|(file, _)| *file == full) {
+            let mut cycle: Vec<&str> = self.running[i..].iter().map(|(_, n)| n.as_str()).collect();
+            cycle.push(path);
+            return Err(format!("Import cycle: {}.", cycle.join(" -> ")));
+        }
+        let source = fs::read_to_string(&full)
+            .map_err(|err| format!("Can't open module '{}': {}.", path, err))?;
+        let stmts =
+            compile(&source).map_err(|err| format!("Can't compile module '{}':\n{}", path, err))?;
+        self.running.push((full, path.to_string()));
+        Ok(Import::Run(stmts))
+    }
+
+    /// Caches `module` as the value of the module [`Loader::start`] returned.
+    pub fn finish(&mut self, module: M) {
+        if let Some((full, _)) = self.running.pop() {
+            self.loaded.insert(full, module);
+        }
+    }
+
+    /// Forgets the module that failed to run, a later import retries it.
+    pub fn abort(&mut self) {
+        self.running.pop();
+    }
+}
+
+impl<M: Clone> Default for Loader<M> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -1,34 +1,48 @@
 use super::parser_ds::*;
+use crate::backend::Backend;
 use crate::error::*;
-use crate::interpreter::core::*;
-use crate::interpreter::evaluate::*;
-use crate::lexer::{read_token, Token};
+use crate::lexer::{read_token, ReadTokenStatus, Span, SpannedToken, Token};
+use crate::resolver::Resolver;
 use std::cell::RefCell;
-use std::process::exit;
 use std::rc::Rc;
 
+/// The most arguments a call passes and parameters a function takes, which
+/// the bytecode encodes in a byte.
+const MAX_ARGS: usize = 255;
+
 struct Parser {
+    source: String,
     it: std::vec::IntoIter<Token>,
-    line_nb: i32,
-    err: bool,
+    spans: Vec<Span>,
+    diagnostics: Vec<Diagnostic>,
+    /// Set after a syntax error until the next statement boundary, so that
+    /// errors cascading from the first one are not reported.
+    panicking: bool,
+    /// Set when the next primary expression must be a value, where `{` opens
+    /// a map rather than a block.
+    value_expected: bool,
     options: ParserOptions,
-    interpreter: Option<Rc<RefCell<Interpreter>>>,
-    warn: Option<Token>,
+    backend: Option<Rc<RefCell<dyn Backend>>>,
 }
 
 impl Parser {
     fn new(
-        it: std::vec::IntoIter<Token>,
-        interpreter: Option<Rc<RefCell<Interpreter>>>,
+        source: &str,
+        tokens: Vec<SpannedToken>,
+        backend: Option<Rc<RefCell<dyn Backend>>>,
         options: ParserOptions,
     ) -> Self {
+        let (tokens, spans): (Vec<_>, Vec<_>) =
+            tokens.into_iter().map(|t| (t.token, t.span)).unzip();
         Self {
-            it,
-            line_nb: 0,
-            err: false,
+            source: source.to_string(),
+            it: tokens.into_iter(),
+            spans,
+            diagnostics: Vec::new(),
+            panicking: false,
+            value_expected: false,
             options,
-            interpreter,
-            warn: None,
+            backend,
         }
     }
 
@@ -36,14 +50,38 @@ impl Parser {
         self.it.clone().peekable().peek().cloned()
     }
 
+    /// Span of the token `get_curr_token` returns.
+    fn span(&self) -> Span {
+        let i = self.spans.len() - self.it.len();
+        self.spans.get(i).copied().unwrap_or_default()
+    }
+
+    /// Span of the last token consumed.
+    fn prev_span(&self) -> Span {
+        let i = self.spans.len() - self.it.len();
+        i.checked_sub(1)
+            .and_then(|i| self.spans.get(i))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Span from `start` to the end of the last token consumed.
+    fn span_from(&self, start: Span) -> Span {
+        start.to(self.prev_span())
+    }
+
     fn parse_op(&self, t: Token) -> ExprAst {
-        ExprAst::OpAst(OperExprAst { val: t })
+        ExprAst::OpAst(OperExprAst {
+            val: t,
+            span: self.prev_span(),
+        })
     }
 
     fn parse_bool(&self, t: Token) -> ExprAst {
+        let span = self.prev_span();
         let b = match t {
-            Token::True => BoolExprAst { val: true },
-            Token::False => BoolExprAst { val: false },
+            Token::True => BoolExprAst { val: true, span },
+            Token::False => BoolExprAst { val: false, span },
             _ => unreachable!(),
         };
         ExprAst::BoolAst(b)
@@ -51,75 +89,194 @@ impl Parser {
 
     fn parse_assign(&mut self, lhs: ExprAst) -> Option<ExprAst> {
         self.get_next_token();
-        let rhs = self.parse_expr()?;
+        let equal = self.prev_span();
+        let rhs = self.parse_value()?;
+
+        if let ExprAst::GetAst(get) = lhs {
+            return Some(ExprAst::SetAst(SetExprAst {
+                object: get.object,
+                name: get.name,
+                span: get.span.to(rhs.span()),
+                val: Box::new(rhs),
+            }));
+        }
+        if let ExprAst::IndexAst(ix) = lhs {
+            return Some(ExprAst::SetIndexAst(SetIndexExprAst {
+                object: ix.object,
+                index: ix.index,
+                span: ix.span.to(rhs.span()),
+                val: Box::new(rhs),
+            }));
+        }
 
         if self.get_curr_token() == Some(Token::Equal) {
-            let next_rhs = self.parse_assign(lhs.clone())?;
+            let next_rhs = self.parse_assign(lhs)?;
             Some(ExprAst::AssignAst(AssignExprAst {
-                lhs: Box::new(rhs),
+                span: rhs.span().to(next_rhs.span()),
+                lhs: self.assign_target(rhs, equal)?,
                 rhs: Box::new(next_rhs),
             }))
         } else {
             Some(ExprAst::AssignAst(AssignExprAst {
-                lhs: Box::new(lhs),
+                span: lhs.span().to(rhs.span()),
+                lhs: self.assign_target(lhs, equal)?,
                 rhs: Box::new(rhs),
             }))
         }
     }
 
+    fn assign_target(&mut self, target: ExprAst, equal: Span) -> Option<IdentExprAst> {
+        match target {
+            ExprAst::IdenAst(id) => Some(id),
+            _ => self.error_at(equal, "Invalid assignment target."),
+        }
+    }
+
+    /// Parses the operand of a `-`, which binds tighter than binary
+    /// operators.
     fn parse_negative_expr(&mut self) -> Option<ExprAst> {
+        let start = self.prev_span();
+        self.value_expected = true;
+        let rhs = self.parse_operand()?;
         Some(ExprAst::NegativeAst(NegativeExprAst {
-            rhs: Box::new(self.parse_expr().unwrap()),
+            span: start.to(rhs.span()),
+            rhs: Box::new(rhs),
         }))
     }
 
     fn parse_print_expr(&mut self) -> Option<ExprAst> {
-        let o = self.parse_expr()?;
+        let start = self.prev_span();
+        let o = self.parse_value()?;
 
-        let p = PrintExprAst { expr: Box::new(o) };
+        let p = PrintExprAst {
+            span: start.to(o.span()),
+            expr: Box::new(o),
+        };
 
         Some(ExprAst::PrintAst(p))
     }
 
     fn parse_while_expr(&mut self) -> Option<ExprAst> {
-        assert_eq!(self.get_next_token(), Some(Token::LeftParen));
-        let cond = self.parse_expr()?;
-        assert_eq!(self.get_next_token(), Some(Token::RightParen));
-        self.unexpect_var_token();
-        let then = self.parse_expr().unwrap();
-
-        if self.get_curr_token() == Some(Token::Semicolon) {
-            self.get_next_token();
-        }
+        let start = self.prev_span();
+        self.consume(Token::LeftParen, "Expect '(' after 'while'.")?;
+        let cond = self.parse_value()?;
+        self.consume(Token::RightParen, "Expect ')' after condition.")?;
+        self.unexpect_var_token()?;
+        let then = self.parse_expr()?;
+        self.end_statement(&then)?;
+        let span = start.to(then.span());
 
         let while_expr = WhileExprAst {
             cond: Box::new(cond),
             then: Box::new(then),
+            span,
         };
-        self.warn = None;
         Some(ExprAst::WhileAst(while_expr))
     }
 
-    fn unexpect_token(&self, token: Token) {
-        let curr_token = self.get_curr_token().unwrap();
-        if curr_token == token {
-            dbg!();
-            self.panic_on_wrong_token(curr_token);
+    fn unexpect_token(&mut self, token: Token) -> Option<()> {
+        if self.get_curr_token()? == token {
+            return self.error_at(self.span(), "Expect expression.");
+        }
+        Some(())
+    }
+
+    /// Records a syntax error at `span`, unless the parser is still
+    /// recovering from a previous one, and returns `None` so that callers can
+    /// bail out with `?`.
+    fn error_at<T>(&mut self, span: Span, msg: &str) -> Option<T> {
+        if !self.panicking {
+            self.panicking = true;
+            let lexeme = self.source.get(span.start..span.end);
+            self.diagnostics.push(Diagnostic {
+                message: msg.to_string(),
+                span,
+                lexeme: lexeme
+                    .filter(|_| span.start < self.source.len())
+                    .map(str::to_string),
+            });
+        }
+        None
+    }
+
+    /// Consumes the next token if it is `expected`, reports `msg` otherwise.
+    fn consume(&mut self, expected: Token, msg: &str) -> Option<()> {
+        if self.get_curr_token()? != expected {
+            return self.error_at(self.span(), msg);
         }
+        self.get_next_token();
+        Some(())
     }
 
-    fn panic_on_wrong_token(&self, resp: Token) -> ! {
-        eprintln!(
-            "[line {}] Error at '{}': Expect expression.",
-            self.line_nb,
-            resp.to_usefull_str()
-        );
-        exit(65);
+    /// Consumes the `;` ending `stmt`, which statements ending with a block
+    /// do without.
+    fn end_statement(&mut self, stmt: &ExprAst) -> Option<()> {
+        let msg = match stmt {
+            ExprAst::IfAst(_)
+            | ExprAst::WhileAst(_)
+            | ExprAst::ForAst(_)
+            | ExprAst::BlockAst(_)
+            | ExprAst::FnDeclAst(_)
+            | ExprAst::ClassDeclAst(_)
+            | ExprAst::TryAst(_) => return Some(()),
+            ExprAst::PrintAst(_) => "Expect ';' after value.",
+            ExprAst::VarAssignAst(_) => "Expect ';' after variable declaration.",
+            ExprAst::RetAst(_) => "Expect ';' after return value.",
+            ExprAst::BreakAst(_) => "Expect ';' after 'break'.",
+            ExprAst::ContinueAst(_) => "Expect ';' after 'continue'.",
+            ExprAst::ThrowAst(_) => "Expect ';' after thrown value.",
+            ExprAst::ImportAst(_) => "Expect ';' after import.",
+            _ => "Expect ';' after expression.",
+        };
+        match self.get_curr_token()? {
+            Token::Semicolon => {
+                self.get_next_token();
+                Some(())
+            }
+            // A lone expression, as `evaluate` and the repl read them.
+            Token::Eof if self.open_end() => Some(()),
+            _ => self.error_at(self.span(), msg),
+        }
+    }
+
+    fn open_end(&self) -> bool {
+        !self.options.contains(ParserOptions::RUN)
+            || self
+                .options
+                .intersects(ParserOptions::ECHO | ParserOptions::OPEN_END)
+    }
+
+    /// Skips tokens up to the start of the next statement after a syntax
+    /// error.
+    fn synchronize(&mut self) {
+        self.panicking = false;
+        while let Some(token) = self.get_curr_token() {
+            match token {
+                Token::Eof
+                | Token::Class
+                | Token::Fun
+                | Token::Var
+                | Token::For
+                | Token::If
+                | Token::While
+                | Token::Print
+                | Token::Return
+                | Token::Throw
+                | Token::Try => return,
+                Token::Semicolon => {
+                    self.get_next_token();
+                    return;
+                }
+                _ => {
+                    self.get_next_token();
+                }
+            }
+        }
     }
 
     fn expect_condition(&mut self) -> Option<ExprAst> {
-        let t = self.get_curr_token().unwrap();
-        let expr = self.parse_expr()?;
+        let span = self.span();
+        let expr = self.parse_value()?;
 
         match expr {
             ExprAst::OpAst(_)
@@ -127,16 +284,13 @@ impl Parser {
             | ExprAst::WhileAst(_)
             | ExprAst::ForAst(_)
             | ExprAst::PrintAst(_)
-            | ExprAst::BlockAst(_) => {
-                dbg!();
-                self.panic_on_wrong_token(t);
-            }
+            | ExprAst::BlockAst(_) => self.error_at(span, "Expect expression."),
             _ => Some(expr),
         }
     }
 
     fn expect_statement(&mut self) -> Option<ExprAst> {
-        let t = self.get_curr_token().unwrap();
+        let span = self.span();
         let expr = self.parse_expr()?;
 
         match expr {
@@ -145,121 +299,111 @@ impl Parser {
             | ExprAst::WhileAst(_)
             | ExprAst::ForAst(_)
             | ExprAst::PrintAst(_)
-            | ExprAst::BlockAst(_) => {
-                dbg!();
-                self.panic_on_wrong_token(t);
-            }
+            | ExprAst::BlockAst(_) => self.error_at(span, "Expect expression."),
             _ => Some(expr),
         }
     }
 
-    fn unexpect_var_token(&self) {
-        self.unexpect_token(Token::Var);
+    fn unexpect_var_token(&mut self) -> Option<()> {
+        self.unexpect_token(Token::Var)
     }
 
     fn parse_for_expr(&mut self) -> Option<ExprAst> {
-        assert_eq!(self.get_next_token(), Some(Token::LeftParen));
+        let start = self.prev_span();
+        self.consume(Token::LeftParen, "Expect '(' after 'for'.")?;
         let lhs = if matches!(self.get_curr_token()?, Token::Semicolon) {
-            ExprAst::NilAst
+            ExprAst::NilAst(self.span())
         } else {
-            self.expect_statement().unwrap_or(ExprAst::NilAst)
+            self.expect_statement()?
         };
-        self.get_next_token();
+        self.consume(Token::Semicolon, "Expect ';' after loop initializer.")?;
 
         let cond = if matches!(self.get_curr_token()?, Token::Semicolon) {
-            ExprAst::NilAst
+            ExprAst::NilAst(self.span())
         } else {
-            self.expect_condition().unwrap_or(ExprAst::NilAst)
+            self.expect_condition()?
         };
+        self.consume(Token::Semicolon, "Expect ';' after loop condition.")?;
 
-        self.get_next_token();
         let rhs = if matches!(self.get_curr_token()?, Token::RightParen) {
-            self.get_next_token();
-            ExprAst::NilAst
+            ExprAst::NilAst(self.span())
         } else {
-            self.expect_statement().unwrap_or(ExprAst::NilAst)
+            self.expect_statement()?
         };
-        if matches!(self.get_curr_token()?, Token::RightParen) {
-            self.get_next_token();
-        }
+        self.consume(Token::RightParen, "Expect ')' after for clauses.")?;
 
-        self.unexpect_var_token();
+        self.unexpect_var_token()?;
 
-        let then = self.parse_expr().unwrap();
+        let then = self.parse_expr()?;
+        self.end_statement(&then)?;
+        let span = start.to(then.span());
 
-        if self.get_curr_token() == Some(Token::Semicolon) {
-            self.get_next_token();
-        }
-        self.warn = None;
         let if_expr = ForExprAst {
             lhs: Box::new(lhs),
             cond: Box::new(cond),
             then: Box::new(then),
             rhs: Box::new(rhs),
+            span,
         };
         Some(ExprAst::ForAst(if_expr))
     }
 
     fn parse_if_expr(&mut self) -> Option<ExprAst> {
-        assert_eq!(self.get_next_token(), Some(Token::LeftParen));
-        let cond = self.expect_condition().unwrap();
-        assert_eq!(self.get_next_token(), Some(Token::RightParen));
-        self.unexpect_var_token();
+        let start = self.prev_span();
+        self.consume(Token::LeftParen, "Expect '(' after 'if'.")?;
+        let cond = self.expect_condition()?;
+        self.consume(Token::RightParen, "Expect ')' after if condition.")?;
+        self.unexpect_var_token()?;
         let then = self.parse_expr()?;
-
-        if self.get_curr_token() == Some(Token::Semicolon) {
-            self.get_next_token();
-        }
+        self.end_statement(&then)?;
 
         let default = if self.get_curr_token() == Some(Token::Else) {
             self.get_next_token();
-            self.parse_expr()
+            let default = self.parse_expr()?;
+            self.end_statement(&default)?;
+            default
         } else {
-            Some(ExprAst::BlockAst(BlockExprAst { cont: vec![] }))
+            ExprAst::BlockAst(BlockExprAst {
+                cont: vec![],
+                span: self.prev_span(),
+            })
         };
 
         let if_expr = IfExprAst {
+            span: start.to(default.span()),
             cond: Box::new(cond),
             then: Box::new(then),
-            default: Box::new(default?),
+            default: Box::new(default),
         };
-        self.warn = None;
         Some(ExprAst::IfAst(if_expr))
     }
 
     fn parse_par(&mut self) -> Option<ExprAst> {
-        let expr = self.parse_expr()?;
-        if self.get_curr_token() == Some(Token::RightParen) {
-            self.get_next_token();
-        } else {
-            eprintln!("Missing right parenthesis");
-            exit(65);
-        }
+        let start = self.prev_span();
+        let expr = self.parse_value()?;
+        self.consume(Token::RightParen, "Expect ')' after expression.")?;
         let r = ExprAst::ParAst(ParExprAst {
             val: Box::new(expr),
+            span: self.span_from(start),
         });
         Some(r)
     }
 
     fn parse_ast(&mut self) -> Option<ExprAst> {
+        let value_expected = std::mem::take(&mut self.value_expected);
         self.get_cur_tok_precedence();
         if let Some(token) = self.get_next_token() {
             let expr = match token {
-                Token::Eof => None,
+                Token::Eof => self.error_at(self.prev_span(), "Expect expression."),
                 Token::False | Token::True => Some(self.parse_bool(token)),
-                Token::Number(_) => {
-                    let v: Vec<String> = token
-                        .to_string()
-                        .split(' ')
-                        .map(|s| s.to_string())
-                        .collect();
-                    Some(self.parse_number(v[2].clone()))
-                }
-                Token::LoxString(s) => Some(self.parse_string(s)),
-                Token::Nil => Some(ExprAst::NilAst),
-                Token::Semicolon => self.parse_ast(),
+                Token::Number(lexeme, value) => Some(self.parse_number(lexeme, value)),
+                Token::LoxString(_, s) => Some(self.parse_string(s)),
+                Token::Nil => Some(ExprAst::NilAst(self.prev_span())),
+                Token::Semicolon => self.error_at(self.prev_span(), "Expect expression."),
                 Token::Minus => self.parse_negative_expr(),
+                Token::LeftParen if self.at_arrow_params() => self.parse_arrow(),
                 Token::LeftParen => self.parse_par(),
+                Token::LeftBracket => self.parse_list(),
                 Token::Bang
                 | Token::Plus
                 | Token::And
@@ -272,42 +416,61 @@ impl Parser {
                 | Token::BangEqual
                 | Token::DoubleEqual
                 | Token::Equal => Some(self.parse_op(token)),
-                Token::Identifier(s) => {
-                    // todo : Add a parser for identifier
-                    Some(self.parse_identifier(s))
-                }
+                Token::Identifier(s) => Some(self.parse_identifier(s)),
                 Token::Return => self.parse_return(),
-                Token::Var => match self.parse_expr()? {
-                    ExprAst::AssignAst(assign_expr_ast) => {
-                        Some(ExprAst::VarAssignAst(assign_expr_ast))
+                Token::Var => {
+                    let start = self.prev_span();
+                    let name = self.span();
+                    if !matches!(self.get_curr_token()?, Token::Identifier(_)) {
+                        return self.error_at(name, "Expect variable name.");
                     }
-                    ExprAst::IdenAst(id) => {
-                        let assign = AssignExprAst {
-                            lhs: Box::new(ExprAst::IdenAst(id)),
-                            rhs: Box::new(ExprAst::NilAst),
-                        };
-                        Some(ExprAst::VarAssignAst(assign))
+                    match self.parse_expr()? {
+                        ExprAst::AssignAst(assign) => Some(ExprAst::VarAssignAst(AssignExprAst {
+                            span: start.to(assign.span),
+                            ..assign
+                        })),
+                        ExprAst::IdenAst(id) => {
+                            let assign = AssignExprAst {
+                                span: start.to(id.span),
+                                rhs: Box::new(ExprAst::NilAst(id.span)),
+                                lhs: id,
+                            };
+                            Some(ExprAst::VarAssignAst(assign))
+                        }
+                        _ => self.error_at(name, "Expect variable name."),
                     }
-                    _ => unimplemented!(),
-                },
-                Token::RightParen => self.panic_on_wrong_token(token),
-                Token::If => {
-                    let o = self.parse_if_expr();
-                    o
                 }
+
+                Token::If => self.parse_if_expr(),
                 Token::While => self.parse_while_expr(),
                 Token::For => self.parse_for_expr(),
                 Token::Print => self.parse_print_expr(),
+                Token::LeftBraces if value_expected => self.parse_map(),
                 Token::LeftBraces => self.parse_block(),
-                Token::Fun => self.parse_func(),
-                _ => {
-                    unimplemented!("{:?}", token)
+                Token::Fun if self.get_curr_token() == Some(Token::LeftParen) => {
+                    self.parse_lambda()
                 }
+                Token::Fun => self.parse_func(self.prev_span()),
+                Token::Class => self.parse_class(),
+                Token::Import => self.parse_import(),
+                Token::Throw => self.parse_throw(),
+                Token::Try => self.parse_try(),
+                Token::This => Some(ExprAst::ThisAst(IdentExprAst {
+                    val: "this".to_string(),
+                    slot: None,
+                    span: self.prev_span(),
+                })),
+                Token::Super => self.parse_super(),
+                Token::Break => Some(ExprAst::BreakAst(self.prev_span())),
+                Token::Continue => Some(ExprAst::ContinueAst(self.prev_span())),
+                _ => self.error_at(self.prev_span(), "Expect expression."),
             };
-            if self.get_curr_token() == Some(Token::LeftParen)
-                && !matches!(expr.as_ref()?, ExprAst::OpAst(..))
+            if matches!(
+                self.get_curr_token(),
+                Some(Token::LeftParen) | Some(Token::Dot) | Some(Token::LeftBracket)
+            ) && !matches!(expr.as_ref()?, ExprAst::OpAst(..))
             {
-                self.parse_fncall(expr?)
+                self.parse_postfix(expr?)
             } else {
                 expr
             }
@@ -317,33 +480,71 @@ impl Parser {
     }
 
     fn parse_string(&self, val: String) -> ExprAst {
-        let s = StringExprAst { val };
+        let s = StringExprAst {
+            val,
+            span: self.prev_span(),
+        };
         ExprAst::StrAst(s)
     }
 
-    fn parse_fncall(&mut self, lhs: ExprAst) -> Option<ExprAst> {
-        let mut lhs = Some(ExprAst::FnCallAst(FnCallExprAst {
-            lhs: Box::new(lhs.clone()),
-            args: self.parse_function_args(),
-        }));
-
+    fn parse_postfix(&mut self, mut lhs: ExprAst) -> Option<ExprAst> {
         loop {
-            if self.get_curr_token() == Some(Token::LeftParen) {
-                lhs = self.parse_fncall(lhs.unwrap());
-            } else {
-                break;
+            match self.get_curr_token() {
+                Some(Token::LeftParen) => {
+                    let args = self.parse_function_args()?;
+                    lhs = ExprAst::FnCallAst(FnCallExprAst {
+                        span: self.span_from(lhs.span()),
+                        lhs: Box::new(lhs),
+                        args,
+                    });
+                }
+                Some(Token::Dot) => {
+                    self.get_next_token();
+                    match self.get_next_token() {
+                        Some(Token::Identifier(name)) => {
+                            lhs = ExprAst::GetAst(GetExprAst {
+                                span: self.span_from(lhs.span()),
+                                object: Box::new(lhs),
+                                name,
+                            });
+                        }
+                        Some(_) => {
+                            return self
+                                .error_at(self.prev_span(), "Expect property name after '.'.")
+                        }
+                        None => return None,
+                    }
+                }
+                Some(Token::LeftBracket) => {
+                    self.get_next_token();
+                    let index = self.parse_value()?;
+                    self.consume(Token::RightBracket, "Expect ']' after index.")?;
+                    lhs = ExprAst::IndexAst(IndexExprAst {
+                        span: self.span_from(lhs.span()),
+                        object: Box::new(lhs),
+                        index: Box::new(index),
+                    });
+                }
+                _ => return Some(lhs),
             }
         }
-        return lhs;
     }
 
     fn parse_identifier(&mut self, val: String) -> ExprAst {
-        let id = IdentExprAst { val: val };
+        let id = IdentExprAst {
+            val,
+            slot: None,
+            span: self.prev_span(),
+        };
         ExprAst::IdenAst(id)
     }
 
-    fn parse_number(&self, number: String) -> ExprAst {
-        let n = NumberExprAst { number };
+    fn parse_number(&self, lexeme: String, number: f64) -> ExprAst {
+        let n = NumberExprAst {
+            number,
+            lexeme,
+            span: self.prev_span(),
+        };
         ExprAst::NumAst(n)
     }
     fn get_next_token(&mut self) -> Option<Token> {
@@ -378,48 +579,51 @@ impl Parser {
 
             self.get_next_token()?;
 
-            let mut rhs = self.parse_ast();
-
-            if rhs.is_none() {
-                eprintln!("RHS is missing {}", self.line_nb);
-                return None;
-            }
+            self.value_expected = true;
+            let mut rhs = Some(self.parse_operand()?);
 
             let next_prec = self.get_cur_tok_precedence()?;
 
             if tok_prec < next_prec {
                 rhs = self.parse_bin_op_rhs(tok_prec + 1, rhs.unwrap());
-                if rhs.is_none() {
-                    return None;
-                }
+                rhs.as_ref()?;
             }
 
+            let rhs = rhs.unwrap();
             lhs = ExprAst::BinaryAst(BinExprAst {
+                span: lhs.span().to(rhs.span()),
                 lhs: Box::new(lhs),
-                rhs: Box::new(rhs.unwrap()),
+                rhs: Box::new(rhs),
                 op: bin_op,
             });
-            // print!(")");
         }
     }
 
     fn parse_uni_op(&mut self, expr: ExprAst) -> Option<ExprAst> {
-        let o = match expr {
-            ExprAst::OpAst(ref o) => o.val.clone(),
+        let (o, start) = match expr {
+            ExprAst::OpAst(ref o) => (o.val.clone(), o.span),
             _ => unreachable!(),
         };
+        // The other operators only come between operands.
+        if o != Token::Bang {
+            return self.error_at(start, "Expect expression.");
+        }
 
-        if let Some(mut expr) = self.parse_ast() {
-            match expr {
-                ExprAst::OpAst(_) => expr = self.parse_uni_op(expr).unwrap(),
-                _ => (),
-            }
-            Some(ExprAst::UnaryAst(UnaExprAst {
-                op: o,
-                rhs: Box::new(expr),
-            }))
-        } else {
-            None
+        self.value_expected = true;
+        let expr = self.parse_operand()?;
+        Some(ExprAst::UnaryAst(UnaExprAst {
+            op: o,
+            span: start.to(expr.span()),
+            rhs: Box::new(expr),
+        }))
+    }
+
+    /// Parses the operand of a binary or prefix operator: a primary
+    /// expression, or a prefix operator applied to one.
+    fn parse_operand(&mut self) -> Option<ExprAst> {
+        match self.parse_ast()? {
+            op @ ExprAst::OpAst(_) => self.parse_uni_op(op),
+            expr => Some(expr),
         }
     }
 
@@ -427,7 +631,10 @@ impl Parser {
         if let Some(lhs) = self.parse_ast() {
             match lhs {
                 ExprAst::IfAst(_) => Some(lhs),
-                ExprAst::OpAst(_) => self.parse_uni_op(lhs),
+                ExprAst::OpAst(_) => {
+                    let unary = self.parse_uni_op(lhs)?;
+                    self.parse_bin_op_rhs(0, unary)
+                }
                 _ => match self.get_curr_token()? {
                     Token::Equal => self.parse_assign(lhs),
                     _ => self.parse_bin_op_rhs(0, lhs),
@@ -438,60 +645,88 @@ impl Parser {
         }
     }
 
-    fn exec(&self, ast: &ExprAst) -> Result<IntermRepr, String> {
-        if self.warn.is_some() {
-            self.panic_on_wrong_token(self.warn.clone().unwrap());
-        }
-        let o = self.evaluate(&ast)?;
+    /// Parses an expression in a place that needs a value.
+    fn parse_value(&mut self) -> Option<ExprAst> {
+        self.value_expected = true;
+        self.parse_expr()
+    }
+
+    fn exec(&self, ast: &ExprAst) -> Result<String, RuntimeError> {
+        let o = self.evaluate(ast)?;
         Ok(o)
     }
 
-    fn evaluate(&self, expr: &ExprAst) -> Result<IntermRepr, String> {
-        self.interpreter
-            .as_ref()
-            .unwrap()
-            .borrow_mut()
-            .evaluate(&expr)
+    fn evaluate(&self, expr: &ExprAst) -> Result<String, RuntimeError> {
+        self.backend.as_ref().unwrap().borrow_mut().execute(expr)
     }
 
-    fn opti_parse(&mut self) -> Result<(), Error> {
-        let mut v: Vec<ExprAst> = Vec::new();
-        loop {
-            if self.err {
-                dbg!();
-                return Err(Error::Parser);
-            } else {
-                self.line_nb += 1;
-            }
-            match self.get_curr_token() {
-                Some(token) => match token {
-                    Token::Eof => break,
-                    _ => {
-                        if let Some(ast) = self.parse_expr() {
-                            if self.options.contains(ParserOptions::RUN) {
-                                v.push(ast);
-                            }
-                        } else {
-                            if !matches!(token, Token::Semicolon | Token::RightBraces) {
-                                dbg!(token);
-                                return Err(Error::Parser);
-                            } else {
-                                break;
-                            }
-                        }
-                    }
-                },
-                _ => {
-                    return Ok(());
+    /// Parses the next top-level statement, recovering at the following
+    /// statement boundary on a syntax error. Returns `None` at end of input.
+    fn next_statement(&mut self) -> Option<Option<ExprAst>> {
+        while self.get_curr_token() == Some(Token::Semicolon) {
+            self.get_next_token();
+        }
+        match self.get_curr_token() {
+            None | Some(Token::Eof) => return None,
+            Some(_) => (),
+        }
+        match self.parse_expr() {
+            Some(ast) if !self.panicking => {
+                // Ends up panicking when the `;` is missing.
+                self.end_statement(&ast);
+                if !self.panicking {
+                    return Some(Some(ast));
                 }
             }
+            Some(_) => (),
+            None if !self.panicking => {
+                self.error_at::<()>(self.prev_span(), "Expect expression.");
+            }
+            None => (),
+        }
+        self.synchronize();
+        Some(None)
+    }
+
+    /// Parses the whole token stream, collecting every syntax error.
+    fn parse_program(&mut self) -> Result<Vec<ExprAst>, Vec<Diagnostic>> {
+        let mut v: Vec<ExprAst> = Vec::new();
+        while let Some(ast) = self.next_statement() {
+            v.extend(ast);
+        }
+        if self.diagnostics.is_empty() {
+            Ok(v)
+        } else {
+            Err(std::mem::take(&mut self.diagnostics))
         }
+    }
+
+    fn report(diagnostics: &[Diagnostic], err: Error) -> Error {
+        for diagnostic in diagnostics {
+            eprintln!("{}", diagnostic);
+        }
+        err
+    }
+
+    fn opti_parse(&mut self) -> Result<(), Error> {
+        let mut v = self
+            .parse_program()
+            .map_err(|diagnostics| Self::report(&diagnostics, Error::Parser))?;
+        if !self.options.contains(ParserOptions::RUN) {
+            return Ok(());
+        }
+        Resolver::new()
+            .resolve(&mut v)
+            .map_err(|diagnostics| Self::report(&diagnostics, Error::Resolver))?;
         for ast in &v {
-            match self.evaluate(&ast) {
+            match self.evaluate(ast) {
+                Ok(r) if self.options.contains(ParserOptions::ECHO) && ast.is_expression() => {
+                    println!("{}", r);
+                }
                 Ok(_) => (),
-                Err(s) => {
-                    eprintln!("[{}] {}", self.line_nb, s);
-                    return Err(Error::Runtime);
+                Err(err) => {
+                    eprintln!("{}", err);
+                    return Err(Error::runtime(&err));
                 }
             }
         }
@@ -499,111 +734,235 @@ impl Parser {
     }
 
     fn parse(&mut self) -> Result<(), Error> {
-        loop {
-            if self.err {
-                dbg!();
-                return Err(Error::Parser);
-            } else {
-                self.line_nb += 1;
+        let mut resolver = Resolver::new();
+        while let Some(ast) = self.next_statement() {
+            // Keep parsing after a syntax error to report the remaining
+            // ones, but stop running the statements.
+            let Some(mut ast) = ast.filter(|_| self.diagnostics.is_empty()) else {
+                continue;
+            };
+            if self
+                .options
+                .intersects(ParserOptions::EVALUATE | ParserOptions::RUN)
+            {
+                resolver
+                    .resolve(std::slice::from_mut(&mut ast))
+                    .map_err(|diagnostics| Self::report(&diagnostics, Error::Resolver))?;
             }
-            match self.get_curr_token() {
-                Some(token) => match token {
-                    Token::Eof => return Ok(()),
-                    _ => {
-                        if let Some(ast) = self.parse_expr() {
-                            if self.options.contains(ParserOptions::EVALUATE) {
-                                match self.evaluate(&ast) {
-                                    Ok(r) => {
-                                        println!("{}", r.to_string());
-                                    }
-                                    Err(s) => {
-                                        eprintln!("[{}] {}", self.line_nb, s);
-                                        return Err(Error::Runtime);
-                                    }
-                                }
-                            } else if self.options.contains(ParserOptions::RUN) {
-                                match self.exec(&ast) {
-                                    Ok(_) => (),
-                                    Err(s) => {
-                                        eprintln!("[{}] {}", self.line_nb, s);
-                                        return Err(Error::Runtime);
-                                    }
-                                }
-                            }
-                            if self.options.contains(ParserOptions::DEBUG) {
-                                ast.print_ast();
-                            }
-                        } else {
-                            if !matches!(token, Token::Semicolon | Token::RightBraces) {
-                                dbg!(token);
-                                return Err(Error::Parser);
-                            } else {
-                                return Ok(());
-                            }
-                        }
+            if self.options.contains(ParserOptions::EVALUATE) {
+                match self.evaluate(&ast) {
+                    Ok(r) => {
+                        println!("{}", r);
+                    }
+                    Err(err) => {
+                        eprintln!("{}", err);
+                        return Err(Error::runtime(&err));
+                    }
+                }
+            } else if self.options.contains(ParserOptions::RUN) {
+                match self.exec(&ast) {
+                    Ok(_) => (),
+                    Err(err) => {
+                        eprintln!("{}", err);
+                        return Err(Error::runtime(&err));
                     }
-                },
-                _ => {
-                    break Ok(());
                 }
             }
+            if self.options.contains(ParserOptions::DEBUG) {
+                ast.print_ast();
+            }
+        }
+        if self.diagnostics.is_empty() {
+            Ok(())
+        } else {
+            Err(Self::report(&self.diagnostics, Error::Parser))
         }
     }
 
-    fn expect_body(&mut self) -> ExprAst {
-        let token = self.get_curr_token().unwrap();
-        if let Some(expr) = self.parse_expr() {
-            match expr {
-                ExprAst::BlockAst(_) => expr,
-                _ => {
-                    self.panic_on_wrong_token(token);
+    fn expect_body(&mut self) -> Option<ExprAst> {
+        self.consume(Token::LeftBraces, "Expect '{' before function body.")?;
+        self.parse_block()
+    }
+
+    /// Parses a function from its name on; `start` is where its declaration
+    /// begins.
+    fn parse_func(&mut self, start: Span) -> Option<ExprAst> {
+        let name = match self.get_curr_token()? {
+            Token::Identifier(name) => name,
+            _ => return self.error_at(self.span(), "Expect function name."),
+        };
+        self.get_next_token();
+        self.consume(Token::LeftParen, "Expect '(' after function name.")?;
+        let args = self.parse_params()?;
+        let body = self.expect_body()?;
+        Some(ExprAst::FnDeclAst(FnDeclExprAst {
+            name,
+            slot: None,
+            span: start.to(body.span()),
+            body: Box::new(body),
+            args,
+        }))
+    }
+
+    /// Parses the parameters of a function, after its `(`.
+    fn parse_params(&mut self) -> Option<Vec<ExprAst>> {
+        let mut params = Vec::new();
+        if self.get_curr_token()? != Token::RightParen {
+            loop {
+                if params.len() == MAX_ARGS {
+                    // Reported, but the parameters are still read.
+                    self.error_at::<()>(self.span(), "Can't have more than 255 parameters.");
+                }
+                match self.get_next_token()? {
+                    Token::Identifier(name) => params.push(self.parse_identifier(name)),
+                    _ => return self.error_at(self.prev_span(), "Expect parameter name."),
+                }
+                if self.get_curr_token()? != Token::Comma {
+                    break;
+                }
+                self.get_next_token();
+            }
+        }
+        self.consume(Token::RightParen, "Expect ')' after parameters.")?;
+        Some(params)
+    }
+
+    fn lambda(args: Vec<ExprAst>, body: ExprAst, start: Span) -> ExprAst {
+        ExprAst::LambdaAst(FnDeclExprAst {
+            name: "lambda".to_string(),
+            slot: None,
+            args,
+            span: start.to(body.span()),
+            body: Box::new(body),
+        })
+    }
+
+    fn parse_lambda(&mut self) -> Option<ExprAst> {
+        let start = self.prev_span();
+        self.get_next_token();
+        let args = self.parse_params()?;
+        let body = self.expect_body()?;
+        Some(Self::lambda(args, body, start))
+    }
+
+    /// Whether the `(` just read opens the parameters of an arrow function.
+    fn at_arrow_params(&self) -> bool {
+        let mut tokens = self.it.as_slice().iter();
+        let mut next = tokens.next();
+        while let Some(Token::Identifier(_)) = next {
+            match tokens.next() {
+                Some(Token::Comma) => next = tokens.next(),
+                other => {
+                    next = other;
+                    break;
+                }
+            }
+        }
+        next == Some(&Token::RightParen) && tokens.next() == Some(&Token::Arrow)
+    }
+
+    /// Parses `(params) => body`, whose body is a block or an expression
+    /// giving the return value.
+    fn parse_arrow(&mut self) -> Option<ExprAst> {
+        let start = self.prev_span();
+        let args = self.parse_params()?;
+        self.consume(Token::Arrow, "Expect '=>' after parameters.")?;
+        let body = if self.get_curr_token()? == Token::LeftBraces {
+            self.get_next_token();
+            self.parse_block()?
+        } else {
+            let val = self.parse_value()?;
+            let span = val.span();
+            let ret = ExprAst::RetAst(RetExprAst {
+                val: Box::new(val),
+                span,
+            });
+            ExprAst::BlockAst(BlockExprAst {
+                cont: vec![ret],
+                span,
+            })
+        };
+        Some(Self::lambda(args, body, start))
+    }
+
+    fn parse_class(&mut self) -> Option<ExprAst> {
+        let start = self.prev_span();
+        let name = match self.get_next_token()? {
+            Token::Identifier(name) => name,
+            _ => return self.error_at(self.prev_span(), "Expect class name."),
+        };
+        let superclass = if self.get_curr_token()? == Token::Less {
+            self.get_next_token();
+            match self.get_next_token()? {
+                Token::Identifier(s) if s == name => {
+                    return self.error_at(self.prev_span(), "A class can't inherit from itself.")
                 }
+                Token::Identifier(s) => Some(Box::new(self.parse_identifier(s))),
+                _ => return self.error_at(self.prev_span(), "Expect superclass name."),
             }
         } else {
-            self.panic_on_wrong_token(token);
-        }
-    }
-
-    fn parse_func(&mut self) -> Option<ExprAst> {
-        let proto = self.parse_ast().unwrap();
-        match proto {
-            ExprAst::FnCallAst(f) => {
-                let args: Vec<ExprAst> = f.args;
-                let body = self.expect_body();
-                let o = ExprAst::FnDeclAst(FnDeclExprAst {
-                    name: f.lhs.to_string(),
-                    body: Box::new(body.clone()),
-                    args: args.clone(),
-                });
-                Some(o)
+            None
+        };
+        self.consume(Token::LeftBraces, "Expect '{' before class body.")?;
+
+        let mut methods = Vec::new();
+        loop {
+            match self.get_curr_token()? {
+                Token::RightBraces => {
+                    self.get_next_token();
+                    break;
+                }
+                Token::Identifier(_) => match self.parse_func(self.span())? {
+                    ExprAst::FnDeclAst(method) => methods.push(method),
+                    _ => unreachable!(),
+                },
+                _ => return self.error_at(self.span(), "Expect method name."),
             }
-            _ => unimplemented!("{:?}", proto),
+        }
+        Some(ExprAst::ClassDeclAst(ClassDeclExprAst {
+            name,
+            slot: None,
+            superclass,
+            methods,
+            span: self.span_from(start),
+        }))
+    }
+
+    fn parse_super(&mut self) -> Option<ExprAst> {
+        let start = self.prev_span();
+        match self.get_next_token()? {
+            Token::Dot => (),
+            _ => return self.error_at(self.prev_span(), "Expect '.' after 'super'."),
+        }
+        match self.get_next_token()? {
+            Token::Identifier(method) => Some(ExprAst::SuperAst(SuperExprAst {
+                method,
+                slot: None,
+                span: self.span_from(start),
+            })),
+            _ => self.error_at(self.prev_span(), "Expect superclass method name."),
         }
     }
 
-    fn parse_function_args(&mut self) -> Vec<ExprAst> {
+    fn parse_function_args(&mut self) -> Option<Vec<ExprAst>> {
         let mut v = Vec::new();
         if self.get_curr_token() == Some(Token::LeftParen) {
             self.get_next_token(); // eat left;
         }
         if self.get_curr_token() == Some(Token::RightParen) {
             self.get_next_token();
-            return v;
+            return Some(v);
         }
         loop {
-            let t = self.get_curr_token().unwrap();
-            match t {
-                Token::Semicolon => {
-                    dbg!();
-                    self.panic_on_wrong_token(t)
-                }
-                _ => (),
+            if self.get_curr_token()? == Token::Semicolon {
+                return self.error_at(self.span(), "Expect expression.");
             };
-            let expr = self.parse_expr().unwrap();
-            self.warn = None;
+            if v.len() == MAX_ARGS {
+                self.error_at::<()>(self.span(), "Can't have more than 255 arguments.");
+            }
+            let expr = self.parse_value()?;
             v.push(expr);
-            let token = self.get_curr_token().unwrap();
-            match token {
+            match self.get_curr_token()? {
                 Token::Comma => {
                     self.get_next_token();
                 }
@@ -611,29 +970,151 @@ impl Parser {
                     self.get_next_token();
                     break;
                 }
-                _ => {
-                    dbg!();
-                    self.panic_on_wrong_token(token)
+                _ => return self.error_at(self.span(), "Expect ')' after arguments."),
+            }
+        }
+        Some(v)
+    }
+
+    fn parse_list(&mut self) -> Option<ExprAst> {
+        let start = self.prev_span();
+        let mut items = Vec::new();
+        if self.get_curr_token()? != Token::RightBracket {
+            loop {
+                items.push(self.parse_value()?);
+                match self.get_curr_token()? {
+                    Token::Comma => {
+                        self.get_next_token();
+                    }
+                    Token::RightBracket => break,
+                    _ => return self.error_at(self.span(), "Expect ']' after list elements."),
                 }
             }
         }
-        v
+        self.get_next_token();
+        Some(ExprAst::ListAst(ListExprAst {
+            items,
+            span: self.span_from(start),
+        }))
     }
 
-    fn parse_return(&mut self) -> Option<ExprAst> {
-        let e = if self.get_curr_token().unwrap() == Token::Semicolon {
-            ExprAst::RetAst(RetExprAst {
-                val: Box::new(ExprAst::NilAst),
+    fn parse_map(&mut self) -> Option<ExprAst> {
+        let start = self.prev_span();
+        let mut entries = Vec::new();
+        if self.get_curr_token()? != Token::RightBraces {
+            loop {
+                let key = self.parse_value()?;
+                self.consume(Token::Colon, "Expect ':' after map key.")?;
+                entries.push((key, self.parse_value()?));
+                match self.get_curr_token()? {
+                    Token::Comma => {
+                        self.get_next_token();
+                    }
+                    Token::RightBraces => break,
+                    _ => return self.error_at(self.span(), "Expect '}' after map entries."),
+                }
+            }
+        }
+        self.get_next_token();
+        Some(ExprAst::MapAst(MapExprAst {
+            entries,
+            span: self.span_from(start),
+        }))
+    }
+
+    fn parse_import(&mut self) -> Option<ExprAst> {
+        let start = self.prev_span();
+        let name = match self.get_curr_token()? {
+            Token::Identifier(name) => {
+                self.get_next_token();
+                match self.get_curr_token()? {
+                    Token::Identifier(from) if from == "from" => {
+                        self.get_next_token();
+                    }
+                    _ => return self.error_at(self.span(), "Expect 'from' after module name."),
+                }
+                Some(name)
+            }
+            _ => None,
+        };
+        let Token::LoxString(_, path) = self.get_curr_token()? else {
+            return self.error_at(self.span(), "Expect module path.");
+        };
+        self.get_next_token();
+        Some(ExprAst::ImportAst(ImportExprAst {
+            path,
+            name,
+            span: self.span_from(start),
+        }))
+    }
+
+    fn parse_throw(&mut self) -> Option<ExprAst> {
+        let start = self.prev_span();
+        let val = self.parse_value()?;
+        Some(ExprAst::ThrowAst(ThrowExprAst {
+            span: start.to(val.span()),
+            val: Box::new(val),
+        }))
+    }
+
+    fn parse_try(&mut self) -> Option<ExprAst> {
+        let start = self.prev_span();
+        self.consume(Token::LeftBraces, "Expect '{' after 'try'.")?;
+        let body = self.parse_block()?;
+        let catch = if self.get_curr_token()? == Token::Catch {
+            self.get_next_token();
+            self.consume(Token::LeftParen, "Expect '(' after 'catch'.")?;
+            let name = match self.get_next_token()? {
+                Token::Identifier(name) => IdentExprAst {
+                    val: name,
+                    slot: None,
+                    span: self.prev_span(),
+                },
+                _ => return self.error_at(self.prev_span(), "Expect error variable name."),
+            };
+            self.consume(Token::RightParen, "Expect ')' after error variable.")?;
+            self.consume(Token::LeftBraces, "Expect '{' before catch body.")?;
+            Some(CatchExprAst {
+                name,
+                body: Box::new(self.parse_block()?),
             })
         } else {
-            ExprAst::RetAst(RetExprAst {
-                val: Box::new(self.parse_expr().unwrap()),
-            })
+            None
+        };
+        let finally = if self.get_curr_token()? == Token::Finally {
+            self.get_next_token();
+            self.consume(Token::LeftBraces, "Expect '{' after 'finally'.")?;
+            Some(Box::new(self.parse_block()?))
+        } else {
+            None
+        };
+        if catch.is_none() && finally.is_none() {
+            return self.error_at(self.span(), "Expect 'catch' or 'finally' after try block.");
+        }
+        Some(ExprAst::TryAst(TryExprAst {
+            body: Box::new(body),
+            catch,
+            finally,
+            span: self.span_from(start),
+        }))
+    }
+
+    fn parse_return(&mut self) -> Option<ExprAst> {
+        let start = self.prev_span();
+        let val = if self.get_curr_token()? == Token::Semicolon {
+            ExprAst::NilAst(start)
+        } else {
+            self.parse_value()?
         };
-        return Some(e);
+        let e = ExprAst::RetAst(RetExprAst {
+            span: start.to(val.span()),
+            val: Box::new(val),
+        });
+        Some(e)
     }
 
     fn parse_block(&mut self) -> Option<ExprAst> {
+        let start = self.prev_span();
         let mut v: Vec<ExprAst> = vec![];
 
         loop {
@@ -648,56 +1129,95 @@ impl Parser {
                     self.get_next_token();
                     break;
                 }
-                Some(_t) => {
-                    if let Some(e) = self.parse_expr() {
-                        v.push(e);
-                    } else {
-                        break;
-                    }
+                Some(Token::Eof) | None => {
+                    return self.error_at(self.span(), "Expect '}' after block.")
+                }
+                Some(_) => {
+                    let stmt = self.parse_expr()?;
+                    self.end_statement(&stmt)?;
+                    v.push(stmt);
                 }
-                None => break,
             }
         }
-        let b = BlockExprAst { cont: v };
+        let b = BlockExprAst {
+            cont: v,
+            span: self.span_from(start),
+        };
         Some(ExprAst::BlockAst(b))
     }
 }
 
 pub fn parse_token(
     s: String,
-    interpreter: Option<Rc<RefCell<Interpreter>>>,
+    backend: Option<Rc<RefCell<dyn Backend>>>,
     options: ParserOptions,
 ) -> Result<(), Error> {
-    let out = read_token(s);
+    let out = lex(&s);
 
-    if out.err || out.braces_depth != 0 {
-        dbg!();
+    if out.err {
         return Err(Error::Lexer);
     }
 
-    let mut parser = Parser::new(out.tokens.into_iter(), interpreter, options);
+    let mut parser = Parser::new(&s, out.tokens, backend, options);
 
     parser.parse()
 }
 
 pub fn opti_run(
     s: String,
-    interpreter: Option<Rc<RefCell<Interpreter>>>,
+    backend: Option<Rc<RefCell<dyn Backend>>>,
     options: ParserOptions,
 ) -> Result<(), Error> {
-    let out = read_token(s);
+    let out = lex(&s);
 
-    if out.err || out.braces_depth != 0 {
-        dbg!();
+    if out.err {
         return Err(Error::Lexer);
     }
 
-    let mut parser = Parser::new(out.tokens.into_iter(), interpreter, options);
+    let mut parser = Parser::new(&s, out.tokens, backend, options);
 
     parser.opti_parse()
 }
 
-pub fn scan_token(s: String) -> (Vec<Token>, bool) {
-    let v = read_token(s);
+/// Reads the tokens of `s`, reporting lexical errors on stderr.
+fn lex(s: &str) -> ReadTokenStatus {
+    let out = read_token(s);
+    for err in &out.errors {
+        eprintln!("{}", err);
+    }
+    out
+}
+
+/// Lexes, parses and resolves `source` without running it.
+pub fn compile(source: &str) -> Result<Vec<ExprAst>, LoxError> {
+    let out = read_token(source);
+    if !out.errors.is_empty() {
+        return Err(LoxError::Lex(out.errors));
+    }
+    let options = ParserOptions::RUN | ParserOptions::OPEN_END;
+    let mut parser = Parser::new(source, out.tokens, None, options);
+    let mut stmts = parser.parse_program().map_err(LoxError::Syntax)?;
+    Resolver::new()
+        .resolve(&mut stmts)
+        .map_err(LoxError::Syntax)?;
+    Ok(stmts)
+}
+
+/// Parses every statement of `source` without resolving them, keeping those
+/// parsed despite syntax errors. The tokens must be free of lexical errors.
+pub(crate) fn parse_partial(
+    source: &str,
+    tokens: Vec<SpannedToken>,
+) -> (Vec<ExprAst>, Vec<Diagnostic>) {
+    let mut parser = Parser::new(source, tokens, None, ParserOptions::RUN);
+    let mut stmts = Vec::new();
+    while let Some(stmt) = parser.next_statement() {
+        stmts.extend(stmt);
+    }
+    (stmts, parser.diagnostics)
+}
+
+pub fn scan_token(s: String) -> (Vec<SpannedToken>, bool) {
+    let v = lex(&s);
     (v.tokens, v.err)
 }
@@ -0,0 +1,550 @@
+use super::core::parse_partial;
+use super::parser_ds::*;
+use crate::error::Error;
+use crate::lexer::{read_token, Comment, Span, Token};
+
+/// Width the formatter keeps lines within when it can.
+const WIDTH: usize = 80;
+const INDENT: usize = 2;
+
+/// A layout of the source, broken into lines by `render`.
+enum Doc {
+    Text(String),
+    /// A space, or a line break when its group does not fit on the line.
+    Line,
+    /// Nothing, or a line break when its group does not fit on the line.
+    SoftLine,
+    HardLine,
+    /// Indents the lines broken inside, when its group is broken.
+    Nest(Box<Doc>),
+    /// Laid out on a single line when it fits.
+    Group(Box<Doc>),
+    /// Always broken, for blocks and for lists holding comments.
+    Broken(Box<Doc>),
+    Concat(Vec<Doc>),
+}
+
+fn text(s: impl Into<String>) -> Doc {
+    Doc::Text(s.into())
+}
+
+fn nest(doc: Doc) -> Doc {
+    Doc::Nest(Box::new(doc))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Mode {
+    Flat,
+    Break,
+}
+
+/// Whether the content of a group, laid out on one line, fits in `width`
+/// along with what follows it up to the next line break.
+fn fits(mut width: isize, doc: &Doc, rest: &[(usize, Mode, &Doc)]) -> bool {
+    let mut stack = vec![(Mode::Flat, doc)];
+    let mut rest = rest.iter().rev().map(|&(_, mode, doc)| (mode, doc));
+    while width >= 0 {
+        let Some((mode, doc)) = stack.pop().or_else(|| rest.next()) else {
+            return true;
+        };
+        match doc {
+            Doc::Text(s) => width -= s.chars().count() as isize,
+            Doc::Line if mode == Mode::Flat => width -= 1,
+            Doc::SoftLine if mode == Mode::Flat => (),
+            Doc::Line | Doc::SoftLine | Doc::HardLine => return true,
+            Doc::Nest(doc) => stack.push((mode, doc)),
+            Doc::Group(doc) => stack.push((Mode::Flat, doc)),
+            Doc::Broken(doc) => stack.push((Mode::Break, doc)),
+            Doc::Concat(docs) => stack.extend(docs.iter().rev().map(|doc| (mode, doc))),
+        }
+    }
+    false
+}
+
+fn newline(out: &mut String, indent: usize) {
+    out.truncate(out.trim_end_matches(' ').len());
+    out.push('\n');
+    out.extend(std::iter::repeat(' ').take(indent));
+}
+
+fn render(doc: &Doc) -> String {
+    let mut out = String::new();
+    let mut column = 0;
+    let mut stack = vec![(0, Mode::Break, doc)];
+    while let Some((indent, mode, doc)) = stack.pop() {
+        match doc {
+            Doc::Text(s) => {
+                out.push_str(s);
+                column += s.chars().count();
+            }
+            Doc::Line if mode == Mode::Flat => {
+                out.push(' ');
+                column += 1;
+            }
+            Doc::SoftLine if mode == Mode::Flat => (),
+            Doc::Line | Doc::SoftLine | Doc::HardLine => {
+                newline(&mut out, indent);
+                column = indent;
+            }
+            Doc::Nest(doc) if mode == Mode::Break => stack.push((indent + INDENT, mode, doc)),
+            Doc::Nest(doc) => stack.push((indent, mode, doc)),
+            Doc::Group(doc) => {
+                let width = WIDTH as isize - column as isize;
+                let mode = if mode == Mode::Flat || fits(width, doc, &stack) {
+                    Mode::Flat
+                } else {
+                    Mode::Break
+                };
+                stack.push((indent, mode, doc));
+            }
+            Doc::Broken(doc) => stack.push((indent, Mode::Break, doc)),
+            Doc::Concat(docs) => stack.extend(docs.iter().rev().map(|doc| (indent, mode, doc))),
+        }
+    }
+    let len = out.trim_end().len();
+    out.truncate(len);
+    if !out.is_empty() {
+        out.push('\n');
+    }
+    out
+}
+
+/// Whether the statement `expr` ends with a semicolon.
+fn terminated(expr: &ExprAst) -> bool {
+    !matches!(
+        expr,
+        ExprAst::IfAst(_)
+            | ExprAst::WhileAst(_)
+            | ExprAst::ForAst(_)
+            | ExprAst::BlockAst(_)
+            | ExprAst::FnDeclAst(_)
+            | ExprAst::ClassDeclAst(_)
+            | ExprAst::TryAst(_)
+    )
+}
+
+/// Lays out the syntax tree of a source, putting its comments back where
+/// they were relative to the statements.
+struct Formatter<'a> {
+    source: &'a str,
+    comments: Vec<Comment>,
+    /// The first comment not laid out yet.
+    next: usize,
+}
+
+impl<'a> Formatter<'a> {
+    fn slice(&self, span: Span) -> &'a str {
+        self.source.get(span.start..span.end).unwrap_or_default()
+    }
+
+    /// Whether `expr` is a `nil` the parser put in place of something
+    /// omitted, such as the value of `var a;` or of `return;`.
+    fn omitted(&self, expr: &ExprAst) -> bool {
+        matches!(expr, ExprAst::NilAst(span) if self.slice(*span) != "nil")
+    }
+
+    /// Takes the comments left that start before `offset`.
+    fn comments_before(&mut self, offset: usize) -> Vec<Comment> {
+        let start = self.next;
+        while self
+            .comments
+            .get(self.next)
+            .is_some_and(|c| c.span.start < offset)
+        {
+            self.next += 1;
+        }
+        self.comments[start..self.next].to_vec()
+    }
+
+    /// Takes the next comment when it starts before `limit` on the line
+    /// where `end` is.
+    fn comment_after(&mut self, end: usize, limit: usize) -> Option<Comment> {
+        let c = self.comments.get(self.next)?;
+        let between = self.source.get(end..c.span.start)?;
+        if c.span.start >= limit || between.contains('\n') {
+            return None;
+        }
+        self.next += 1;
+        Some(c.clone())
+    }
+
+    /// Whether the source has a blank line between `end` and `start`.
+    fn blank_between(&self, end: Option<usize>, start: usize) -> bool {
+        end.and_then(|end| self.source.get(end..start))
+            .is_some_and(|between| between.matches('\n').count() > 1)
+    }
+
+    /// Adds `doc`, starting at `start` in the source, as a new line after
+    /// `lines`, keeping a blank line from the source before it.
+    fn line(&self, lines: &mut Vec<Doc>, last: Option<usize>, start: usize, doc: Doc) {
+        if !lines.is_empty() {
+            lines.push(Doc::HardLine);
+            if self.blank_between(last, start) {
+                lines.push(Doc::HardLine);
+            }
+        }
+        lines.push(doc);
+    }
+
+    /// `items` one per line with the comments among them, up to `end` where
+    /// the list closes.
+    fn lines<T>(
+        &mut self,
+        items: &[T],
+        end: usize,
+        span: impl Fn(&T) -> Span,
+        mut layout: impl FnMut(&mut Self, &T) -> Doc,
+    ) -> Option<Doc> {
+        let mut lines = Vec::new();
+        // Where the last line ends in the source.
+        let mut last = None;
+        for (i, item) in items.iter().enumerate() {
+            let at = span(item);
+            let limit = items.get(i + 1).map_or(end, |next| span(next).start);
+            for c in self.comments_before(at.start) {
+                self.line(&mut lines, last, c.span.start, text(c.text));
+                last = Some(c.span.end);
+            }
+            let mut line = vec![layout(self, item)];
+            let mut end_of_line = at.end;
+            let mut trailing = self.comments_before(at.end);
+            trailing.extend(self.comment_after(at.end, limit));
+            for (i, c) in trailing.into_iter().enumerate() {
+                if i > 0 {
+                    line.push(Doc::HardLine);
+                }
+                line.push(text(format!(" {}", c.text)));
+                end_of_line = end_of_line.max(c.span.end);
+            }
+            self.line(&mut lines, last, at.start, Doc::Concat(line));
+            last = Some(end_of_line);
+        }
+        for c in self.comments_before(end) {
+            self.line(&mut lines, last, c.span.start, text(c.text));
+            last = Some(c.span.end);
+        }
+        if lines.is_empty() {
+            None
+        } else {
+            Some(Doc::Concat(lines))
+        }
+    }
+
+    /// `items` between `open` and `close`, on one line when they fit and one
+    /// per line otherwise. `end` is where `close` is in the source.
+    fn list<T>(
+        &mut self,
+        open: &str,
+        close: &str,
+        items: &[T],
+        end: usize,
+        span: impl Fn(&T) -> Span,
+        mut layout: impl FnMut(&mut Self, &T) -> Doc,
+    ) -> Doc {
+        let mut docs = vec![Doc::SoftLine];
+        let mut commented = false;
+        for (i, item) in items.iter().enumerate() {
+            let at = span(item);
+            for c in self.comments_before(at.start) {
+                commented = true;
+                docs.extend([text(c.text), Doc::HardLine]);
+            }
+            docs.push(layout(self, item));
+            let limit = items.get(i + 1).map_or(end, |next| span(next).start);
+            if i + 1 < items.len() {
+                docs.push(text(","));
+            }
+            if let Some(c) = self.comment_after(at.end, limit) {
+                commented = true;
+                docs.push(text(format!(" {}", c.text)));
+            }
+            if i + 1 < items.len() {
+                docs.push(Doc::Line);
+            }
+        }
+        for c in self.comments_before(end) {
+            commented = true;
+            if docs.len() > 1 {
+                docs.push(Doc::HardLine);
+            }
+            docs.push(text(c.text));
+        }
+        if docs.len() == 1 {
+            return text(format!("{}{}", open, close));
+        }
+        let doc = Doc::Concat(vec![
+            text(open),
+            nest(Doc::Concat(docs)),
+            Doc::SoftLine,
+            text(close),
+        ]);
+        if commented {
+            Doc::Broken(Box::new(doc))
+        } else {
+            Doc::Group(Box::new(doc))
+        }
+    }
+
+    fn statements(&mut self, stmts: &[ExprAst], end: usize) -> Option<Doc> {
+        self.lines(stmts, end, ExprAst::span, Self::statement)
+    }
+
+    fn statement(&mut self, stmt: &ExprAst) -> Doc {
+        let doc = self.expr(stmt);
+        if terminated(stmt) {
+            Doc::Concat(vec![doc, text(";")])
+        } else {
+            doc
+        }
+    }
+
+    /// A block, or the statements of `body` between braces.
+    fn block(&mut self, body: &ExprAst) -> Doc {
+        let ExprAst::BlockAst(b) = body else {
+            return self.statement(body);
+        };
+        match self.statements(&b.cont, b.span.end.saturating_sub(1)) {
+            Some(lines) => Doc::Broken(Box::new(Doc::Concat(vec![
+                text("{"),
+                nest(Doc::Concat(vec![Doc::HardLine, lines])),
+                Doc::HardLine,
+                text("}"),
+            ]))),
+            None => text("{}"),
+        }
+    }
+
+    /// The body of a control flow statement, after its header.
+    fn body(&mut self, body: &ExprAst) -> Doc {
+        Doc::Concat(vec![text(" "), self.block(body)])
+    }
+
+    fn params(&mut self, params: &[ExprAst], end: usize) -> Doc {
+        self.list("(", ")", params, end, ExprAst::span, Self::expr)
+    }
+
+    /// A function from its name on.
+    fn function(&mut self, f: &FnDeclExprAst) -> Doc {
+        Doc::Concat(vec![
+            text(&f.name),
+            self.params(&f.args, f.body.span().start),
+            text(" "),
+            self.block(&f.body),
+        ])
+    }
+
+    fn lambda(&mut self, f: &FnDeclExprAst) -> Doc {
+        let params = self.params(&f.args, f.body.span().start);
+        if self.slice(f.span).starts_with("fun") {
+            return Doc::Concat(vec![text("fun "), params, text(" "), self.block(&f.body)]);
+        }
+        let body = match &*f.body {
+            // The expression an arrow function returns is parsed as a block
+            // returning it, spanning the expression only.
+            ExprAst::BlockAst(b) if !self.slice(b.span).starts_with('{') => match &b.cont[..] {
+                [ExprAst::RetAst(r)] => self.expr(&r.val),
+                _ => self.block(&f.body),
+            },
+            body => self.block(body),
+        };
+        Doc::Concat(vec![params, text(" => "), body])
+    }
+
+    fn class(&mut self, c: &ClassDeclExprAst) -> Doc {
+        let mut docs = vec![text(format!("class {}", c.name))];
+        if let Some(superclass) = &c.superclass {
+            docs.extend([text(" < "), self.expr(superclass)]);
+        }
+        let end = c.span.end.saturating_sub(1);
+        match self.lines(&c.methods, end, |m| m.span, Self::function) {
+            Some(lines) => docs.push(Doc::Broken(Box::new(Doc::Concat(vec![
+                text(" {"),
+                nest(Doc::Concat(vec![Doc::HardLine, lines])),
+                Doc::HardLine,
+                text("}"),
+            ])))),
+            None => docs.push(text(" {}")),
+        }
+        Doc::Concat(docs)
+    }
+
+    fn if_stmt(&mut self, i: &IfExprAst) -> Doc {
+        let mut docs = vec![
+            text("if ("),
+            self.expr(&i.cond),
+            text(")"),
+            self.body(&i.then),
+        ];
+        // A missing else branch is an empty block spanning the end of the
+        // then branch.
+        let omitted = matches!(&*i.default, ExprAst::BlockAst(b)
+            if b.cont.is_empty() && !self.slice(b.span).starts_with('{'));
+        if !omitted {
+            if matches!(&*i.then, ExprAst::BlockAst(_)) {
+                docs.push(text(" else"));
+            } else {
+                docs.extend([Doc::HardLine, text("else")]);
+            }
+            docs.push(self.body(&i.default));
+        }
+        Doc::Concat(docs)
+    }
+
+    fn for_stmt(&mut self, f: &ForExprAst) -> Doc {
+        let mut docs = vec![text("for (")];
+        if !self.omitted(&f.lhs) {
+            docs.push(self.expr(&f.lhs));
+        }
+        docs.push(text(";"));
+        if !self.omitted(&f.cond) {
+            docs.extend([text(" "), self.expr(&f.cond)]);
+        }
+        docs.push(text(";"));
+        if !self.omitted(&f.rhs) {
+            docs.extend([text(" "), self.expr(&f.rhs)]);
+        }
+        docs.extend([text(")"), self.body(&f.then)]);
+        Doc::Concat(docs)
+    }
+
+    fn try_stmt(&mut self, t: &TryExprAst) -> Doc {
+        let mut docs = vec![text("try "), self.block(&t.body)];
+        if let Some(catch) = &t.catch {
+            docs.push(text(format!(" catch ({}) ", catch.name.val)));
+            docs.push(self.block(&catch.body));
+        }
+        if let Some(finally) = &t.finally {
+            docs.extend([text(" finally "), self.block(finally)]);
+        }
+        Doc::Concat(docs)
+    }
+
+    fn expr(&mut self, expr: &ExprAst) -> Doc {
+        match expr {
+            ExprAst::BoolAst(b) => text(b.val.to_string()),
+            ExprAst::NumAst(n) => text(self.slice(n.span)),
+            ExprAst::StrAst(s) => text(self.slice(s.span)),
+            ExprAst::ParAst(p) => Doc::Concat(vec![text("("), self.expr(&p.val), text(")")]),
+            ExprAst::IdenAst(id) => text(&id.val),
+            ExprAst::ThisAst(_) => text("this"),
+            ExprAst::NilAst(_) => text("nil"),
+            ExprAst::OpAst(o) => text(o.val.to_usefull_str()),
+            ExprAst::BinaryAst(b) => Doc::Concat(vec![
+                self.expr(&b.lhs),
+                text(format!(" {} ", b.op.to_usefull_str())),
+                self.expr(&b.rhs),
+            ]),
+            ExprAst::UnaryAst(u) => {
+                let op = match u.op {
+                    Token::And | Token::Or => format!("{} ", u.op.to_usefull_str()),
+                    _ => u.op.to_usefull_str(),
+                };
+                Doc::Concat(vec![text(op), self.expr(&u.rhs)])
+            }
+            ExprAst::NegativeAst(n) => Doc::Concat(vec![text("-"), self.expr(&n.rhs)]),
+            ExprAst::IfAst(i) => self.if_stmt(i),
+            ExprAst::WhileAst(w) => Doc::Concat(vec![
+                text("while ("),
+                self.expr(&w.cond),
+                text(")"),
+                self.body(&w.then),
+            ]),
+            ExprAst::ForAst(f) => self.for_stmt(f),
+            ExprAst::AssignAst(a) => {
+                Doc::Concat(vec![text(format!("{} = ", a.lhs.val)), self.expr(&a.rhs)])
+            }
+            ExprAst::VarAssignAst(a) if self.omitted(&a.rhs) => text(format!("var {}", a.lhs.val)),
+            ExprAst::VarAssignAst(a) => Doc::Concat(vec![
+                text(format!("var {} = ", a.lhs.val)),
+                self.expr(&a.rhs),
+            ]),
+            ExprAst::PrintAst(p) => Doc::Concat(vec![text("print "), self.expr(&p.expr)]),
+            ExprAst::BlockAst(_) => self.block(expr),
+            ExprAst::FnCallAst(f) => {
+                let callee = self.expr(&f.lhs);
+                let end = f.span.end.saturating_sub(1);
+                let args = self.list("(", ")", &f.args, end, ExprAst::span, Self::expr);
+                Doc::Concat(vec![callee, args])
+            }
+            ExprAst::FnDeclAst(f) => Doc::Concat(vec![text("fun "), self.function(f)]),
+            ExprAst::LambdaAst(f) => self.lambda(f),
+            ExprAst::RetAst(r) if self.omitted(&r.val) => text("return"),
+            ExprAst::RetAst(r) => Doc::Concat(vec![text("return "), self.expr(&r.val)]),
+            ExprAst::ClassDeclAst(c) => self.class(c),
+            ExprAst::GetAst(g) => {
+                Doc::Concat(vec![self.expr(&g.object), text(format!(".{}", g.name))])
+            }
+            ExprAst::SetAst(s) => Doc::Concat(vec![
+                self.expr(&s.object),
+                text(format!(".{} = ", s.name)),
+                self.expr(&s.val),
+            ]),
+            ExprAst::SuperAst(s) => text(format!("super.{}", s.method)),
+            ExprAst::ImportAst(i) => {
+                // The path as written, escape sequences included.
+                let written = self.slice(i.span);
+                let path = written.find('"').map_or(written, |at| &written[at..]);
+                match &i.name {
+                    Some(name) => text(format!("import {} from {}", name, path)),
+                    None => text(format!("import {}", path)),
+                }
+            }
+            ExprAst::ThrowAst(t) => Doc::Concat(vec![text("throw "), self.expr(&t.val)]),
+            ExprAst::TryAst(t) => self.try_stmt(t),
+            ExprAst::BreakAst(_) => text("break"),
+            ExprAst::ContinueAst(_) => text("continue"),
+            ExprAst::ListAst(l) => {
+                let end = l.span.end.saturating_sub(1);
+                self.list("[", "]", &l.items, end, ExprAst::span, Self::expr)
+            }
+            ExprAst::MapAst(m) => {
+                let end = m.span.end.saturating_sub(1);
+                let entry = |f: &mut Self, (k, v): &(ExprAst, ExprAst)| {
+                    Doc::Concat(vec![f.expr(k), text(": "), f.expr(v)])
+                };
+                let span = |(k, v): &(ExprAst, ExprAst)| k.span().to(v.span());
+                self.list("{", "}", &m.entries, end, span, entry)
+            }
+            ExprAst::IndexAst(i) => Doc::Concat(vec![
+                self.expr(&i.object),
+                text("["),
+                self.expr(&i.index),
+                text("]"),
+            ]),
+            ExprAst::SetIndexAst(s) => Doc::Concat(vec![
+                self.expr(&s.object),
+                text("["),
+                self.expr(&s.index),
+                text("] = "),
+                self.expr(&s.val),
+            ]),
+        }
+    }
+}
+
+/// `source` laid out with the canonical indentation, spacing and line
+/// breaks, comments kept. Lexical and syntax errors are reported on stderr,
+/// nothing is formatted then.
+pub fn format_source(source: &str) -> Result<String, Error> {
+    let out = read_token(source);
+    if !out.errors.is_empty() {
+        for err in &out.errors {
+            eprintln!("{}", err);
+        }
+        return Err(Error::Lexer);
+    }
+    let (stmts, diagnostics) = parse_partial(source, out.tokens);
+    if !diagnostics.is_empty() {
+        for diagnostic in &diagnostics {
+            eprintln!("{}", diagnostic);
+        }
+        return Err(Error::Parser);
+    }
+    let mut formatter = Formatter {
+        source,
+        comments: out.comments,
+        next: 0,
+    };
+    let doc = formatter.statements(&stmts, source.len());
+    Ok(doc.map(|doc| render(&doc)).unwrap_or_default())
+}
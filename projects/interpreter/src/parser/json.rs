@@ -0,0 +1,274 @@
+use super::core::parse_partial;
+use super::parser_ds::*;
+use crate::error::{Diagnostic, Error, LexError};
+use crate::json::Json;
+use crate::lexer::{read_token, Span, SpannedToken, Token};
+
+/// Version of the documents `tokenize` and `parse` print with
+/// `--format=json`, raised whenever their shape changes.
+pub const FORMAT_VERSION: usize = 1;
+
+fn span(span: Span) -> Json {
+    Json::object([
+        ("line", span.line.into()),
+        ("column", span.column.into()),
+        ("start", span.start.into()),
+        ("end", span.end.into()),
+    ])
+}
+
+/// An object for a node of type `kind`, its span followed by `fields`.
+fn node<'a>(kind: &str, at: Span, fields: impl IntoIterator<Item = (&'a str, Json)>) -> Json {
+    let mut members = vec![("type", Json::str(kind)), ("span", span(at))];
+    members.extend(fields);
+    Json::object(members)
+}
+
+fn nodes(exprs: &[ExprAst]) -> Json {
+    Json::Array(exprs.iter().map(ExprAst::to_json).collect())
+}
+
+fn function(kind: &str, f: &FnDeclExprAst) -> Json {
+    node(
+        kind,
+        f.span,
+        [
+            ("name", Json::str(&f.name)),
+            ("params", nodes(&f.args)),
+            ("body", f.body.to_json()),
+        ],
+    )
+}
+
+impl ExprAst {
+    /// The node and everything below it, each with its type and span.
+    pub fn to_json(&self) -> Json {
+        match self {
+            Self::BoolAst(b) => node("Bool", b.span, [("value", Json::Bool(b.val))]),
+            Self::NumAst(n) => node("Number", n.span, [("value", Json::Num(n.number))]),
+            Self::StrAst(s) => node("String", s.span, [("value", Json::str(&s.val))]),
+            Self::ParAst(p) => node("Grouping", p.span, [("expression", p.val.to_json())]),
+            Self::IdenAst(id) => node("Variable", id.span, [("name", Json::str(&id.val))]),
+            Self::NilAst(at) => node("Nil", *at, []),
+            Self::OpAst(o) => node(
+                "Operator",
+                o.span,
+                [("operator", Json::str(o.val.to_usefull_str()))],
+            ),
+            Self::BinaryAst(b) => node(
+                "Binary",
+                b.span,
+                [
+                    ("operator", Json::str(b.op.to_usefull_str())),
+                    ("left", b.lhs.to_json()),
+                    ("right", b.rhs.to_json()),
+                ],
+            ),
+            Self::UnaryAst(u) => node(
+                "Unary",
+                u.span,
+                [
+                    ("operator", Json::str(u.op.to_usefull_str())),
+                    ("operand", u.rhs.to_json()),
+                ],
+            ),
+            Self::NegativeAst(n) => node("Negate", n.span, [("operand", n.rhs.to_json())]),
+            Self::IfAst(i) => node(
+                "If",
+                i.span,
+                [
+                    ("condition", i.cond.to_json()),
+                    ("then", i.then.to_json()),
+                    ("else", i.default.to_json()),
+                ],
+            ),
+            Self::WhileAst(w) => node(
+                "While",
+                w.span,
+                [("condition", w.cond.to_json()), ("body", w.then.to_json())],
+            ),
+            Self::ForAst(f) => node(
+                "For",
+                f.span,
+                [
+                    ("initializer", f.lhs.to_json()),
+                    ("condition", f.cond.to_json()),
+                    ("increment", f.rhs.to_json()),
+                    ("body", f.then.to_json()),
+                ],
+            ),
+            Self::AssignAst(a) | Self::VarAssignAst(a) => node(
+                if matches!(self, Self::VarAssignAst(_)) {
+                    "Var"
+                } else {
+                    "Assign"
+                },
+                a.span,
+                [("name", Json::str(&a.lhs.val)), ("value", a.rhs.to_json())],
+            ),
+            Self::PrintAst(p) => node("Print", p.span, [("expression", p.expr.to_json())]),
+            Self::BlockAst(b) => node("Block", b.span, [("statements", nodes(&b.cont))]),
+            Self::FnCallAst(f) => node(
+                "Call",
+                f.span,
+                [("callee", f.lhs.to_json()), ("arguments", nodes(&f.args))],
+            ),
+            Self::FnDeclAst(f) => function("Function", f),
+            Self::LambdaAst(f) => function("Lambda", f),
+            Self::RetAst(r) => node("Return", r.span, [("value", r.val.to_json())]),
+            Self::ClassDeclAst(c) => node(
+                "Class",
+                c.span,
+                [
+                    ("name", Json::str(&c.name)),
+                    (
+                        "superclass",
+                        c.superclass.as_ref().map(|s| s.to_json()).into(),
+                    ),
+                    (
+                        "methods",
+                        Json::Array(c.methods.iter().map(|m| function("Function", m)).collect()),
+                    ),
+                ],
+            ),
+            Self::GetAst(g) => node(
+                "Get",
+                g.span,
+                [("object", g.object.to_json()), ("name", Json::str(&g.name))],
+            ),
+            Self::SetAst(s) => node(
+                "Set",
+                s.span,
+                [
+                    ("object", s.object.to_json()),
+                    ("name", Json::str(&s.name)),
+                    ("value", s.val.to_json()),
+                ],
+            ),
+            Self::ThisAst(th) => node("This", th.span, []),
+            Self::SuperAst(su) => node("Super", su.span, [("method", Json::str(&su.method))]),
+            Self::ImportAst(i) => node(
+                "Import",
+                i.span,
+                [
+                    ("path", Json::str(&i.path)),
+                    ("name", i.name.clone().into()),
+                ],
+            ),
+            Self::ThrowAst(t) => node("Throw", t.span, [("value", t.val.to_json())]),
+            Self::TryAst(t) => {
+                let catch = t.catch.as_ref().map(|c| {
+                    Json::object([
+                        ("name", Self::IdenAst(c.name.clone()).to_json()),
+                        ("body", c.body.to_json()),
+                    ])
+                });
+                node(
+                    "Try",
+                    t.span,
+                    [
+                        ("body", t.body.to_json()),
+                        ("catch", catch.into()),
+                        ("finally", t.finally.as_ref().map(|f| f.to_json()).into()),
+                    ],
+                )
+            }
+            Self::BreakAst(at) => node("Break", *at, []),
+            Self::ContinueAst(at) => node("Continue", *at, []),
+            Self::ListAst(l) => node("List", l.span, [("items", nodes(&l.items))]),
+            Self::MapAst(m) => {
+                let entries = m
+                    .entries
+                    .iter()
+                    .map(|(k, v)| Json::object([("key", k.to_json()), ("value", v.to_json())]))
+                    .collect();
+                node("Map", m.span, [("entries", Json::Array(entries))])
+            }
+            Self::IndexAst(i) => node(
+                "Index",
+                i.span,
+                [("object", i.object.to_json()), ("index", i.index.to_json())],
+            ),
+            Self::SetIndexAst(s) => node(
+                "SetIndex",
+                s.span,
+                [
+                    ("object", s.object.to_json()),
+                    ("index", s.index.to_json()),
+                    ("value", s.val.to_json()),
+                ],
+            ),
+        }
+    }
+}
+
+fn token(t: &SpannedToken) -> Json {
+    let (lexeme, literal) = match &t.token {
+        Token::LoxString(lexeme, s) => (format!("\"{}\"", lexeme), Json::str(s)),
+        Token::Number(lexeme, n) => (lexeme.clone(), Json::Num(*n)),
+        Token::Eof => (String::new(), Json::Null),
+        other => (other.to_usefull_str(), Json::Null),
+    };
+    Json::object([
+        ("type", Json::str(t.token.name())),
+        ("lexeme", Json::Str(lexeme)),
+        ("literal", literal),
+        ("span", span(t.span)),
+    ])
+}
+
+fn lex_error(err: &LexError) -> Json {
+    Json::object([
+        ("message", Json::str(&err.message)),
+        ("span", span(err.span)),
+    ])
+}
+
+fn syntax_error(diagnostic: &Diagnostic) -> Json {
+    Json::object([
+        ("message", Json::Str(diagnostic.to_string())),
+        ("span", span(diagnostic.span)),
+    ])
+}
+
+fn document<'a>(members: impl IntoIterator<Item = (&'a str, Json)>, errors: Vec<Json>) -> Json {
+    let mut all = vec![("version", FORMAT_VERSION.into())];
+    all.extend(members);
+    all.push(("errors", Json::Array(errors)));
+    Json::object(all)
+}
+
+/// The tokens of `source` for `tokenize --format=json`, and the error to exit
+/// with when it has lexical errors, which the document lists.
+pub fn tokens_json(source: &str) -> (Json, Result<(), Error>) {
+    let out = read_token(source);
+    let tokens = out.tokens.iter().map(token).collect();
+    let errors: Vec<Json> = out.errors.iter().map(lex_error).collect();
+    let r = if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(Error::Lexer)
+    };
+    (document([("tokens", Json::Array(tokens))], errors), r)
+}
+
+/// The syntax tree of `source` for `parse --format=json`, and the error to
+/// exit with when it has lexical or syntax errors, which the document lists.
+/// The statements parsed despite syntax errors are kept; lexical errors leave
+/// none.
+pub fn ast_json(source: &str) -> (Json, Result<(), Error>) {
+    let out = read_token(source);
+    if !out.errors.is_empty() {
+        let errors = out.errors.iter().map(lex_error).collect();
+        let doc = document([("statements", Json::Array(Vec::new()))], errors);
+        return (doc, Err(Error::Lexer));
+    }
+    let (stmts, diagnostics) = parse_partial(source, out.tokens);
+    let r = if diagnostics.is_empty() {
+        Ok(())
+    } else {
+        Err(Error::Parser)
+    };
+    let errors = diagnostics.iter().map(syntax_error).collect();
+    (document([("statements", nodes(&stmts))], errors), r)
+}
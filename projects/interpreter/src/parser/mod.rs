@@ -1,2 +1,4 @@
 pub mod core;
+pub mod format;
+pub mod json;
 pub mod parser_ds;
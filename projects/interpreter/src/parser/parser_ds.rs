@@ -1,28 +1,44 @@
-use crate::lexer::Token;
+use crate::lexer::{number_literal, Span, Token};
 use std::fmt;
 
 #[derive(Debug, Clone)]
 pub struct BoolExprAst {
     pub val: bool,
+    pub span: Span,
 }
 #[derive(Debug, Clone)]
 pub struct NumberExprAst {
-    pub number: String,
+    pub number: f64,
+    /// The literal as written.
+    pub lexeme: String,
+    pub span: Span,
 }
 
 #[derive(Debug, Clone)]
 pub struct NegativeExprAst {
     pub rhs: Box<ExprAst>,
+    pub span: Span,
 }
 
 #[derive(Debug, Clone)]
 pub struct StringExprAst {
     pub val: String,
+    pub span: Span,
+}
+
+/// Where a local variable lives, as computed by the resolver: how many scopes
+/// to walk up from the current one, and its position in that scope.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Slot {
+    pub depth: usize,
+    pub index: usize,
 }
 
 #[derive(Clone)]
 pub struct IdentExprAst {
     pub val: String,
+    pub slot: Option<Slot>,
+    pub span: Span,
 }
 
 impl fmt::Debug for IdentExprAst {
@@ -34,6 +50,7 @@ impl fmt::Debug for IdentExprAst {
 #[derive(Debug, Clone)]
 pub struct OperExprAst {
     pub val: Token,
+    pub span: Span,
 }
 
 #[derive(Debug, Clone)]
@@ -41,17 +58,20 @@ pub struct BinExprAst {
     pub lhs: Box<ExprAst>,
     pub rhs: Box<ExprAst>,
     pub op: Token,
+    pub span: Span,
 }
 
 #[derive(Debug, Clone)]
 pub struct UnaExprAst {
     pub op: Token,
     pub rhs: Box<ExprAst>,
+    pub span: Span,
 }
 
 #[derive(Debug, Clone)]
 pub struct PrintExprAst {
     pub expr: Box<ExprAst>,
+    pub span: Span,
 }
 
 #[derive(Debug, Clone)]
@@ -59,12 +79,14 @@ pub struct IfExprAst {
     pub cond: Box<ExprAst>,
     pub then: Box<ExprAst>,
     pub default: Box<ExprAst>,
+    pub span: Span,
 }
 
 #[derive(Debug, Clone)]
 pub struct WhileExprAst {
     pub cond: Box<ExprAst>,
     pub then: Box<ExprAst>,
+    pub span: Span,
 }
 
 #[derive(Debug, Clone)]
@@ -73,42 +95,139 @@ pub struct ForExprAst {
     pub cond: Box<ExprAst>,
     pub rhs: Box<ExprAst>,
     pub then: Box<ExprAst>,
+    pub span: Span,
 }
 
 #[derive(Debug, Clone)]
 pub struct AssignExprAst {
-    pub lhs: Box<ExprAst>,
+    pub lhs: IdentExprAst,
     pub rhs: Box<ExprAst>,
+    pub span: Span,
 }
 
 #[derive(Debug, Clone)]
 pub struct BlockExprAst {
     pub cont: Vec<ExprAst>,
+    pub span: Span,
 }
 
 #[derive(Debug, Clone)]
 pub struct FnDeclExprAst {
     pub name: String,
+    pub slot: Option<Slot>,
     pub args: Vec<ExprAst>,
     pub body: Box<ExprAst>,
+    pub span: Span,
 }
 
 #[derive(Debug, Clone)]
 pub struct FnCallExprAst {
     pub lhs: Box<ExprAst>,
     pub args: Vec<ExprAst>,
+    pub span: Span,
 }
 
 #[derive(Debug, Clone)]
 pub struct RetExprAst {
     pub val: Box<ExprAst>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone)]
+pub struct ClassDeclExprAst {
+    pub name: String,
+    pub slot: Option<Slot>,
+    pub superclass: Option<Box<ExprAst>>,
+    pub methods: Vec<FnDeclExprAst>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone)]
+pub struct GetExprAst {
+    pub object: Box<ExprAst>,
+    pub name: String,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone)]
+pub struct SetExprAst {
+    pub object: Box<ExprAst>,
+    pub name: String,
+    pub val: Box<ExprAst>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone)]
+pub struct SuperExprAst {
+    pub method: String,
+    pub slot: Option<Slot>,
+    pub span: Span,
+}
+
+/// `import "path";`, or `import name from "path";` when `name` is set.
+#[derive(Debug, Clone)]
+pub struct ImportExprAst {
+    pub path: String,
+    pub name: Option<String>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone)]
+pub struct ThrowExprAst {
+    pub val: Box<ExprAst>,
+    pub span: Span,
+}
+
+/// The `catch (name) { body }` clause of a `try` statement.
+#[derive(Debug, Clone)]
+pub struct CatchExprAst {
+    pub name: IdentExprAst,
+    pub body: Box<ExprAst>,
+}
+
+/// `try { body }`, followed by a `catch` clause, a `finally` block or both.
+#[derive(Debug, Clone)]
+pub struct TryExprAst {
+    pub body: Box<ExprAst>,
+    pub catch: Option<CatchExprAst>,
+    pub finally: Option<Box<ExprAst>>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone)]
+pub struct ListExprAst {
+    pub items: Vec<ExprAst>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone)]
+pub struct MapExprAst {
+    pub entries: Vec<(ExprAst, ExprAst)>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone)]
+pub struct IndexExprAst {
+    pub object: Box<ExprAst>,
+    pub index: Box<ExprAst>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone)]
+pub struct SetIndexExprAst {
+    pub object: Box<ExprAst>,
+    pub index: Box<ExprAst>,
+    pub val: Box<ExprAst>,
+    pub span: Span,
 }
 
 #[derive(Debug, Clone)]
 pub struct ParExprAst {
     pub val: Box<ExprAst>,
+    pub span: Span,
 }
 
+#[allow(clippy::enum_variant_names)]
 #[derive(Debug, Clone)]
 pub enum ExprAst {
     BoolAst(BoolExprAst),
@@ -116,7 +235,7 @@ pub enum ExprAst {
     StrAst(StringExprAst),
     ParAst(ParExprAst),
     IdenAst(IdentExprAst),
-    NilAst,
+    NilAst(Span),
     OpAst(OperExprAst),
     BinaryAst(BinExprAst),
     UnaryAst(UnaExprAst),
@@ -130,7 +249,23 @@ pub enum ExprAst {
     BlockAst(BlockExprAst),
     FnCallAst(FnCallExprAst),
     FnDeclAst(FnDeclExprAst),
+    /// An anonymous function, `fun (a) { ... }` or `(a) => ...`.
+    LambdaAst(FnDeclExprAst),
     RetAst(RetExprAst),
+    ClassDeclAst(ClassDeclExprAst),
+    GetAst(GetExprAst),
+    SetAst(SetExprAst),
+    ThisAst(IdentExprAst),
+    SuperAst(SuperExprAst),
+    ImportAst(ImportExprAst),
+    ThrowAst(ThrowExprAst),
+    TryAst(TryExprAst),
+    BreakAst(Span),
+    ContinueAst(Span),
+    ListAst(ListExprAst),
+    MapAst(MapExprAst),
+    IndexAst(IndexExprAst),
+    SetIndexAst(SetIndexExprAst),
 }
 
 bitflags::bitflags! {
@@ -138,52 +273,135 @@ bitflags::bitflags! {
         const EVALUATE   = 0b0001;
         const DEBUG      = 0b0010;
         const RUN        = 0b0100;
+        /// With `RUN`, prints the value of every expression statement.
+        const ECHO       = 0b1000;
+        /// Lets the last statement go without its `;`, for sources that are
+        /// a single expression.
+        const OPEN_END   = 0b1_0000;
     }
 }
 
-impl ExprAst {
-    pub fn to_string(&self) -> String {
-        match &self {
+impl fmt::Display for ExprAst {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match &self {
             Self::BoolAst(b) => format!("{}", b.val),
-            Self::NumAst(n) => {
-                if n.number.chars().next().unwrap() == '-' {
-                    format!("(- {})", &n.number[1..])
-                } else {
-                    format!("{}", n.number)
-                }
-            }
+            Self::NumAst(n) => number_literal(&n.lexeme, n.number),
             Self::FnCallAst(f) => {
                 let mut args = String::new();
                 for arg in &f.args {
-                    args.push_str(&format!("{} ", arg.to_string()));
+                    args.push_str(&format!("{} ", arg));
                 }
-                format!("({} {})", f.lhs.to_string(), args)
+                format!("({} {})", f.lhs, args)
             }
             Self::ParAst(p) => {
-                format!("(group {})", p.val.to_string())
+                format!("(group {})", p.val)
             }
-            Self::StrAst(s) => format!("{}", s.val),
-            Self::IdenAst(id) => format!("{}", id.val),
+            Self::StrAst(s) => s.val.to_string(),
+            Self::IdenAst(id) => id.val.to_string(),
             Self::OpAst(o) => o.val.to_usefull_str(),
-            Self::NilAst => format!("nil"),
+            Self::NilAst(_) => "nil".to_string(),
             Self::BinaryAst(s) => {
-                format!(
-                    "({} {} {})",
-                    s.op.to_usefull_str(),
-                    s.lhs.to_string(),
-                    s.rhs.to_string()
-                )
+                format!("({} {} {})", s.op.to_usefull_str(), s.lhs, s.rhs)
             } //println!("{:?}", s)
             Self::UnaryAst(s) => {
-                format!("({} {})", s.op.to_usefull_str(), s.rhs.to_string())
+                format!("({} {})", s.op.to_usefull_str(), s.rhs)
             }
             Self::NegativeAst(e) => {
-                format!("(- {})", e.rhs.to_string())
+                format!("(- {})", e.rhs)
+            }
+            Self::GetAst(g) => format!("(. {} {})", g.object, g.name),
+            Self::SetAst(s) => {
+                format!("(= (. {} {}) {})", s.object, s.name, s.val)
+            }
+            Self::ThisAst(_) => "this".to_string(),
+            Self::SuperAst(su) => format!("(. super {})", su.method),
+            Self::ImportAst(i) => match &i.name {
+                Some(name) => format!("(import {} {})", name, i.path),
+                None => format!("(import {})", i.path),
+            },
+            Self::ThrowAst(t) => format!("(throw {})", t.val),
+            Self::ListAst(l) => {
+                let items: Vec<String> = l.items.iter().map(|i| i.to_string()).collect();
+                format!("(list {})", items.join(" "))
+            }
+            Self::MapAst(m) => {
+                let entries: Vec<String> = m
+                    .entries
+                    .iter()
+                    .map(|(k, v)| format!("({} {})", k, v))
+                    .collect();
+                format!("(map {})", entries.join(" "))
+            }
+            Self::IndexAst(i) => format!("([] {} {})", i.object, i.index),
+            Self::SetIndexAst(s) => {
+                format!("(= ([] {} {}) {})", s.object, s.index, s.val)
             }
             _ => format!("{:?}", self),
+        };
+        f.write_str(&s)
+    }
+}
+
+impl ExprAst {
+    /// The part of the source this node was parsed from.
+    pub fn span(&self) -> Span {
+        match self {
+            Self::BoolAst(b) => b.span,
+            Self::NumAst(n) => n.span,
+            Self::StrAst(s) => s.span,
+            Self::ParAst(p) => p.span,
+            Self::IdenAst(id) | Self::ThisAst(id) => id.span,
+            Self::NilAst(span) | Self::BreakAst(span) | Self::ContinueAst(span) => *span,
+            Self::OpAst(o) => o.span,
+            Self::BinaryAst(b) => b.span,
+            Self::UnaryAst(u) => u.span,
+            Self::IfAst(i) => i.span,
+            Self::WhileAst(w) => w.span,
+            Self::ForAst(f) => f.span,
+            Self::NegativeAst(n) => n.span,
+            Self::AssignAst(a) | Self::VarAssignAst(a) => a.span,
+            Self::PrintAst(p) => p.span,
+            Self::BlockAst(b) => b.span,
+            Self::FnCallAst(f) => f.span,
+            Self::FnDeclAst(f) | Self::LambdaAst(f) => f.span,
+            Self::RetAst(r) => r.span,
+            Self::ClassDeclAst(c) => c.span,
+            Self::ImportAst(i) => i.span,
+            Self::ThrowAst(t) => t.span,
+            Self::TryAst(t) => t.span,
+            Self::GetAst(g) => g.span,
+            Self::SetAst(s) => s.span,
+            Self::SuperAst(s) => s.span,
+            Self::ListAst(l) => l.span,
+            Self::MapAst(m) => m.span,
+            Self::IndexAst(i) => i.span,
+            Self::SetIndexAst(s) => s.span,
         }
     }
+
+    /// Whether this node is an expression statement, as opposed to a
+    /// declaration or a control flow statement.
+    pub fn is_expression(&self) -> bool {
+        !matches!(
+            self,
+            Self::IfAst(_)
+                | Self::WhileAst(_)
+                | Self::ForAst(_)
+                | Self::VarAssignAst(_)
+                | Self::PrintAst(_)
+                | Self::BlockAst(_)
+                | Self::FnDeclAst(_)
+                | Self::RetAst(_)
+                | Self::ClassDeclAst(_)
+                | Self::ImportAst(_)
+                | Self::ThrowAst(_)
+                | Self::TryAst(_)
+                | Self::BreakAst(_)
+                | Self::ContinueAst(_)
+        )
+    }
+
     pub fn print_ast(&self) {
-        println!("{}", self.to_string());
+        println!("{}", self);
     }
 }
@@ -0,0 +1,101 @@
+use codecrafters_interpreter::backend::{new_backend, Backend, BackendKind};
+use codecrafters_interpreter::lexer::read_token;
+use codecrafters_interpreter::limits::Limits;
+use codecrafters_interpreter::parser::core::opti_run;
+use codecrafters_interpreter::parser::parser_ds::ParserOptions;
+use std::cell::RefCell;
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::rc::Rc;
+
+const HELP: &str = "\
+:load <file>  run a file in the current session
+:env          list the global variables
+:reset        forget every definition
+:help         show this message
+:quit         leave the repl";
+
+/// Interactive session: every line runs against the same backend, so
+/// definitions stay around until `:reset`.
+pub struct Repl {
+    kind: BackendKind,
+    backend: Rc<RefCell<dyn Backend>>,
+    limits: Limits,
+}
+
+impl Repl {
+    pub fn new(kind: BackendKind, limits: Limits) -> Self {
+        Self {
+            kind,
+            backend: new_backend(kind),
+            limits,
+        }
+    }
+
+    /// Reads lines from stdin until end of input or `:quit`.
+    pub fn run(&mut self) {
+        let stdin = io::stdin();
+        let mut lines = stdin.lock().lines();
+        let mut buffer = String::new();
+        loop {
+            print!("{}", if buffer.is_empty() { "> " } else { "... " });
+            let _ = io::stdout().flush();
+            let Some(Ok(line)) = lines.next() else {
+                println!();
+                break;
+            };
+            if buffer.is_empty() {
+                match line.trim() {
+                    "" => continue,
+                    cmd if cmd.starts_with(':') => {
+                        if !self.command(cmd) {
+                            break;
+                        }
+                        continue;
+                    }
+                    _ => (),
+                }
+            }
+            buffer.push_str(&line);
+            buffer.push('\n');
+            // Keep reading while a block is still open.
+            if read_token(&buffer).braces_depth > 0 {
+                continue;
+            }
+            self.eval(std::mem::take(&mut buffer), ParserOptions::ECHO);
+        }
+    }
+
+    /// Runs a `:` command, returns false when the session should end.
+    fn command(&mut self, cmd: &str) -> bool {
+        let (name, arg) = cmd.split_once(' ').unwrap_or((cmd, ""));
+        match (name, arg.trim()) {
+            (":quit" | ":q", _) => return false,
+            (":help", _) => println!("{}", HELP),
+            (":reset", _) => self.backend = new_backend(self.kind),
+            (":env", _) => {
+                for (name, val) in self.backend.borrow().globals() {
+                    println!("{} = {}", name, val);
+                }
+            }
+            (":load", "") => eprintln!("Usage: :load <file>"),
+            (":load", file) => match fs::read_to_string(file) {
+                Ok(source) => self.eval(source, ParserOptions::empty()),
+                Err(err) => eprintln!("Failed to read file {}: {}", file, err),
+            },
+            _ => eprintln!("Unknown command: {} (try :help)", name),
+        }
+        true
+    }
+
+    /// Errors are already reported on stderr, the session goes on after them.
+    /// Each input gets the whole of the limits.
+    fn eval(&mut self, source: String, options: ParserOptions) {
+        self.backend.borrow_mut().set_limits(self.limits);
+        let _ = opti_run(
+            source,
+            Some(self.backend.clone()),
+            ParserOptions::RUN | options,
+        );
+    }
+}
@@ -0,0 +1,361 @@
+use crate::error::Diagnostic;
+use crate::lexer::Span;
+use crate::parser::parser_ds::*;
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum FunctionKind {
+    None,
+    Function,
+    Method,
+    Initializer,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ClassKind {
+    None,
+    Class,
+    Subclass,
+}
+
+#[derive(Debug)]
+struct Local {
+    index: usize,
+    defined: bool,
+}
+
+/// Walks the AST once before evaluation, binds every local variable use to
+/// the scope it was declared in and reports the static errors of the language.
+pub struct Resolver {
+    scopes: Vec<HashMap<String, Local>>,
+    curr_function: FunctionKind,
+    curr_class: ClassKind,
+    /// Number of loops around the current statement, in the current function.
+    loop_depth: usize,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Resolver {
+    pub fn new() -> Self {
+        Self {
+            scopes: Vec::new(),
+            curr_function: FunctionKind::None,
+            curr_class: ClassKind::None,
+            loop_depth: 0,
+            diagnostics: Vec::new(),
+        }
+    }
+
+    /// Resolves `stmts`, or returns every error found in them.
+    pub fn resolve(&mut self, stmts: &mut [ExprAst]) -> Result<(), Vec<Diagnostic>> {
+        for stmt in stmts.iter_mut() {
+            self.resolve_expr(stmt);
+        }
+        if !self.diagnostics.is_empty() {
+            return Err(std::mem::take(&mut self.diagnostics));
+        }
+        Ok(())
+    }
+
+    fn error(&mut self, span: Span, name: &str, msg: &str) {
+        self.diagnostics.push(Diagnostic {
+            message: msg.to_string(),
+            span,
+            lexeme: Some(name.to_string()),
+        });
+    }
+
+    fn begin_scope(&mut self) {
+        self.scopes.push(HashMap::new());
+    }
+
+    fn end_scope(&mut self) {
+        self.scopes.pop();
+    }
+
+    fn declare(&mut self, name: &str, span: Span) -> Option<Slot> {
+        let scope = self.scopes.last_mut()?;
+        if let Some(local) = scope.get(name) {
+            let index = local.index;
+            self.error(
+                span,
+                name,
+                "Already a variable with this name in this scope.",
+            );
+            return Some(Slot { depth: 0, index });
+        }
+        let index = scope.len();
+        scope.insert(
+            name.to_string(),
+            Local {
+                index,
+                defined: false,
+            },
+        );
+        Some(Slot { depth: 0, index })
+    }
+
+    fn define(&mut self, name: &str) {
+        if let Some(local) = self.scopes.last_mut().and_then(|s| s.get_mut(name)) {
+            local.defined = true;
+        }
+    }
+
+    fn resolve_local(&self, name: &str) -> Option<Slot> {
+        for (depth, scope) in self.scopes.iter().rev().enumerate() {
+            if let Some(local) = scope.get(name) {
+                return Some(Slot {
+                    depth,
+                    index: local.index,
+                });
+            }
+        }
+        None
+    }
+
+    fn resolve_ident(&mut self, id: &mut IdentExprAst) {
+        let in_initializer = self
+            .scopes
+            .last()
+            .and_then(|s| s.get(&id.val))
+            .is_some_and(|l| !l.defined);
+        if in_initializer {
+            self.error(
+                id.span,
+                &id.val,
+                "Can't read local variable in its own initializer.",
+            );
+        }
+        id.slot = self.resolve_local(&id.val);
+    }
+
+    fn resolve_function(&mut self, func: &mut FnDeclExprAst, kind: FunctionKind) {
+        let enclosing = self.curr_function;
+        let enclosing_loops = self.loop_depth;
+        self.curr_function = kind;
+        self.loop_depth = 0;
+        self.begin_scope();
+        for arg in func.args.iter() {
+            let name = arg.to_string();
+            self.declare(&name, arg.span());
+            self.define(&name);
+        }
+        // The body shares the scope of the parameters.
+        match func.body.as_mut() {
+            ExprAst::BlockAst(b) => {
+                for expr in b.cont.iter_mut() {
+                    self.resolve_expr(expr);
+                }
+            }
+            body => self.resolve_expr(body),
+        }
+        self.end_scope();
+        self.curr_function = enclosing;
+        self.loop_depth = enclosing_loops;
+    }
+
+    fn resolve_class(&mut self, class: &mut ClassDeclExprAst) {
+        let enclosing = self.curr_class;
+        self.curr_class = ClassKind::Class;
+        class.slot = self.declare(&class.name, class.span);
+        self.define(&class.name);
+
+        if let Some(ref mut superclass) = class.superclass {
+            self.curr_class = ClassKind::Subclass;
+            self.resolve_expr(superclass);
+            self.begin_scope();
+            self.declare("super", class.span);
+            self.define("super");
+        }
+
+        self.begin_scope();
+        self.declare("this", class.span);
+        self.define("this");
+        for method in class.methods.iter_mut() {
+            let kind = if method.name == "init" {
+                FunctionKind::Initializer
+            } else {
+                FunctionKind::Method
+            };
+            self.resolve_function(method, kind);
+        }
+        self.end_scope();
+
+        if class.superclass.is_some() {
+            self.end_scope();
+        }
+        self.curr_class = enclosing;
+    }
+
+    fn resolve_loop_body(&mut self, body: &mut ExprAst) {
+        self.loop_depth += 1;
+        self.resolve_expr(body);
+        self.loop_depth -= 1;
+    }
+
+    fn resolve_expr(&mut self, expr: &mut ExprAst) {
+        match expr {
+            ExprAst::BoolAst(_)
+            | ExprAst::NumAst(_)
+            | ExprAst::StrAst(_)
+            | ExprAst::NilAst(_)
+            | ExprAst::OpAst(_) => (),
+            ExprAst::ParAst(p) => self.resolve_expr(&mut p.val),
+            ExprAst::NegativeAst(n) => self.resolve_expr(&mut n.rhs),
+            ExprAst::UnaryAst(u) => self.resolve_expr(&mut u.rhs),
+            ExprAst::BinaryAst(b) => {
+                self.resolve_expr(&mut b.lhs);
+                self.resolve_expr(&mut b.rhs);
+            }
+            ExprAst::IdenAst(id) => self.resolve_ident(id),
+            ExprAst::PrintAst(p) => self.resolve_expr(&mut p.expr),
+            ExprAst::IfAst(i) => {
+                self.resolve_expr(&mut i.cond);
+                self.resolve_expr(&mut i.then);
+                self.resolve_expr(&mut i.default);
+            }
+            ExprAst::WhileAst(w) => {
+                self.resolve_expr(&mut w.cond);
+                self.resolve_loop_body(&mut w.then);
+            }
+            ExprAst::ForAst(f) => {
+                self.begin_scope();
+                self.resolve_expr(&mut f.lhs);
+                self.resolve_expr(&mut f.cond);
+                self.resolve_expr(&mut f.rhs);
+                self.resolve_loop_body(&mut f.then);
+                self.end_scope();
+            }
+            ExprAst::BreakAst(span) => {
+                if self.loop_depth == 0 {
+                    self.error(*span, "break", "Can't use 'break' outside of a loop.");
+                }
+            }
+            ExprAst::ContinueAst(span) => {
+                if self.loop_depth == 0 {
+                    self.error(*span, "continue", "Can't use 'continue' outside of a loop.");
+                }
+            }
+            ExprAst::AssignAst(a) => {
+                self.resolve_expr(&mut a.rhs);
+                a.lhs.slot = self.resolve_local(&a.lhs.val);
+            }
+            ExprAst::VarAssignAst(a) => {
+                a.lhs.slot = self.declare(&a.lhs.val, a.lhs.span);
+                self.resolve_expr(&mut a.rhs);
+                self.define(&a.lhs.val);
+            }
+            ExprAst::BlockAst(b) => {
+                self.begin_scope();
+                for expr in b.cont.iter_mut() {
+                    self.resolve_expr(expr);
+                }
+                self.end_scope();
+            }
+            ExprAst::FnCallAst(f) => {
+                self.resolve_expr(&mut f.lhs);
+                for arg in f.args.iter_mut() {
+                    self.resolve_expr(arg);
+                }
+            }
+            ExprAst::FnDeclAst(f) => {
+                f.slot = self.declare(&f.name, f.span);
+                self.define(&f.name);
+                self.resolve_function(f, FunctionKind::Function);
+            }
+            ExprAst::LambdaAst(f) => self.resolve_function(f, FunctionKind::Function),
+            ExprAst::RetAst(r) => {
+                if self.curr_function == FunctionKind::None {
+                    self.error(r.span, "return", "Can't return from top-level code.");
+                }
+                if self.curr_function == FunctionKind::Initializer
+                    && !matches!(*r.val, ExprAst::NilAst(_))
+                {
+                    self.error(
+                        r.span,
+                        "return",
+                        "Can't return a value from an initializer.",
+                    );
+                }
+                self.resolve_expr(&mut r.val);
+            }
+            ExprAst::ClassDeclAst(c) => self.resolve_class(c),
+            ExprAst::ImportAst(i) => {
+                // Imports define globals of the module being run.
+                if !self.scopes.is_empty() || self.curr_function != FunctionKind::None {
+                    self.error(i.span, "import", "Can't import outside of top-level code.");
+                }
+            }
+            ExprAst::ThrowAst(t) => self.resolve_expr(&mut t.val),
+            ExprAst::TryAst(t) => {
+                self.resolve_expr(&mut t.body);
+                if let Some(ref mut catch) = t.catch {
+                    // The catch body shares the scope of the error variable.
+                    self.begin_scope();
+                    catch.name.slot = self.declare(&catch.name.val, catch.name.span);
+                    self.define(&catch.name.val);
+                    if let ExprAst::BlockAst(b) = catch.body.as_mut() {
+                        for expr in b.cont.iter_mut() {
+                            self.resolve_expr(expr);
+                        }
+                    }
+                    self.end_scope();
+                }
+                if let Some(ref mut finally) = t.finally {
+                    self.resolve_expr(finally);
+                }
+            }
+            ExprAst::ListAst(l) => {
+                for item in l.items.iter_mut() {
+                    self.resolve_expr(item);
+                }
+            }
+            ExprAst::MapAst(m) => {
+                for (key, val) in m.entries.iter_mut() {
+                    self.resolve_expr(key);
+                    self.resolve_expr(val);
+                }
+            }
+            ExprAst::IndexAst(i) => {
+                self.resolve_expr(&mut i.object);
+                self.resolve_expr(&mut i.index);
+            }
+            ExprAst::SetIndexAst(s) => {
+                self.resolve_expr(&mut s.object);
+                self.resolve_expr(&mut s.index);
+                self.resolve_expr(&mut s.val);
+            }
+            ExprAst::GetAst(g) => self.resolve_expr(&mut g.object),
+            ExprAst::SetAst(s) => {
+                self.resolve_expr(&mut s.val);
+                self.resolve_expr(&mut s.object);
+            }
+            ExprAst::ThisAst(id) => {
+                if self.curr_class == ClassKind::None {
+                    self.error(id.span, "this", "Can't use 'this' outside of a class.");
+                }
+                self.resolve_ident(id);
+            }
+            ExprAst::SuperAst(s) => {
+                match self.curr_class {
+                    ClassKind::None => {
+                        self.error(s.span, "super", "Can't use 'super' outside of a class.")
+                    }
+                    ClassKind::Class => self.error(
+                        s.span,
+                        "super",
+                        "Can't use 'super' in a class with no superclass.",
+                    ),
+                    ClassKind::Subclass => (),
+                }
+                s.slot = self.resolve_local("super");
+            }
+        }
+    }
+}
+
+impl Default for Resolver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,109 @@
+use std::rc::Rc;
+
+use super::value::{Function, Value};
+use crate::lexer::Span;
+
+/// One VM instruction. Operands are indexes into the chunk pools, slots as
+/// computed by the resolver, or jump offsets counted in instructions.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OpCode {
+    Constant(u16),
+    Nil,
+    True,
+    False,
+    Pop,
+    DefineGlobal(u16),
+    GetGlobal(u16),
+    SetGlobal(u16),
+    DefineLocal(u16),
+    GetLocal(u16, u16),
+    SetLocal(u16, u16),
+    PushScope,
+    PopScope,
+    GetProperty(u16),
+    SetProperty(u16),
+    GetSuper(u16, u16),
+    BuildList(u16),
+    BuildMap(u16),
+    GetIndex,
+    SetIndex,
+    Equal,
+    NotEqual,
+    Greater,
+    GreaterEqual,
+    Less,
+    LessEqual,
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Not,
+    Negate,
+    Print,
+    Jump(u16),
+    JumpIfFalse(u16),
+    Loop(u16),
+    Call(u8),
+    /// Starts the body of a `try` statement: an error raised before the
+    /// matching `EndTry` unwinds back to this frame and jumps forward to the
+    /// `catch` clause, with the error value pushed.
+    Try(u16),
+    /// Same for the code a `finally` block protects, except that the error
+    /// is set aside for `EndFinally` to raise again.
+    TryFinally(u16),
+    /// Ends the innermost `Try` or `TryFinally` region.
+    EndTry,
+    /// Enters a `finally` block with no error set aside.
+    EnterFinally,
+    /// Ends a `finally` block, raising the error set aside if there is one.
+    EndFinally,
+    /// Jumps out of a `finally` block, dropping the error set aside.
+    LeaveFinally,
+    /// Raises the value on top of the stack.
+    Throw,
+    Closure(u16),
+    Class(u16),
+    /// Pushes the module whose path is the constant, running it first if it
+    /// was never imported.
+    Import(u16),
+    /// Replaces the module on top of the stack by nil, after copying what it
+    /// defined into the current globals.
+    ImportAll,
+    Return,
+}
+
+/// What the `Class` instruction needs to build a class at runtime.
+#[derive(Debug)]
+pub struct ClassProto {
+    pub name: String,
+    pub inherit: bool,
+    pub methods: Vec<Rc<Function>>,
+}
+
+/// A compiled sequence of instructions with its constant pool. Source spans
+/// are stored run-length encoded as `(first instruction, span)` pairs.
+#[derive(Debug, Default)]
+pub struct Chunk {
+    pub code: Vec<OpCode>,
+    pub constants: Vec<Value>,
+    pub functions: Vec<Rc<Function>>,
+    pub classes: Vec<ClassProto>,
+    spans: Vec<(usize, Span)>,
+}
+
+impl Chunk {
+    pub fn write(&mut self, op: OpCode, span: Span) -> usize {
+        if self.spans.last().map(|&(_, p)| p) != Some(span) {
+            self.spans.push((self.code.len(), span));
+        }
+        self.code.push(op);
+        self.code.len() - 1
+    }
+
+    /// Source span of the instruction at `offset`.
+    pub fn span_at(&self, offset: usize) -> Span {
+        let i = self.spans.partition_point(|&(start, _)| start <= offset);
+        i.checked_sub(1)
+            .map_or(Span::default(), |i| self.spans[i].1)
+    }
+}
@@ -0,0 +1,709 @@
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use super::chunk::{Chunk, ClassProto, OpCode};
+use super::value::{Function, Value};
+use crate::error::RuntimeError;
+use crate::lexer::{Span, Token};
+use crate::parser::parser_ds::*;
+
+/// The loop being compiled, with the jumps of its `break` and `continue`
+/// statements waiting to be patched.
+struct Loop {
+    /// Scope depth outside of the loop body.
+    scope_depth: usize,
+    breaks: Vec<usize>,
+    continues: Vec<usize>,
+}
+
+/// What leaving a region of a `try` statement takes.
+#[derive(Clone)]
+enum Exit {
+    /// Removing the handler of the `catch` clause.
+    Catch,
+    /// Removing the handler of the `finally` block, then running it.
+    Finally(ExprAst),
+    /// Dropping the error set aside while the `finally` block runs.
+    Pending,
+}
+
+/// A region of a `try` statement around the code being compiled.
+#[derive(Clone)]
+struct Try {
+    /// Scope depth outside of the statement.
+    scope_depth: usize,
+    /// Number of loops around the statement.
+    loops: usize,
+    exit: Exit,
+}
+
+/// Turns resolved statements into bytecode. Every expression leaves exactly
+/// one value on the stack, the same value the tree-walking interpreter
+/// returns for it.
+pub struct Compiler {
+    function: Function,
+    names: HashMap<String, u16>,
+    /// Span recorded for the instructions emitted next.
+    span: Span,
+    /// Scopes opened by `PushScope` in this function.
+    scope_depth: usize,
+    loops: Vec<Loop>,
+    /// `try` regions `break`, `continue` and `return` may jump out of,
+    /// innermost last.
+    tries: Vec<Try>,
+}
+
+impl Compiler {
+    fn new(name: String, arity: usize, is_initializer: bool, span: Span) -> Self {
+        Self {
+            function: Function {
+                name,
+                arity,
+                chunk: Chunk::default(),
+                is_initializer,
+            },
+            names: HashMap::new(),
+            span,
+            scope_depth: 0,
+            loops: Vec::new(),
+            tries: Vec::new(),
+        }
+    }
+
+    /// Compiles one top level statement into a function leaving its value.
+    /// `name` shows in stack traces.
+    pub fn compile_script(stmt: &ExprAst, name: &str) -> Result<Rc<Function>, RuntimeError> {
+        let mut compiler = Self::new(name.to_string(), 0, false, Span::default());
+        compiler
+            .expr(stmt)
+            .map_err(|msg| RuntimeError::new(msg, compiler.span))?;
+        compiler.emit(OpCode::Return);
+        Ok(Rc::new(compiler.function))
+    }
+
+    fn compile_function(
+        &self,
+        decl: &FnDeclExprAst,
+        is_method: bool,
+    ) -> Result<Rc<Function>, String> {
+        let is_initializer = is_method && decl.name == "init";
+        let mut compiler = Self::new(
+            decl.name.clone(),
+            decl.args.len(),
+            is_initializer,
+            self.span,
+        );
+        // The parameters and the body share the scope opened by the call.
+        match decl.body.as_ref() {
+            ExprAst::BlockAst(b) => {
+                for stmt in b.cont.iter() {
+                    compiler.expr(stmt)?;
+                    compiler.emit(OpCode::Pop);
+                }
+            }
+            body => {
+                compiler.expr(body)?;
+                compiler.emit(OpCode::Pop);
+            }
+        }
+        compiler.emit(OpCode::Nil);
+        compiler.emit(OpCode::Return);
+        Ok(Rc::new(compiler.function))
+    }
+
+    fn chunk(&mut self) -> &mut Chunk {
+        &mut self.function.chunk
+    }
+
+    fn emit(&mut self, op: OpCode) -> usize {
+        let span = self.span;
+        self.chunk().write(op, span)
+    }
+
+    fn index(i: usize, what: &str) -> Result<u16, String> {
+        u16::try_from(i).map_err(|_| format!("Too many {} in one chunk.", what))
+    }
+
+    fn constant(&mut self, value: Value) -> Result<u16, String> {
+        self.chunk().constants.push(value);
+        Self::index(self.function.chunk.constants.len() - 1, "constants")
+    }
+
+    fn name(&mut self, name: &str) -> Result<u16, String> {
+        if let Some(&i) = self.names.get(name) {
+            return Ok(i);
+        }
+        let i = self.constant(Value::Str(name.into()))?;
+        self.names.insert(name.to_string(), i);
+        Ok(i)
+    }
+
+    fn slot(slot: Slot) -> Result<(u16, u16), String> {
+        Ok((
+            Self::index(slot.depth, "nested scopes")?,
+            Self::index(slot.index, "local variables")?,
+        ))
+    }
+
+    fn patch_jump(&mut self, at: usize) -> Result<(), String> {
+        let offset = self.function.chunk.code.len() - at - 1;
+        let offset = u16::try_from(offset).map_err(|_| "Too much code to jump over.")?;
+        match &mut self.chunk().code[at] {
+            OpCode::Jump(o) | OpCode::JumpIfFalse(o) | OpCode::Try(o) | OpCode::TryFinally(o) => {
+                *o = offset
+            }
+            op => unreachable!("{:?} is not a jump", op),
+        }
+        Ok(())
+    }
+
+    fn push_scope(&mut self) {
+        self.emit(OpCode::PushScope);
+        self.scope_depth += 1;
+    }
+
+    fn pop_scope(&mut self) {
+        self.emit(OpCode::PopScope);
+        self.scope_depth -= 1;
+    }
+
+    /// Compiles the body of a loop, leaving nothing on the stack. `continue`
+    /// jumps to the end of the body.
+    fn loop_body(&mut self, body: &ExprAst) -> Result<Vec<usize>, String> {
+        self.loops.push(Loop {
+            scope_depth: self.scope_depth,
+            breaks: Vec::new(),
+            continues: Vec::new(),
+        });
+        let r = self.expr(body);
+        let lp = self.loops.pop().unwrap();
+        r?;
+        self.emit(OpCode::Pop);
+        for at in lp.continues {
+            self.patch_jump(at)?;
+        }
+        Ok(lp.breaks)
+    }
+
+    /// Closes the scopes opened inside the innermost loop, then jumps out of
+    /// it, or to its next iteration when `exit` is false.
+    fn loop_jump(&mut self, exit: bool) -> Result<(), String> {
+        let depth = self
+            .loops
+            .last()
+            .ok_or("Can't jump outside of a loop.")?
+            .scope_depth;
+        let loops = self.loops.len();
+        let first = self
+            .tries
+            .iter()
+            .position(|t| t.loops == loops)
+            .unwrap_or(self.tries.len());
+        let scope_depth = self.leave_tries(first)?;
+        for _ in depth..scope_depth {
+            self.emit(OpCode::PopScope);
+        }
+        let at = self.emit(OpCode::Jump(0));
+        let lp = self.loops.last_mut().unwrap();
+        if exit {
+            lp.breaks.push(at);
+        } else {
+            lp.continues.push(at);
+        }
+        // Unreachable, keeps every statement leaving one value.
+        self.emit(OpCode::Nil);
+        Ok(())
+    }
+
+    /// Leaves the `try` regions from the `first` one on, innermost first,
+    /// running their `finally` blocks. Returns the scope depth reached.
+    fn leave_tries(&mut self, first: usize) -> Result<usize, String> {
+        let mut depth = self.scope_depth;
+        for i in (first..self.tries.len()).rev() {
+            let t = self.tries[i].clone();
+            for _ in t.scope_depth..depth {
+                self.emit(OpCode::PopScope);
+            }
+            depth = t.scope_depth;
+            match t.exit {
+                Exit::Catch => {
+                    self.emit(OpCode::EndTry);
+                }
+                Exit::Finally(finally) => {
+                    self.emit(OpCode::EndTry);
+                    self.emit(OpCode::EnterFinally);
+                    // The block runs outside of this region and the inner ones.
+                    let inner = self.tries.split_off(i);
+                    let outer_depth = std::mem::replace(&mut self.scope_depth, depth);
+                    let r = self.finally_block(&finally, t.loops);
+                    self.scope_depth = outer_depth;
+                    self.tries.extend(inner);
+                    r?;
+                    self.emit(OpCode::LeaveFinally);
+                }
+                Exit::Pending => {
+                    self.emit(OpCode::LeaveFinally);
+                }
+            }
+        }
+        Ok(depth)
+    }
+
+    /// Compiles a `finally` block, leaving nothing on the stack.
+    fn finally_block(&mut self, finally: &ExprAst, loops: usize) -> Result<(), String> {
+        self.tries.push(Try {
+            scope_depth: self.scope_depth,
+            loops,
+            exit: Exit::Pending,
+        });
+        let r = self.expr(finally);
+        self.tries.pop();
+        r?;
+        self.emit(OpCode::Pop);
+        Ok(())
+    }
+
+    /// Compiles `body` as a region of a `try` statement, leaving nothing on
+    /// the stack.
+    fn try_region(
+        &mut self,
+        body: impl FnOnce(&mut Self) -> Result<(), String>,
+        exit: Exit,
+    ) -> Result<(), String> {
+        self.tries.push(Try {
+            scope_depth: self.scope_depth,
+            loops: self.loops.len(),
+            exit,
+        });
+        let r = body(self);
+        self.tries.pop();
+        r?;
+        self.emit(OpCode::EndTry);
+        Ok(())
+    }
+
+    fn try_stmt(&mut self, t: &TryExprAst) -> Result<(), String> {
+        match t.finally {
+            Some(ref finally) => {
+                let handler = self.emit(OpCode::TryFinally(0));
+                self.try_region(|c| c.try_catch(t), Exit::Finally(*finally.clone()))?;
+                self.emit(OpCode::EnterFinally);
+                // An error leaving the region lands here, set aside.
+                self.patch_jump(handler)?;
+                self.finally_block(finally, self.loops.len())?;
+                self.emit(OpCode::EndFinally);
+            }
+            None => self.try_catch(t)?,
+        }
+        self.emit(OpCode::Nil);
+        Ok(())
+    }
+
+    /// The `try` block and its `catch` clause, leaving nothing on the stack.
+    fn try_catch(&mut self, t: &TryExprAst) -> Result<(), String> {
+        let Some(ref catch) = t.catch else {
+            self.expr(&t.body)?;
+            self.emit(OpCode::Pop);
+            return Ok(());
+        };
+        let handler = self.emit(OpCode::Try(0));
+        self.try_region(
+            |c| {
+                c.expr(&t.body)?;
+                c.emit(OpCode::Pop);
+                Ok(())
+            },
+            Exit::Catch,
+        )?;
+        let end = self.emit(OpCode::Jump(0));
+        // The error value is on the stack. The catch body shares the scope
+        // of the error variable.
+        self.patch_jump(handler)?;
+        self.push_scope();
+        self.span = catch.name.span;
+        self.define(&catch.name.val, catch.name.slot)?;
+        self.emit(OpCode::Pop);
+        if let ExprAst::BlockAst(b) = catch.body.as_ref() {
+            for stmt in b.cont.iter() {
+                self.expr(stmt)?;
+                self.emit(OpCode::Pop);
+            }
+        }
+        self.pop_scope();
+        self.patch_jump(end)
+    }
+
+    fn emit_loop(&mut self, start: usize) -> Result<(), String> {
+        let offset = self.function.chunk.code.len() + 1 - start;
+        let offset = u16::try_from(offset).map_err(|_| "Loop body too large.")?;
+        self.emit(OpCode::Loop(offset));
+        Ok(())
+    }
+
+    fn define(&mut self, name: &str, slot: Option<Slot>) -> Result<(), String> {
+        let op = match slot {
+            Some(slot) => OpCode::DefineLocal(Self::slot(slot)?.1),
+            None => OpCode::DefineGlobal(self.name(name)?),
+        };
+        self.emit(op);
+        Ok(())
+    }
+
+    fn get(&mut self, name: &str, slot: Option<Slot>) -> Result<(), String> {
+        let op = match slot {
+            Some(slot) => {
+                let (depth, index) = Self::slot(slot)?;
+                OpCode::GetLocal(depth, index)
+            }
+            None => OpCode::GetGlobal(self.name(name)?),
+        };
+        self.emit(op);
+        Ok(())
+    }
+
+    fn set(&mut self, name: &str, slot: Option<Slot>) -> Result<(), String> {
+        let op = match slot {
+            Some(slot) => {
+                let (depth, index) = Self::slot(slot)?;
+                OpCode::SetLocal(depth, index)
+            }
+            None => OpCode::SetGlobal(self.name(name)?),
+        };
+        self.emit(op);
+        Ok(())
+    }
+
+    /// Leaves `rhs` when it is truthy and `false` otherwise, which is how the
+    /// interpreter ends both `and` and `or`.
+    fn logical_tail(&mut self, rhs: &ExprAst) -> Result<(), String> {
+        self.expr(rhs)?;
+        let falsy = self.emit(OpCode::JumpIfFalse(0));
+        let end = self.emit(OpCode::Jump(0));
+        self.patch_jump(falsy)?;
+        self.emit(OpCode::Pop);
+        self.emit(OpCode::False);
+        self.patch_jump(end)
+    }
+
+    fn binary(&mut self, b: &BinExprAst) -> Result<(), String> {
+        match b.op {
+            Token::Or => {
+                self.expr(&b.lhs)?;
+                let rhs = self.emit(OpCode::JumpIfFalse(0));
+                let end = self.emit(OpCode::Jump(0));
+                self.patch_jump(rhs)?;
+                self.emit(OpCode::Pop);
+                self.logical_tail(&b.rhs)?;
+                return self.patch_jump(end);
+            }
+            Token::And => {
+                self.expr(&b.lhs)?;
+                let end = self.emit(OpCode::JumpIfFalse(0));
+                self.emit(OpCode::Pop);
+                self.logical_tail(&b.rhs)?;
+                return self.patch_jump(end);
+            }
+            _ => (),
+        }
+        self.expr(&b.lhs)?;
+        self.expr(&b.rhs)?;
+        self.span = b.span;
+        let op = match b.op {
+            Token::Plus => OpCode::Add,
+            Token::Minus => OpCode::Subtract,
+            Token::Star => OpCode::Multiply,
+            Token::Slash => OpCode::Divide,
+            Token::DoubleEqual => OpCode::Equal,
+            Token::BangEqual => OpCode::NotEqual,
+            Token::Greater => OpCode::Greater,
+            Token::GreaterEqual => OpCode::GreaterEqual,
+            Token::Less => OpCode::Less,
+            Token::LessEqual => OpCode::LessEqual,
+            ref op => {
+                return Err(format!(
+                    "Unknown binary operator '{}'.",
+                    op.to_usefull_str()
+                ))
+            }
+        };
+        self.span = b.span;
+        self.emit(op);
+        Ok(())
+    }
+
+    fn block(&mut self, b: &BlockExprAst) -> Result<(), String> {
+        self.push_scope();
+        for stmt in b.cont.iter() {
+            self.expr(stmt)?;
+            self.emit(OpCode::Pop);
+        }
+        self.pop_scope();
+        self.emit(OpCode::Nil);
+        Ok(())
+    }
+
+    fn if_expr(&mut self, i: &IfExprAst) -> Result<(), String> {
+        self.expr(&i.cond)?;
+        let default = self.emit(OpCode::JumpIfFalse(0));
+        self.emit(OpCode::Pop);
+        self.expr(&i.then)?;
+        let end = self.emit(OpCode::Jump(0));
+        self.patch_jump(default)?;
+        self.emit(OpCode::Pop);
+        self.expr(&i.default)?;
+        self.patch_jump(end)
+    }
+
+    fn while_expr(&mut self, w: &WhileExprAst) -> Result<(), String> {
+        let start = self.function.chunk.code.len();
+        self.expr(&w.cond)?;
+        let exit = self.emit(OpCode::JumpIfFalse(0));
+        self.emit(OpCode::Pop);
+        let breaks = self.loop_body(&w.then)?;
+        self.emit_loop(start)?;
+        self.patch_jump(exit)?;
+        self.emit(OpCode::Pop);
+        for at in breaks {
+            self.patch_jump(at)?;
+        }
+        self.emit(OpCode::Nil);
+        Ok(())
+    }
+
+    fn for_expr(&mut self, f: &ForExprAst) -> Result<(), String> {
+        // The initializer lives in its own scope, wrapping the whole loop.
+        self.push_scope();
+        self.expr(&f.lhs)?;
+        self.emit(OpCode::Pop);
+        let start = self.function.chunk.code.len();
+        self.expr(&f.cond)?;
+        let exit = self.emit(OpCode::JumpIfFalse(0));
+        self.emit(OpCode::Pop);
+        let breaks = self.loop_body(&f.then)?;
+        self.expr(&f.rhs)?;
+        self.emit(OpCode::Pop);
+        self.emit_loop(start)?;
+        self.patch_jump(exit)?;
+        self.emit(OpCode::Pop);
+        for at in breaks {
+            self.patch_jump(at)?;
+        }
+        self.pop_scope();
+        self.emit(OpCode::Nil);
+        Ok(())
+    }
+
+    fn call(&mut self, f: &FnCallExprAst) -> Result<(), String> {
+        self.expr(&f.lhs)?;
+        for arg in f.args.iter() {
+            self.expr(arg)?;
+        }
+        self.span = f.span;
+        let argc = u8::try_from(f.args.len()).map_err(|_| "Can't have more than 255 arguments.")?;
+        self.emit(OpCode::Call(argc));
+        Ok(())
+    }
+
+    /// Leaves the closure of `f` over the current scope.
+    fn closure(&mut self, f: &FnDeclExprAst) -> Result<(), String> {
+        let function = self.compile_function(f, false)?;
+        self.chunk().functions.push(function);
+        let i = Self::index(self.function.chunk.functions.len() - 1, "functions")?;
+        self.span = f.span;
+        self.emit(OpCode::Closure(i));
+        Ok(())
+    }
+
+    fn fn_decl(&mut self, f: &FnDeclExprAst) -> Result<(), String> {
+        self.closure(f)?;
+        self.define(&f.name, f.slot)?;
+        self.emit(OpCode::Pop);
+        self.emit(OpCode::Nil);
+        Ok(())
+    }
+
+    fn class_decl(&mut self, c: &ClassDeclExprAst) -> Result<(), String> {
+        if let Some(ref superclass) = c.superclass {
+            self.expr(superclass)?;
+        }
+        let methods = c
+            .methods
+            .iter()
+            .map(|m| self.compile_function(m, true))
+            .collect::<Result<_, _>>()?;
+        self.chunk().classes.push(ClassProto {
+            name: c.name.clone(),
+            inherit: c.superclass.is_some(),
+            methods,
+        });
+        let i = Self::index(self.function.chunk.classes.len() - 1, "classes")?;
+        self.span = match c.superclass.as_deref() {
+            Some(ExprAst::IdenAst(superclass)) => superclass.span,
+            _ => c.span,
+        };
+        self.emit(OpCode::Class(i));
+        self.define(&c.name, c.slot)?;
+        self.emit(OpCode::Pop);
+        self.emit(OpCode::Nil);
+        Ok(())
+    }
+
+    fn expr(&mut self, expr: &ExprAst) -> Result<(), String> {
+        // Instructions that don't set a more precise one get the span of
+        // their expression, so that step and time limits report a line.
+        self.span = expr.span();
+        match expr {
+            ExprAst::BoolAst(b) => {
+                self.emit(if b.val { OpCode::True } else { OpCode::False });
+            }
+            ExprAst::NumAst(n) => {
+                let i = self.constant(Value::Num(n.number))?;
+                self.emit(OpCode::Constant(i));
+            }
+            ExprAst::StrAst(s) => {
+                let i = self.constant(Value::Str(s.val.as_str().into()))?;
+                self.emit(OpCode::Constant(i));
+            }
+            ExprAst::OpAst(o) => {
+                let i = self.constant(Value::Str(o.val.to_usefull_str().into()))?;
+                self.emit(OpCode::Constant(i));
+            }
+            ExprAst::NilAst(_) => {
+                self.emit(OpCode::Nil);
+            }
+            ExprAst::ParAst(p) => self.expr(&p.val)?,
+            ExprAst::NegativeAst(n) => {
+                self.expr(&n.rhs)?;
+                self.span = n.span;
+                self.emit(OpCode::Negate);
+            }
+            ExprAst::UnaryAst(u) => match u.op {
+                Token::Bang => {
+                    self.expr(&u.rhs)?;
+                    self.emit(OpCode::Not);
+                }
+                ref op => {
+                    self.span = u.span;
+                    return Err(format!("Unknown unary operator '{}'.", op.to_usefull_str()));
+                }
+            },
+            ExprAst::BinaryAst(b) => self.binary(b)?,
+            ExprAst::IdenAst(id) => {
+                self.span = id.span;
+                self.get(&id.val, id.slot)?
+            }
+            ExprAst::PrintAst(p) => {
+                self.expr(&p.expr)?;
+                self.emit(OpCode::Print);
+            }
+            ExprAst::IfAst(i) => self.if_expr(i)?,
+            ExprAst::WhileAst(w) => self.while_expr(w)?,
+            ExprAst::ForAst(f) => self.for_expr(f)?,
+            ExprAst::AssignAst(a) | ExprAst::VarAssignAst(a) => {
+                let id = &a.lhs;
+                self.expr(&a.rhs)?;
+                self.span = id.span;
+                if matches!(expr, ExprAst::VarAssignAst(_)) {
+                    self.define(&id.val, id.slot)?;
+                } else {
+                    self.set(&id.val, id.slot)?;
+                }
+            }
+            ExprAst::BlockAst(b) => self.block(b)?,
+            ExprAst::FnCallAst(f) => self.call(f)?,
+            ExprAst::FnDeclAst(f) => self.fn_decl(f)?,
+            ExprAst::LambdaAst(f) => self.closure(f)?,
+            ExprAst::BreakAst(span) => {
+                self.span = *span;
+                self.loop_jump(true)?
+            }
+            ExprAst::ContinueAst(span) => {
+                self.span = *span;
+                self.loop_jump(false)?
+            }
+            ExprAst::RetAst(r) => {
+                self.expr(&r.val)?;
+                self.leave_tries(0)?;
+                self.emit(OpCode::Return);
+            }
+            ExprAst::ClassDeclAst(c) => self.class_decl(c)?,
+            ExprAst::ImportAst(i) => {
+                self.span = i.span;
+                let path = self.constant(Value::Str(i.path.as_str().into()))?;
+                self.emit(OpCode::Import(path));
+                match &i.name {
+                    Some(name) => {
+                        let name = self.name(name)?;
+                        self.emit(OpCode::DefineGlobal(name));
+                        self.emit(OpCode::Pop);
+                        self.emit(OpCode::Nil);
+                    }
+                    None => {
+                        self.emit(OpCode::ImportAll);
+                    }
+                }
+            }
+            ExprAst::ThrowAst(t) => {
+                self.expr(&t.val)?;
+                self.span = t.span;
+                self.emit(OpCode::Throw);
+            }
+            ExprAst::TryAst(t) => self.try_stmt(t)?,
+            ExprAst::GetAst(g) => {
+                self.expr(&g.object)?;
+                self.span = g.span;
+                let name = self.name(&g.name)?;
+                self.emit(OpCode::GetProperty(name));
+            }
+            ExprAst::SetAst(s) => {
+                self.expr(&s.object)?;
+                self.expr(&s.val)?;
+                self.span = s.span;
+                let name = self.name(&s.name)?;
+                self.emit(OpCode::SetProperty(name));
+            }
+            ExprAst::ListAst(l) => {
+                for item in &l.items {
+                    self.expr(item)?;
+                }
+                self.span = l.span;
+                let n = Self::index(l.items.len(), "list elements")?;
+                self.emit(OpCode::BuildList(n));
+            }
+            ExprAst::MapAst(m) => {
+                for (key, val) in &m.entries {
+                    self.expr(key)?;
+                    self.expr(val)?;
+                }
+                self.span = m.span;
+                let n = Self::index(m.entries.len(), "map entries")?;
+                self.emit(OpCode::BuildMap(n));
+            }
+            ExprAst::IndexAst(i) => {
+                self.expr(&i.object)?;
+                self.expr(&i.index)?;
+                self.span = i.span;
+                self.emit(OpCode::GetIndex);
+            }
+            ExprAst::SetIndexAst(s) => {
+                self.expr(&s.object)?;
+                self.expr(&s.index)?;
+                self.expr(&s.val)?;
+                self.span = s.span;
+                self.emit(OpCode::SetIndex);
+            }
+            ExprAst::ThisAst(id) => {
+                self.span = id.span;
+                self.get("this", id.slot)?
+            }
+            ExprAst::SuperAst(s) => {
+                self.span = s.span;
+                let slot = s.slot.ok_or("Can't use 'super' outside of a class.")?;
+                let (depth, _) = Self::slot(slot)?;
+                let name = self.name(&s.method)?;
+                self.emit(OpCode::GetSuper(depth, name));
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,751 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::path::Path;
+use std::rc::Rc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::chunk::OpCode;
+use super::compiler::Compiler;
+use super::list::list_natives;
+use super::map::{self, map_natives};
+use super::value::*;
+use crate::backend::{list_index, Arity, Backend};
+use crate::error::{CaughtError, RuntimeError};
+use crate::lexer::Span;
+use crate::limits::{Budget, Limit, Limits};
+use crate::map::{MapKey, OrderedMap};
+use crate::module::{Import, Loader};
+use crate::parser::parser_ds::ExprAst;
+
+fn clock(_: &[Value]) -> Result<Value, String> {
+    let now = SystemTime::now();
+    let seconds = now.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+    Ok(Value::Num(seconds as f64))
+}
+
+/// What `object[index]` designates.
+enum Element {
+    List(List, usize),
+    Map(Map, MapKey),
+}
+
+fn element(object: Value, index: Value) -> Result<Element, String> {
+    match (object, index) {
+        (Value::List(list), Value::Num(index)) => {
+            let i = list_index(index, list.borrow().len())?;
+            Ok(Element::List(list, i))
+        }
+        (Value::List(_), _) => Err("List index must be a number.".to_string()),
+        (Value::Map(map), index) => Ok(Element::Map(map, map::key(&index)?)),
+        _ => Err("Only lists and maps can be indexed.".to_string()),
+    }
+}
+
+struct CallFrame {
+    closure: Rc<Closure>,
+    ip: usize,
+    /// Stack height when the frame was entered, restored on return.
+    base: usize,
+    env: Option<Env>,
+}
+
+/// Where an error raised inside a `try` region resumes.
+struct Handler {
+    /// Frame count when the region was entered, its frame is the last one.
+    frames: usize,
+    stack: usize,
+    env: Option<Env>,
+    ip: usize,
+    /// Whether it leads to a `catch` clause rather than a `finally` block.
+    catches: bool,
+    /// Number of errors set aside by the `finally` blocks running then.
+    pending: usize,
+}
+
+/// An error set aside while a `finally` block runs.
+struct Pending {
+    err: RuntimeError,
+    thrown: Option<Value>,
+}
+
+/// A stack machine running the bytecode produced by the `Compiler`.
+/// Locals live in heap scopes shared with closures, addressed by the slots
+/// the resolver computed; globals are looked up by name, in the namespace of
+/// the module the running closure comes from.
+pub struct Vm {
+    stack: Vec<Value>,
+    frames: Vec<CallFrame>,
+    globals: Globals,
+    modules: Loader<Rc<Module>>,
+    handlers: Vec<Handler>,
+    /// One entry per `finally` block running, with the error to raise again
+    /// at its end.
+    pending: Vec<Option<Pending>>,
+    /// The value of the `throw` whose error is propagating.
+    thrown: Option<Value>,
+    /// The error raised again at the end of a `finally` block, reported as
+    /// it was first raised.
+    raised: Option<RuntimeError>,
+    budget: Budget,
+    /// The limit the program ran into, its error skips every handler.
+    tripped: Option<Limit>,
+}
+
+/// A global namespace holding only the native functions.
+fn builtin_globals() -> Globals {
+    let mut globals = HashMap::new();
+    let clock = Native {
+        name: "clock".to_string(),
+        arity: Arity::Fixed(0),
+        fun: Rc::new(clock),
+    };
+    for native in std::iter::once(clock)
+        .chain(list_natives())
+        .chain(map_natives())
+    {
+        globals.insert(native.name.as_str().into(), Value::Native(Rc::new(native)));
+    }
+    Rc::new(RefCell::new(globals))
+}
+
+impl Vm {
+    pub fn new() -> Self {
+        Self {
+            stack: Vec::new(),
+            frames: Vec::new(),
+            globals: builtin_globals(),
+            modules: Loader::new(),
+            handlers: Vec::new(),
+            pending: Vec::new(),
+            thrown: None,
+            raised: None,
+            budget: Budget::default(),
+            tripped: None,
+        }
+    }
+
+    /// Makes `native` callable as a global function.
+    pub fn define_native(&mut self, native: Native) {
+        let name = native.name.as_str().into();
+        let native = Value::Native(Rc::new(native));
+        self.globals.borrow_mut().insert(name, native);
+    }
+
+    pub fn interpret(&mut self, stmt: &ExprAst) -> Result<Value, RuntimeError> {
+        let function = Compiler::compile_script(stmt, "script")?;
+        self.stack.clear();
+        self.frames.clear();
+        self.handlers.clear();
+        self.pending.clear();
+        self.thrown = None;
+        self.raised = None;
+        self.tripped = None;
+        self.run_script(function, self.globals.clone())
+            .map_err(|msg| {
+                let mut err = self
+                    .raised
+                    .take()
+                    .unwrap_or_else(|| self.runtime_error(msg));
+                err.limit = self.tripped.take();
+                err
+            })
+    }
+
+    /// Records that the program ran into `limit`, returns the error message.
+    fn trip(&mut self, limit: Limit) -> String {
+        self.tripped = Some(limit);
+        limit.message().to_string()
+    }
+
+    /// Counts a value of `size` units created against the allocation limit.
+    fn allocate(&mut self, size: usize) -> Result<(), String> {
+        self.budget.allocate(size).map_err(|limit| self.trip(limit))
+    }
+
+    /// Runs a compiled top level statement with `globals`, on top of the
+    /// frames already there.
+    fn run_script(&mut self, function: Rc<Function>, globals: Globals) -> Result<Value, String> {
+        let depth = self.frames.len();
+        let script = Rc::new(Closure {
+            function,
+            env: None,
+            globals,
+        });
+        self.frames.push(CallFrame {
+            closure: script,
+            ip: 0,
+            base: self.stack.len(),
+            env: None,
+        });
+        self.run(depth)
+    }
+
+    /// The module at `path`, run the first time it is imported.
+    fn import(&mut self, path: &str) -> Result<Rc<Module>, String> {
+        let stmts = match self.modules.start(path)? {
+            Import::Loaded(module) => return Ok(module),
+            Import::Run(stmts) => stmts,
+        };
+        let module = Rc::new(Module {
+            name: path.to_string(),
+            globals: builtin_globals(),
+        });
+        let name = format!("module {}", path);
+        let r = stmts.iter().try_for_each(|stmt| {
+            let function = Compiler::compile_script(stmt, &name).map_err(|err| err.message)?;
+            self.run_script(function, module.globals.clone())
+                .map(|_| ())
+        });
+        match r {
+            Ok(()) => {
+                self.modules.finish(module.clone());
+                Ok(module)
+            }
+            Err(msg) => {
+                self.modules.abort();
+                Err(msg)
+            }
+        }
+    }
+
+    fn current_globals(&self) -> Globals {
+        self.frames.last().unwrap().closure.globals.clone()
+    }
+
+    /// Span of the instruction `frame` is executing.
+    fn span(frame: &CallFrame) -> Span {
+        frame.closure.function.chunk.span_at(frame.ip - 1)
+    }
+
+    /// Attaches the position of the failing instruction and the trace of the
+    /// frames still on the call stack to `msg`.
+    fn runtime_error(&self, msg: String) -> RuntimeError {
+        let (innermost, callers) = self.frames.split_last().unwrap();
+        let mut err = RuntimeError::new(msg, Self::span(innermost));
+        let mut callee = innermost;
+        for caller in callers.iter().rev() {
+            err = err.unwind(&callee.closure.function.name, Self::span(caller));
+            callee = caller;
+        }
+        err.in_script()
+    }
+
+    fn frame(&mut self) -> &mut CallFrame {
+        self.frames.last_mut().unwrap()
+    }
+
+    fn pop(&mut self) -> Value {
+        self.stack.pop().unwrap()
+    }
+
+    fn peek(&self) -> &Value {
+        self.stack.last().unwrap()
+    }
+
+    fn name(&mut self, i: u16) -> Rc<str> {
+        match &self.frame().closure.function.chunk.constants[i as usize] {
+            Value::Str(s) => s.clone(),
+            v => unreachable!("{:?} is not a name", v),
+        }
+    }
+
+    fn ancestor(&mut self, depth: u16) -> Option<Env> {
+        let mut scope = self.frame().env.clone()?;
+        for _ in 0..depth {
+            let enclosing = scope.borrow().enclosing.clone()?;
+            scope = enclosing;
+        }
+        Some(scope)
+    }
+
+    fn numbers(&mut self) -> Result<(f64, f64), String> {
+        let rhs = self.pop();
+        let lhs = self.pop();
+        match (lhs, rhs) {
+            (Value::Num(l), Value::Num(r)) => Ok((l, r)),
+            _ => Err("Operands must be numbers.".to_string()),
+        }
+    }
+
+    fn call_value(&mut self, argc: usize) -> Result<(), String> {
+        let callee = self.stack[self.stack.len() - argc - 1].clone();
+        match callee {
+            Value::Closure(closure) => self.call(closure, argc),
+            Value::Native(native) => {
+                native.arity.check(argc)?;
+                let args = self.stack.split_off(self.stack.len() - argc);
+                self.pop();
+                let v = (native.fun)(&args)?;
+                self.stack.push(v);
+                Ok(())
+            }
+            Value::Class(class) => {
+                self.allocate(1)?;
+                let instance = Value::Instance(Rc::new(RefCell::new(Instance {
+                    class: class.clone(),
+                    fields: HashMap::new(),
+                })));
+                let len = self.stack.len();
+                self.stack[len - argc - 1] = instance.clone();
+                match class.find_method("init") {
+                    Some((init, owner)) => self.call(owner.bind(init, instance), argc),
+                    None => Arity::Fixed(0).check(argc),
+                }
+            }
+            _ => Err("Can only call functions and classes.".to_string()),
+        }
+    }
+
+    fn call(&mut self, closure: Rc<Closure>, argc: usize) -> Result<(), String> {
+        Arity::Fixed(closure.function.arity).check(argc)?;
+        // The first frame runs the top-level statement.
+        if let Err(limit) = self.budget.call(self.frames.len() - 1) {
+            return Err(self.trip(limit));
+        }
+        let args = self.stack.split_off(self.stack.len() - argc);
+        self.pop();
+        let env = Scope::new(args, closure.env.clone());
+        self.frames.push(CallFrame {
+            closure,
+            ip: 0,
+            base: self.stack.len(),
+            env: Some(env),
+        });
+        Ok(())
+    }
+
+    fn bind_method(&self, class: &Rc<Class>, name: &str, this: Value) -> Option<Value> {
+        class
+            .find_method(name)
+            .map(|(method, owner)| Value::Closure(owner.bind(method, this)))
+    }
+
+    /// Runs until the frame count drops back to `depth`.
+    fn run(&mut self, depth: usize) -> Result<Value, String> {
+        loop {
+            match self.step(depth) {
+                Ok(Some(result)) => return Ok(result),
+                Ok(None) => (),
+                Err(msg) => self.catch(msg, depth)?,
+            }
+        }
+    }
+
+    /// Resumes at the innermost handler in the frames this `run` owns, or
+    /// hands the error back when there is none.
+    fn catch(&mut self, msg: String, depth: usize) -> Result<(), String> {
+        if self.tripped.is_some() {
+            return Err(msg);
+        }
+        let handler = match self.handlers.pop() {
+            Some(handler) if handler.frames > depth => handler,
+            other => {
+                self.handlers.extend(other);
+                return Err(msg);
+            }
+        };
+        let err = self
+            .raised
+            .take()
+            .unwrap_or_else(|| self.runtime_error(msg));
+        let thrown = self.thrown.take();
+        self.frames.truncate(handler.frames);
+        self.stack.truncate(handler.stack);
+        self.pending.truncate(handler.pending);
+        let frame = self.frame();
+        frame.env = handler.env;
+        frame.ip = handler.ip;
+        if handler.catches {
+            let v = thrown.unwrap_or_else(|| Value::Error(Rc::new(CaughtError::new(&err))));
+            self.stack.push(v);
+        } else {
+            self.pending.push(Some(Pending { err, thrown }));
+        }
+        Ok(())
+    }
+
+    fn push_handler(&mut self, offset: u16, catches: bool) {
+        let frame = self.frames.last().unwrap();
+        let handler = Handler {
+            frames: self.frames.len(),
+            stack: self.stack.len(),
+            env: frame.env.clone(),
+            ip: frame.ip + offset as usize,
+            catches,
+            pending: self.pending.len(),
+        };
+        self.handlers.push(handler);
+    }
+
+    /// Executes one instruction. Returns the result once the frame count
+    /// drops back to `depth`.
+    fn step(&mut self, depth: usize) -> Result<Option<Value>, String> {
+        let frame = self.frames.last_mut().unwrap();
+        let op = frame.closure.function.chunk.code[frame.ip];
+        frame.ip += 1;
+        if let Err(limit) = self.budget.step() {
+            return Err(self.trip(limit));
+        }
+        match op {
+            OpCode::Constant(i) => {
+                let v = self.frame().closure.function.chunk.constants[i as usize].clone();
+                self.stack.push(v);
+            }
+            OpCode::Nil => self.stack.push(Value::Nil),
+            OpCode::True => self.stack.push(Value::Bool(true)),
+            OpCode::False => self.stack.push(Value::Bool(false)),
+            OpCode::Pop => {
+                self.pop();
+            }
+            OpCode::DefineGlobal(i) => {
+                let name = self.name(i);
+                let v = self.peek().clone();
+                self.current_globals().borrow_mut().insert(name, v);
+            }
+            OpCode::GetGlobal(i) => {
+                let name = self.name(i);
+                let v = self.current_globals().borrow().get(&name).cloned();
+                match v {
+                    Some(v) => self.stack.push(v),
+                    None => return Err(format!("Undefined variable '{}'.", name)),
+                }
+            }
+            OpCode::SetGlobal(i) => {
+                let name = self.name(i);
+                let v = self.peek().clone();
+                match self.current_globals().borrow_mut().get_mut(&name) {
+                    Some(var) => *var = v,
+                    None => return Err(format!("Undefined variable '{}'.", name)),
+                }
+            }
+            OpCode::DefineLocal(index) => {
+                let v = self.peek().clone();
+                let env = self.frame().env.clone().unwrap();
+                let slots = &mut env.borrow_mut().slots;
+                let index = index as usize;
+                if index >= slots.len() {
+                    slots.resize(index + 1, Value::Nil);
+                }
+                slots[index] = v;
+            }
+            OpCode::GetLocal(depth, index) => {
+                let v = self
+                    .ancestor(depth)
+                    .and_then(|s| s.borrow().slots.get(index as usize).cloned());
+                match v {
+                    Some(v) => self.stack.push(v),
+                    None => return Err("Undefined variable.".to_string()),
+                }
+            }
+            OpCode::SetLocal(depth, index) => {
+                let v = self.peek().clone();
+                let scope = self.ancestor(depth);
+                match scope.as_ref().and_then(|s| {
+                    s.borrow_mut()
+                        .slots
+                        .get_mut(index as usize)
+                        .map(|var| *var = v)
+                }) {
+                    Some(()) => (),
+                    None => return Err("Undefined variable.".to_string()),
+                }
+            }
+            OpCode::PushScope => {
+                let frame = self.frame();
+                frame.env = Some(Scope::new(Vec::new(), frame.env.take()));
+            }
+            OpCode::PopScope => {
+                let frame = self.frame();
+                let enclosing = frame.env.as_ref().unwrap().borrow().enclosing.clone();
+                frame.env = enclosing;
+            }
+            OpCode::GetProperty(i) => {
+                let name = self.name(i);
+                let instance = match self.pop() {
+                    Value::Instance(instance) => instance,
+                    Value::Module(module) => {
+                        let v = module.globals.borrow().get(&name).cloned();
+                        self.stack
+                            .push(v.ok_or(format!("Undefined property '{}'.", name))?);
+                        return Ok(None);
+                    }
+                    Value::Error(e) => {
+                        let v = match &*name {
+                            "message" => Value::Str(e.message.as_str().into()),
+                            "line" => Value::Num(e.line as f64),
+                            _ => return Err(format!("Undefined property '{}'.", name)),
+                        };
+                        self.stack.push(v);
+                        return Ok(None);
+                    }
+                    _ => return Err("Only instances have properties.".to_string()),
+                };
+                let field = instance.borrow().fields.get(&*name).cloned();
+                let class = instance.borrow().class.clone();
+                let v = field
+                    .or_else(|| self.bind_method(&class, &name, Value::Instance(instance)))
+                    .ok_or(format!("Undefined property '{}'.", name))?;
+                self.stack.push(v);
+            }
+            OpCode::SetProperty(i) => {
+                let name = self.name(i);
+                let v = self.pop();
+                let Value::Instance(instance) = self.pop() else {
+                    return Err("Only instances have fields.".to_string());
+                };
+                instance
+                    .borrow_mut()
+                    .fields
+                    .insert(name.to_string(), v.clone());
+                self.stack.push(v);
+            }
+            OpCode::BuildList(n) => {
+                let items = self.stack.split_off(self.stack.len() - n as usize);
+                self.allocate(items.len())?;
+                self.stack.push(Value::List(Rc::new(RefCell::new(items))));
+            }
+            OpCode::BuildMap(n) => {
+                let values = self.stack.split_off(self.stack.len() - 2 * n as usize);
+                let mut entries = OrderedMap::new();
+                for pair in values.chunks(2) {
+                    entries.insert(map::key(&pair[0])?, pair[1].clone());
+                }
+                self.allocate(entries.len())?;
+                self.stack.push(Value::Map(Rc::new(RefCell::new(entries))));
+            }
+            OpCode::GetIndex => {
+                let index = self.pop();
+                let v = match element(self.pop(), index)? {
+                    Element::List(list, i) => list.borrow()[i].clone(),
+                    Element::Map(map, key) => map
+                        .borrow()
+                        .get(&key)
+                        .cloned()
+                        .ok_or(format!("Undefined key '{}'.", key))?,
+                };
+                self.stack.push(v);
+            }
+            OpCode::SetIndex => {
+                let v = self.pop();
+                let index = self.pop();
+                match element(self.pop(), index)? {
+                    Element::List(list, i) => list.borrow_mut()[i] = v.clone(),
+                    Element::Map(map, key) => map.borrow_mut().insert(key, v.clone()),
+                }
+                self.stack.push(v);
+            }
+            OpCode::GetSuper(depth, i) => {
+                let name = self.name(i);
+                // `this` is bound in the scope right inside the one holding `super`.
+                let superclass = self.ancestor(depth).unwrap().borrow().slots[0].clone();
+                let this = self.ancestor(depth - 1).unwrap().borrow().slots[0].clone();
+                let Value::Class(superclass) = superclass else {
+                    unreachable!("'super' is always bound to a class")
+                };
+                let v = self
+                    .bind_method(&superclass, &name, this)
+                    .ok_or(format!("Undefined property '{}'.", name))?;
+                self.stack.push(v);
+            }
+            OpCode::Equal => {
+                let rhs = self.pop();
+                let lhs = self.pop();
+                self.stack.push(Value::Bool(lhs == rhs));
+            }
+            OpCode::NotEqual => {
+                let rhs = self.pop();
+                let lhs = self.pop();
+                self.stack.push(Value::Bool(lhs != rhs));
+            }
+            OpCode::Greater => {
+                let (l, r) = self.numbers()?;
+                self.stack.push(Value::Bool(l > r));
+            }
+            OpCode::GreaterEqual => {
+                let (l, r) = self.numbers()?;
+                self.stack.push(Value::Bool(l >= r));
+            }
+            OpCode::Less => {
+                let (l, r) = self.numbers()?;
+                self.stack.push(Value::Bool(l < r));
+            }
+            OpCode::LessEqual => {
+                let (l, r) = self.numbers()?;
+                self.stack.push(Value::Bool(l <= r));
+            }
+            OpCode::Add => {
+                let rhs = self.pop();
+                let lhs = self.pop();
+                let v = match (lhs, rhs) {
+                    (Value::Num(l), Value::Num(r)) => Value::Num(l + r),
+                    (Value::Str(l), Value::Str(r)) => {
+                        self.allocate(l.len() + r.len())?;
+                        Value::Str(format!("{}{}", l, r).into())
+                    }
+                    _ => return Err("Operands must be two numbers or two strings.".to_string()),
+                };
+                self.stack.push(v);
+            }
+            OpCode::Subtract => {
+                let (l, r) = self.numbers()?;
+                self.stack.push(Value::Num(l - r));
+            }
+            OpCode::Multiply => {
+                let (l, r) = self.numbers()?;
+                self.stack.push(Value::Num(l * r));
+            }
+            OpCode::Divide => {
+                let (l, r) = self.numbers()?;
+                self.stack.push(Value::Num(l / r));
+            }
+            OpCode::Not => {
+                let v = self.pop();
+                self.stack.push(Value::Bool(!v.is_truthy()));
+            }
+            OpCode::Negate => match self.pop() {
+                Value::Num(n) => self.stack.push(Value::Num(-n)),
+                _ => return Err("Operands must be numbers.".to_string()),
+            },
+            OpCode::Print => println!("{}", self.peek()),
+            OpCode::Jump(offset) => self.frame().ip += offset as usize,
+            OpCode::JumpIfFalse(offset) => {
+                if !self.peek().is_truthy() {
+                    self.frame().ip += offset as usize;
+                }
+            }
+            OpCode::Loop(offset) => self.frame().ip -= offset as usize,
+            OpCode::Call(argc) => self.call_value(argc as usize)?,
+            OpCode::Try(offset) => self.push_handler(offset, true),
+            OpCode::TryFinally(offset) => self.push_handler(offset, false),
+            OpCode::EndTry => {
+                self.handlers.pop();
+            }
+            OpCode::EnterFinally => self.pending.push(None),
+            OpCode::EndFinally => {
+                if let Some(Pending { err, thrown }) = self.pending.pop().unwrap() {
+                    let msg = err.message.clone();
+                    self.thrown = thrown;
+                    self.raised = Some(err);
+                    return Err(msg);
+                }
+            }
+            OpCode::LeaveFinally => {
+                self.pending.pop();
+            }
+            OpCode::Throw => {
+                let v = self.pop();
+                let msg = v.to_string();
+                self.thrown = Some(v);
+                return Err(msg);
+            }
+            OpCode::Closure(i) => {
+                self.allocate(1)?;
+                let frame = self.frame();
+                let function = frame.closure.function.chunk.functions[i as usize].clone();
+                let closure = Closure {
+                    function,
+                    env: frame.env.clone(),
+                    globals: frame.closure.globals.clone(),
+                };
+                self.stack.push(Value::Closure(Rc::new(closure)));
+            }
+            OpCode::Class(i) => {
+                let frame = self.frame();
+                let proto = &frame.closure.function.chunk.classes[i as usize];
+                let name = proto.name.clone();
+                let inherit = proto.inherit;
+                let methods = proto
+                    .methods
+                    .iter()
+                    .map(|m| (m.name.clone(), m.clone()))
+                    .collect();
+                let mut env = frame.env.clone();
+                let globals = frame.closure.globals.clone();
+                let superclass = if inherit {
+                    let Value::Class(superclass) = self.pop() else {
+                        return Err("Superclass must be a class.".to_string());
+                    };
+                    let slots = vec![Value::Class(superclass.clone())];
+                    env = Some(Scope::new(slots, env));
+                    Some(superclass)
+                } else {
+                    None
+                };
+                self.allocate(1)?;
+                let class = Class {
+                    name,
+                    methods,
+                    superclass,
+                    env,
+                    globals,
+                };
+                self.stack.push(Value::Class(Rc::new(class)));
+            }
+            OpCode::Import(i) => {
+                let path = self.name(i);
+                let module = self.import(&path)?;
+                self.stack.push(Value::Module(module));
+            }
+            OpCode::ImportAll => {
+                let Value::Module(module) = self.pop() else {
+                    unreachable!("'ImportAll' follows 'Import'")
+                };
+                // Natives are already there, everything else the module
+                // defined is copied.
+                let globals = self.current_globals();
+                for (name, v) in module.globals.borrow().iter() {
+                    if !matches!(v, Value::Native(_)) {
+                        globals.borrow_mut().insert(name.clone(), v.clone());
+                    }
+                }
+                self.stack.push(Value::Nil);
+            }
+            OpCode::Return => {
+                let mut result = self.pop();
+                let frame = self.frames.pop().unwrap();
+                if frame.closure.function.is_initializer {
+                    // Initializers always hand back the instance bound to `this`.
+                    let this = frame.closure.env.as_ref().unwrap();
+                    result = this.borrow().slots[0].clone();
+                }
+                self.stack.truncate(frame.base);
+                if self.frames.len() == depth {
+                    return Ok(Some(result));
+                }
+                self.stack.push(result);
+            }
+        }
+        Ok(None)
+    }
+}
+
+impl Default for Vm {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Backend for Vm {
+    fn execute(&mut self, stmt: &ExprAst) -> Result<String, RuntimeError> {
+        self.interpret(stmt).map(|v| v.to_string())
+    }
+
+    fn globals(&self) -> Vec<(String, String)> {
+        let mut globals: Vec<_> = self
+            .globals
+            .borrow()
+            .iter()
+            .map(|(name, val)| (name.to_string(), val.to_string()))
+            .collect();
+        globals.sort();
+        globals
+    }
+
+    fn set_script(&mut self, path: &Path) {
+        self.modules.set_script(path);
+    }
+
+    fn set_limits(&mut self, limits: Limits) {
+        self.budget = Budget::new(limits);
+    }
+}
@@ -0,0 +1,81 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use super::value::{List, Native, Value};
+use crate::backend::{list_index, Arity};
+
+fn list(v: &Value) -> Result<&List, String> {
+    match v {
+        Value::List(l) => Ok(l),
+        _ => Err("Expected a list.".to_string()),
+    }
+}
+
+fn number(v: &Value) -> Result<f64, String> {
+    match v {
+        Value::Num(n) => Ok(*n),
+        _ => Err("List index must be a number.".to_string()),
+    }
+}
+
+fn len(args: &[Value]) -> Result<Value, String> {
+    match &args[0] {
+        Value::List(l) => Ok(Value::Num(l.borrow().len() as f64)),
+        Value::Map(m) => Ok(Value::Num(m.borrow().len() as f64)),
+        Value::Str(s) => Ok(Value::Num(s.chars().count() as f64)),
+        _ => Err("Expected a list, a map or a string.".to_string()),
+    }
+}
+
+fn push(args: &[Value]) -> Result<Value, String> {
+    list(&args[0])?.borrow_mut().push(args[1].clone());
+    Ok(Value::Nil)
+}
+
+fn pop(args: &[Value]) -> Result<Value, String> {
+    let val = list(&args[0])?.borrow_mut().pop();
+    val.ok_or_else(|| "Can't pop from an empty list.".to_string())
+}
+
+fn insert(args: &[Value]) -> Result<Value, String> {
+    let mut list = list(&args[0])?.borrow_mut();
+    // Inserting right after the last element is allowed.
+    let i = list_index(number(&args[1])?, list.len() + 1)?;
+    list.insert(i, args[2].clone());
+    Ok(Value::Nil)
+}
+
+fn remove(args: &[Value]) -> Result<Value, String> {
+    let mut list = list(&args[0])?.borrow_mut();
+    let i = list_index(number(&args[1])?, list.len())?;
+    Ok(list.remove(i))
+}
+
+fn slice(args: &[Value]) -> Result<Value, String> {
+    let list = list(&args[0])?.borrow();
+    let len = list.len();
+    let start = list_index(number(&args[1])?, len + 1)?;
+    let end = list_index(number(&args[2])?, len + 1)?;
+    let items = list.get(start..end).ok_or("List index out of range.")?;
+    Ok(Value::List(Rc::new(RefCell::new(items.to_vec()))))
+}
+
+fn native(name: &str, arity: usize, fun: fn(&[Value]) -> Result<Value, String>) -> Native {
+    Native {
+        name: name.to_string(),
+        arity: Arity::Fixed(arity),
+        fun: Rc::new(fun),
+    }
+}
+
+/// The natives working on lists, same as the tree-walking interpreter's.
+pub fn list_natives() -> Vec<Native> {
+    vec![
+        native("len", 1, len),
+        native("push", 2, push),
+        native("pop", 1, pop),
+        native("insert", 3, insert),
+        native("remove", 2, remove),
+        native("slice", 3, slice),
+    ]
+}
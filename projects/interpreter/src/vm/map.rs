@@ -0,0 +1,78 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use super::value::{Map, Native, Value};
+use crate::backend::Arity;
+use crate::map::{MapKey, KEY_ERROR};
+
+pub fn key(v: &Value) -> Result<MapKey, String> {
+    match v {
+        Value::Bool(b) => Ok(MapKey::Bool(*b)),
+        Value::Num(n) => Ok(MapKey::num(*n)),
+        Value::Str(s) => Ok(MapKey::Str(s.to_string())),
+        _ => Err(KEY_ERROR.to_string()),
+    }
+}
+
+fn key_value(key: &MapKey) -> Value {
+    match key {
+        MapKey::Bool(b) => Value::Bool(*b),
+        MapKey::Num(n) => Value::Num(f64::from_bits(*n)),
+        MapKey::Str(s) => Value::Str(s.as_str().into()),
+    }
+}
+
+fn map(v: &Value) -> Result<&Map, String> {
+    match v {
+        Value::Map(m) => Ok(m),
+        _ => Err("Expected a map.".to_string()),
+    }
+}
+
+fn keys(args: &[Value]) -> Result<Value, String> {
+    let keys = map(&args[0])?
+        .borrow()
+        .iter()
+        .map(|(k, _)| key_value(k))
+        .collect();
+    Ok(Value::List(Rc::new(RefCell::new(keys))))
+}
+
+fn values(args: &[Value]) -> Result<Value, String> {
+    let values = map(&args[0])?
+        .borrow()
+        .iter()
+        .map(|(_, v)| v.clone())
+        .collect();
+    Ok(Value::List(Rc::new(RefCell::new(values))))
+}
+
+fn has(args: &[Value]) -> Result<Value, String> {
+    let key = key(&args[1])?;
+    Ok(Value::Bool(map(&args[0])?.borrow().contains_key(&key)))
+}
+
+/// Returns whether the key was there.
+fn delete(args: &[Value]) -> Result<Value, String> {
+    let key = key(&args[1])?;
+    let removed = map(&args[0])?.borrow_mut().remove(&key);
+    Ok(Value::Bool(removed.is_some()))
+}
+
+fn native(name: &str, arity: usize, fun: fn(&[Value]) -> Result<Value, String>) -> Native {
+    Native {
+        name: name.to_string(),
+        arity: Arity::Fixed(arity),
+        fun: Rc::new(fun),
+    }
+}
+
+/// The natives working on maps, same as the tree-walking interpreter's.
+pub fn map_natives() -> Vec<Native> {
+    vec![
+        native("keys", 1, keys),
+        native("values", 1, values),
+        native("has", 2, has),
+        native("delete", 2, delete),
+    ]
+}
@@ -0,0 +1,6 @@
+pub mod chunk;
+pub mod compiler;
+pub mod core;
+pub mod list;
+pub mod map;
+pub mod value;
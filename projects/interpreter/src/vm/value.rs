@@ -0,0 +1,200 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+use std::rc::Rc;
+
+use super::chunk::Chunk;
+use crate::backend::Arity;
+use crate::error::CaughtError;
+use crate::map::OrderedMap;
+
+/// A runtime value of the VM. Heap objects are reference counted and compare
+/// by identity, like in the tree-walking interpreter.
+#[derive(Clone)]
+pub enum Value {
+    Nil,
+    Bool(bool),
+    Num(f64),
+    Str(Rc<str>),
+    Closure(Rc<Closure>),
+    Native(Rc<Native>),
+    Class(Rc<Class>),
+    Instance(Rc<RefCell<Instance>>),
+    List(List),
+    Map(Map),
+    Module(Rc<Module>),
+    Error(Rc<CaughtError>),
+}
+
+/// Lists and maps are shared by reference, like instances.
+pub type List = Rc<RefCell<Vec<Value>>>;
+pub type Map = Rc<RefCell<OrderedMap<Value>>>;
+
+impl Value {
+    pub fn is_truthy(&self) -> bool {
+        match self {
+            Value::Nil => false,
+            Value::Bool(b) => *b,
+            Value::Num(n) => *n != 0.0,
+            Value::Str(s) => !s.is_empty(),
+            _ => true,
+        }
+    }
+}
+
+impl PartialEq for Value {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Value::Nil, Value::Nil) => true,
+            (Value::Bool(l), Value::Bool(r)) => l == r,
+            (Value::Num(l), Value::Num(r)) => l == r,
+            (Value::Str(l), Value::Str(r)) => l == r,
+            (Value::Closure(l), Value::Closure(r)) => Rc::ptr_eq(l, r),
+            (Value::Native(l), Value::Native(r)) => Rc::ptr_eq(l, r),
+            (Value::Class(l), Value::Class(r)) => Rc::ptr_eq(l, r),
+            (Value::Instance(l), Value::Instance(r)) => Rc::ptr_eq(l, r),
+            (Value::List(l), Value::List(r)) => Rc::ptr_eq(l, r),
+            (Value::Map(l), Value::Map(r)) => Rc::ptr_eq(l, r),
+            (Value::Module(l), Value::Module(r)) => Rc::ptr_eq(l, r),
+            (Value::Error(l), Value::Error(r)) => Rc::ptr_eq(l, r),
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Nil => write!(f, "nil"),
+            Value::Bool(b) => write!(f, "{}", b),
+            Value::Num(n) => write!(f, "{}", n),
+            Value::Str(s) => write!(f, "{}", s),
+            Value::Closure(c) => write!(f, "<fn {}>", c.function.name),
+            Value::Native(n) => write!(f, "<fn {}>", n.name),
+            Value::Class(c) => write!(f, "<class {}>", c.name),
+            Value::Instance(i) => write!(f, "<instance {}>", i.borrow().class.name),
+            Value::List(l) => {
+                let items: Vec<String> = l.borrow().iter().map(Value::to_string).collect();
+                write!(f, "[{}]", items.join(", "))
+            }
+            Value::Map(m) => write!(f, "{}", m.borrow()),
+            Value::Module(m) => write!(f, "<module {}>", m.name),
+            Value::Error(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl fmt::Debug for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+/// The global variables of one module. Closures and classes keep the one
+/// they were created in.
+pub type Globals = Rc<RefCell<HashMap<Rc<str>, Value>>>;
+
+/// An imported module, giving access to its globals as properties.
+#[derive(Debug)]
+pub struct Module {
+    pub name: String,
+    pub globals: Globals,
+}
+
+/// One lexical scope at runtime, addressed by the resolver's slots.
+#[derive(Debug)]
+pub struct Scope {
+    pub slots: Vec<Value>,
+    pub enclosing: Option<Env>,
+}
+
+pub type Env = Rc<RefCell<Scope>>;
+
+impl Scope {
+    pub fn new(slots: Vec<Value>, enclosing: Option<Env>) -> Env {
+        Rc::new(RefCell::new(Self { slots, enclosing }))
+    }
+}
+
+/// A compiled function body.
+#[derive(Debug)]
+pub struct Function {
+    pub name: String,
+    pub arity: usize,
+    pub chunk: Chunk,
+    pub is_initializer: bool,
+}
+
+/// A function together with the scope it was declared in.
+#[derive(Debug)]
+pub struct Closure {
+    pub function: Rc<Function>,
+    pub env: Option<Env>,
+    pub globals: Globals,
+}
+
+pub type NativeFn = Rc<dyn Fn(&[Value]) -> Result<Value, String>>;
+
+/// A function implemented in Rust. An `Err` becomes a runtime error at the
+/// call site.
+pub struct Native {
+    pub name: String,
+    pub arity: Arity,
+    pub fun: NativeFn,
+}
+
+impl fmt::Debug for Native {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Native {{ name : {} }}", self.name)
+    }
+}
+
+pub struct Class {
+    pub name: String,
+    pub methods: HashMap<String, Rc<Function>>,
+    pub superclass: Option<Rc<Class>>,
+    pub env: Option<Env>,
+    pub globals: Globals,
+}
+
+impl Class {
+    /// Walks the superclass chain and returns the method along with the class
+    /// declaring it.
+    pub fn find_method(self: &Rc<Self>, name: &str) -> Option<(Rc<Function>, Rc<Class>)> {
+        let mut curr = Some(self.clone());
+        while let Some(c) = curr {
+            if let Some(method) = c.methods.get(name) {
+                return Some((method.clone(), c));
+            }
+            curr = c.superclass.clone();
+        }
+        None
+    }
+
+    /// Binds `method` of this class to `instance` through a `this` scope.
+    pub fn bind(&self, method: Rc<Function>, instance: Value) -> Rc<Closure> {
+        let env = Scope::new(vec![instance], self.env.clone());
+        Rc::new(Closure {
+            function: method,
+            env: Some(env),
+            globals: self.globals.clone(),
+        })
+    }
+}
+
+impl fmt::Debug for Class {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Class {{ name : {} }}", self.name)
+    }
+}
+
+pub struct Instance {
+    pub class: Rc<Class>,
+    pub fields: HashMap<String, Value>,
+}
+
+impl fmt::Debug for Instance {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Instance {{ class : {} }}", self.class.name)
+    }
+}
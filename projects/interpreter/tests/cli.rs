@@ -0,0 +1,164 @@
+mod common;
+
+use common::TempDir;
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+fn interpreter() -> Command {
+    Command::new(env!("CARGO_BIN_EXE_codecrafters-interpreter"))
+}
+
+#[test]
+fn bad_options_exit_with_usage_error() {
+    for flag in ["--backend=xyz", "--max-steps=abc", "--bogus"] {
+        let out = interpreter()
+            .args(["run", "tests/lox/classes.lox", flag])
+            .output()
+            .unwrap();
+        assert_eq!(out.status.code(), Some(64), "{}", flag);
+        assert!(String::from_utf8_lossy(&out.stderr).contains("Usage:"));
+    }
+}
+
+#[test]
+fn missing_arguments_exit_with_usage_error() {
+    let out = interpreter().arg("run").output().unwrap();
+    assert_eq!(out.status.code(), Some(64));
+}
+
+/// Runs `command` on a script in `dir` holding `source`, returns its
+/// stdout.
+fn run_on(dir: &TempDir, command: &str, source: &str) -> String {
+    let path = dir.script("script.lox", source);
+    let out = interpreter().arg(command).arg(&path).output().unwrap();
+    String::from_utf8(out.stdout).unwrap()
+}
+
+#[test]
+fn numbers_print_without_exponent() {
+    let dir = TempDir::new("numbers_print_without_exponent");
+    let tokens = run_on(
+        &dir,
+        "tokenize",
+        "10000000000000000.0 0.0000001 1234567890123456789 1.50",
+    );
+    assert_eq!(
+        tokens,
+        "NUMBER 10000000000000000.0 10000000000000000.0\n\
+         NUMBER 0.0000001 0.0000001\n\
+         NUMBER 1234567890123456789 1234567890123456789.0\n\
+         NUMBER 1.50 1.5\n\
+         EOF  null\n"
+    );
+    let ast = run_on(&dir, "parse", "1234567890123456789 * 0.0000001");
+    assert_eq!(ast, "(* 1234567890123456789.0 0.0000001)\n");
+}
+
+#[test]
+fn crlf_counts_one_line() {
+    let dir = TempDir::new("crlf_counts_one_line");
+    let path = dir.script("crlf.lox", "var a = 1;\r\nvar b = 2;\r\nprint a + nil;\r\n");
+    let out = interpreter().arg("run").arg(&path).output().unwrap();
+    let stderr = String::from_utf8(out.stderr).unwrap();
+    assert!(stderr.starts_with("[line 3:7] "), "{}", stderr);
+}
+
+#[test]
+fn minus_is_its_own_token() {
+    let dir = TempDir::new("minus_is_its_own_token");
+    let tokens = run_on(&dir, "tokenize", "5-1");
+    assert_eq!(
+        tokens,
+        "NUMBER 5 5.0\nMINUS - null\nNUMBER 1 1.0\nEOF  null\n"
+    );
+    let ast = run_on(&dir, "parse", "-2 * 3 - -1");
+    assert_eq!(ast, "(- (* (- 2.0) 3.0) (- 1.0))\n");
+}
+
+#[test]
+fn tabs_are_whitespace() {
+    let dir = TempDir::new("tabs_are_whitespace");
+    let source = "if (true) {\n\tprint 1;\t// tab\n}\n";
+    assert_eq!(run_on(&dir, "run", source), "1\n");
+    let tokens = run_on(&dir, "tokenize", "\tprint\t1;");
+    assert_eq!(
+        tokens,
+        "PRINT print null\nNUMBER 1 1.0\nSEMICOLON ; null\nEOF  null\n"
+    );
+}
+
+#[test]
+fn unary_operands_continue_into_binary_operators() {
+    let dir = TempDir::new("unary_operands_continue_into_binary_operators");
+    let ast = run_on(&dir, "parse", "!true == false");
+    assert_eq!(ast, "(== (! true) false)\n");
+    let ast = run_on(&dir, "parse", "!a or -b * c");
+    assert_eq!(ast, "(or (! a) (* (- b) c))\n");
+}
+
+#[test]
+fn doubling_a_string_runs_into_the_allocation_limit() {
+    let dir = TempDir::new("doubling_a_string_runs_into_the_allocation_limit");
+    let path = dir.script(
+        "double.lox",
+        "var s = \"a\";\nwhile (true) { s = s + s; }\n",
+    );
+    for backend in ["--backend=tree", "--backend=vm"] {
+        let out = interpreter()
+            .arg("run")
+            .arg(&path)
+            .args([backend, "--max-allocations=100"])
+            .output()
+            .unwrap();
+        assert_eq!(out.status.code(), Some(70), "{}", backend);
+        let stderr = String::from_utf8(out.stderr).unwrap();
+        assert!(stderr.starts_with("[line 2:"), "{}", stderr);
+        assert!(stderr.contains("Allocation limit exceeded."), "{}", stderr);
+    }
+}
+
+/// Runs the repl with `input` on stdin, returns its exit status, stdout
+/// and stderr.
+fn repl(input: &str) -> (Option<i32>, String, String) {
+    let mut child = interpreter()
+        .arg("repl")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(input.as_bytes())
+        .unwrap();
+    let out = child.wait_with_output().unwrap();
+    let stdout = String::from_utf8(out.stdout).unwrap();
+    (
+        out.status.code(),
+        stdout,
+        String::from_utf8(out.stderr).unwrap(),
+    )
+}
+
+#[test]
+fn repl_keeps_state_and_goes_on_after_errors() {
+    let (status, stdout, stderr) = repl("var a = 1;\na + 1\nnil();\nvar = 2;\na = a + 5;\na\n");
+    assert_eq!(status, Some(0));
+    assert_eq!(stdout, "> > 2\n> > > 6\n> 6\n> \n");
+    assert_eq!(
+        stderr,
+        "[line 1:1] Can only call functions and classes.\n\
+         [line 1] in script\n\
+         [line 1] Error at '=': Expect variable name.\n"
+    );
+}
+
+#[test]
+fn repl_exits_on_end_of_input() {
+    let (status, stdout, stderr) = repl("");
+    assert_eq!(status, Some(0));
+    assert_eq!(stdout, "> \n");
+    assert!(stderr.is_empty());
+}
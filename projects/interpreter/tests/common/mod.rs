@@ -0,0 +1,29 @@
+use std::fs;
+use std::path::PathBuf;
+use std::process;
+
+/// A directory of a test's own under the temporary directory, named after
+/// the process and the test so that concurrent runs keep apart, and
+/// removed when dropped.
+pub struct TempDir(PathBuf);
+
+impl TempDir {
+    pub fn new(test: &str) -> Self {
+        let path = std::env::temp_dir().join(format!("lox-{}-{}", process::id(), test));
+        fs::create_dir_all(&path).unwrap();
+        Self(path)
+    }
+
+    /// Writes `source` to the script `name` in the directory.
+    pub fn script(&self, name: &str, source: &str) -> PathBuf {
+        let path = self.0.join(name);
+        fs::write(&path, source).unwrap();
+        path
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
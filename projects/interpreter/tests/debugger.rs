@@ -0,0 +1,234 @@
+mod common;
+
+use codecrafters_interpreter::{DebugHooks, Lox, Paused, Resume, Stop, Value};
+use common::TempDir;
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::rc::Rc;
+
+const COUNTER: &str = "\
+fun make() {
+  var count = 0;
+  fun inc() {
+    count = count + 1;
+    return count;
+  }
+  return inc;
+}
+var inc = make();
+var a = inc();
+var b = inc();
+a + b;
+";
+
+/// What the hooks saw at a stop.
+#[derive(Debug, PartialEq)]
+struct Stopped {
+    stop: Stop,
+    line: usize,
+    stack: Vec<String>,
+    locals: Vec<(String, String)>,
+    captured: Vec<(String, String)>,
+}
+
+/// Hooks that stop on `breakpoints`, resume with `resumes` in turn then
+/// continue, and record every stop.
+struct Script {
+    breakpoints: Vec<usize>,
+    resumes: VecDeque<Resume>,
+    stops: Rc<RefCell<Vec<Stopped>>>,
+}
+
+impl DebugHooks for Script {
+    fn is_breakpoint(&mut self, line: usize) -> bool {
+        self.breakpoints.contains(&line)
+    }
+
+    fn paused(&mut self, stop: Stop, state: &Paused) -> Resume {
+        self.stops.borrow_mut().push(Stopped {
+            stop,
+            line: state.line,
+            stack: state.stack().map(|f| f.function.clone()).collect(),
+            locals: state.locals(),
+            captured: state.captured(),
+        });
+        self.resumes.pop_front().unwrap_or(Resume::Continue)
+    }
+}
+
+/// Runs `source` under hooks built from `breakpoints` and `resumes`,
+/// returns its value and the stops.
+fn debug_source(source: &str, breakpoints: &[usize], resumes: &[Resume]) -> (Value, Vec<Stopped>) {
+    let stops = Rc::new(RefCell::new(Vec::new()));
+    let mut lox = Lox::new();
+    lox.set_debugger(Script {
+        breakpoints: breakpoints.to_vec(),
+        resumes: resumes.iter().copied().collect(),
+        stops: stops.clone(),
+    });
+    let value = lox.eval(source).unwrap();
+    (value, stops.take())
+}
+
+/// Runs `COUNTER` under hooks built from `breakpoints` and `resumes`,
+/// returns the stops.
+fn debug(breakpoints: &[usize], resumes: &[Resume]) -> Vec<Stopped> {
+    let (value, stops) = debug_source(COUNTER, breakpoints, resumes);
+    assert_eq!(value, Value::Number(3.0));
+    stops
+}
+
+fn var(name: &str, val: &str) -> (String, String) {
+    (name.to_string(), val.to_string())
+}
+
+/// The stop and line of each of `stops`.
+fn lines(stops: &[Stopped]) -> Vec<(Stop, usize)> {
+    stops.iter().map(|s| (s.stop, s.line)).collect()
+}
+
+#[test]
+fn the_first_statement_stops_then_the_breakpoints() {
+    let stops = debug(&[4], &[]);
+    assert_eq!(
+        lines(&stops),
+        [
+            (Stop::Step, 1),
+            (Stop::Breakpoint, 4),
+            (Stop::Breakpoint, 4)
+        ]
+    );
+    assert_eq!(stops[1].stack, ["inc", "script"]);
+    assert!(stops[1].locals.is_empty());
+    assert_eq!(stops[1].captured[0], var("count", "0"));
+    assert_eq!(stops[2].captured[0], var("count", "1"));
+}
+
+#[test]
+fn steps_go_into_over_and_out_of_calls() {
+    let resumes = [
+        Resume::StepOver,
+        Resume::StepInto,
+        Resume::StepOver,
+        Resume::StepOut,
+        Resume::StepInto,
+        Resume::StepOver,
+        Resume::StepOver,
+    ];
+    let stops = debug(&[], &resumes);
+    assert_eq!(
+        lines(&stops),
+        [
+            (Stop::Step, 1),
+            (Stop::Step, 9),
+            (Stop::Step, 2),
+            (Stop::Step, 3),
+            (Stop::Step, 10),
+            (Stop::Step, 4),
+            (Stop::Step, 5),
+            (Stop::Step, 11),
+        ]
+    );
+    assert_eq!(stops[2].stack, ["make", "script"]);
+    assert_eq!(stops[3].locals, [var("count", "0")]);
+    assert_eq!(stops[4].stack, ["script"]);
+    assert_eq!(stops[6].captured[0], var("count", "1"));
+}
+
+#[test]
+fn a_line_stops_once_however_many_statements_it_holds() {
+    let source = "\
+var a = 1;
+if (a > 0) print \"pos\";
+var i = 0;
+while (i < 3) i = i + 1;
+while (i > 1)
+  i = i - 1;
+i;
+";
+    let (value, stops) = debug_source(source, &[], &[Resume::StepInto; 20]);
+    assert_eq!(value, Value::Number(1.0));
+    let stopped_on: Vec<usize> = stops.iter().map(|s| s.line).collect();
+    // The body on its own line is reached again on each pass.
+    assert_eq!(stopped_on, [1, 2, 3, 4, 5, 6, 6, 7]);
+
+    let (_, stops) = debug_source(source, &[2, 4], &[]);
+    assert_eq!(
+        lines(&stops),
+        [
+            (Stop::Step, 1),
+            (Stop::Breakpoint, 2),
+            (Stop::Breakpoint, 4)
+        ]
+    );
+}
+
+/// Runs the `debug` command on `COUNTER` with `commands` on stdin, in a
+/// temporary directory of `test`'s own, returns its stdout.
+fn debug_command(test: &str, commands: &str) -> String {
+    let dir = TempDir::new(&format!("debugger-{}", test));
+    let path = dir.script("counter.lox", COUNTER);
+    let mut child = Command::new(env!("CARGO_BIN_EXE_codecrafters-interpreter"))
+        .arg("debug")
+        .arg(&path)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let mut stdin = child.stdin.take().unwrap();
+    stdin.write_all(commands.as_bytes()).unwrap();
+    drop(stdin);
+    let out = child.wait_with_output().unwrap();
+    assert!(out.status.success());
+    String::from_utf8(out.stdout).unwrap()
+}
+
+#[test]
+fn the_command_reads_commands_at_each_stop() {
+    let out = debug_command(
+        "the_command_reads_commands_at_each_stop",
+        "break 5\nc\nbt\np count\nlocals\nquit\n",
+    );
+    let expected = [
+        "script at line 1",
+        "   1 | fun make() {",
+        "(debug) Breakpoint on line 5.",
+        "(debug) Breakpoint, inc at line 5",
+        "   5 |     return count;",
+        "(debug) #0 inc at line 5",
+        "#1 script at line 10",
+        "(debug) 1",
+        "(debug) (debug) ",
+    ];
+    assert_eq!(out, expected.join("\n"));
+}
+
+#[test]
+fn the_program_runs_to_the_end_once_stdin_is_closed() {
+    let out = debug_command(
+        "the_program_runs_to_the_end_once_stdin_is_closed",
+        "break 4\n",
+    );
+    assert_eq!(
+        out,
+        "script at line 1\n   1 | fun make() {\n(debug) Breakpoint on line 4.\n(debug) \n"
+    );
+}
+
+#[test]
+fn globals_leave_out_the_natives_unless_asked() {
+    let out = debug_command(
+        "globals_leave_out_the_natives_unless_asked",
+        "break 10\nc\nglobals\nglobals all\nquit\n",
+    );
+    let (_, listed) = out.split_once("  10 | var a = inc();\n").unwrap();
+    let mut lists = listed.split("(debug) ").skip(1);
+    assert_eq!(lists.next(), Some("inc = <fn inc>\nmake = <fn make>\n"));
+    let all: Vec<&str> = lists.next().unwrap().lines().collect();
+    assert_eq!(all[0], "clock = <fn clock>");
+    for var in ["inc = <fn inc>", "len = <fn len>", "make = <fn make>"] {
+        assert!(all.contains(&var), "{}", var);
+    }
+}
@@ -0,0 +1,110 @@
+use codecrafters_interpreter::{Lox, LoxError, Value};
+
+#[test]
+fn eval_returns_the_last_expression() {
+    let mut lox = Lox::new();
+    assert_eq!(lox.eval("1 + 2").unwrap(), Value::Number(3.0));
+    assert_eq!(lox.eval("var a = 1; a + 1;").unwrap(), Value::Number(2.0));
+    assert_eq!(lox.eval("var b = 1;").unwrap(), Value::Nil);
+    assert_eq!(lox.eval("").unwrap(), Value::Nil);
+}
+
+#[test]
+fn eval_returns_typed_values() {
+    let mut lox = Lox::new();
+    assert_eq!(lox.eval("nil").unwrap(), Value::Nil);
+    assert_eq!(lox.eval("1 < 2").unwrap(), Value::Bool(true));
+    assert_eq!(
+        lox.eval("\"a\" + \"b\"").unwrap(),
+        Value::String("ab".into())
+    );
+    let list = lox.eval("[1, 2]").unwrap();
+    assert!(matches!(list, Value::Object(_)));
+    assert_eq!(list.to_string(), "[1, 2]");
+}
+
+#[test]
+fn globals_outlive_eval() {
+    let mut lox = Lox::new();
+    lox.eval("var greeting = \"hi\"; fun twice(n) { return n * 2; }")
+        .unwrap();
+    assert_eq!(lox.eval("twice(21)").unwrap(), Value::Number(42.0));
+    assert_eq!(lox.get_global("greeting"), Some(Value::String("hi".into())));
+    assert_eq!(lox.get_global("missing"), None);
+}
+
+#[test]
+fn set_global_is_seen_by_lox() {
+    let mut lox = Lox::new();
+    lox.set_global("base", 40.0);
+    lox.set_global("name", "lox");
+    lox.set_global("flag", true);
+    assert_eq!(lox.eval("base + 2").unwrap(), Value::Number(42.0));
+    assert_eq!(lox.eval("name").unwrap(), Value::String("lox".into()));
+    lox.set_global("base", 1.0);
+    assert_eq!(lox.eval("flag and base").unwrap(), Value::Number(1.0));
+}
+
+#[test]
+fn objects_pass_back_to_lox() {
+    let mut lox = Lox::new();
+    let list = lox.eval("[1, 2, 3]").unwrap();
+    lox.set_global("items", list);
+    lox.eval("items[0] = 10;").unwrap();
+    assert_eq!(lox.eval("items").unwrap().to_string(), "[10, 2, 3]");
+}
+
+#[test]
+fn call_runs_a_function_or_class() {
+    let mut lox = Lox::new();
+    lox.eval("fun add(a, b) { return a + b; } class P { init(x) { this.x = x; } }")
+        .unwrap();
+    let sum = lox.call("add", &[1.0.into(), 2.0.into()]).unwrap();
+    assert_eq!(sum, Value::Number(3.0));
+    let p = lox.call("P", &[5.0.into()]).unwrap();
+    assert_eq!(p.to_string(), "<instance P>");
+}
+
+#[test]
+fn call_reports_errors() {
+    let mut lox = Lox::new();
+    lox.eval("var a = 1;\nfun one(a) { return a; }\nclass P {}")
+        .unwrap();
+    let Err(LoxError::Runtime(err)) = lox.call("one", &[]) else {
+        panic!("expected a runtime error");
+    };
+    assert_eq!(err.message, "Expected 1 arguments but got 0.");
+    assert_eq!(err.line, 2);
+    let Err(LoxError::Runtime(err)) = lox.call("P", &[1.0.into()]) else {
+        panic!("expected a runtime error");
+    };
+    assert_eq!(err.message, "Expected 0 arguments but got 1.");
+    assert_eq!(err.line, 3);
+    let Err(LoxError::Runtime(err)) = lox.call("missing", &[]) else {
+        panic!("expected a runtime error");
+    };
+    assert_eq!(err.message, "Undefined variable 'missing'.");
+}
+
+#[test]
+fn eval_reports_each_kind_of_error() {
+    let mut lox = Lox::new();
+    assert!(matches!(lox.eval("print 1; @"), Err(LoxError::Lex(_))));
+    let Err(LoxError::Syntax(diagnostics)) = lox.eval("var = 1;") else {
+        panic!("expected a syntax error");
+    };
+    assert_eq!(diagnostics[0].message, "Expect variable name.");
+    let Err(LoxError::Runtime(err)) = lox.eval("fun f() {\n  nil();\n}\nf();") else {
+        panic!("expected a runtime error");
+    };
+    assert_eq!(err.message, "Can only call functions and classes.");
+    assert_eq!(err.line, 2);
+    assert_eq!(err.trace.len(), 2);
+}
+
+#[test]
+fn nothing_runs_when_the_source_does_not_compile() {
+    let mut lox = Lox::new();
+    assert!(lox.eval("var ran = true; var = 1;").is_err());
+    assert_eq!(lox.get_global("ran"), None);
+}
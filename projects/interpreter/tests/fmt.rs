@@ -0,0 +1,99 @@
+mod common;
+
+use common::TempDir;
+use std::fs;
+use std::path::Path;
+use std::process::{Command, Output};
+
+fn fmt(args: &[&str], path: &Path) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_codecrafters-interpreter"))
+        .arg("fmt")
+        .arg(path)
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+fn formatted(path: &Path) -> String {
+    let out = fmt(&[], path);
+    assert!(
+        out.status.success(),
+        "{}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+    String::from_utf8(out.stdout).unwrap()
+}
+
+#[test]
+fn comments_are_kept() {
+    let dir = TempDir::new("fmt-comments_are_kept");
+    let path = dir.script(
+        "comments.lox",
+        "// Doubles.\nfun twice(n){\n\t// Inside.\n\treturn n*2; // Trailing.\n}\n\n\n// Last.\nprint -twice(1);\n",
+    );
+    assert_eq!(
+        formatted(&path),
+        "// Doubles.\nfun twice(n) {\n  // Inside.\n  return n * 2; // Trailing.\n}\n\n// Last.\nprint -twice(1);\n"
+    );
+}
+
+#[test]
+fn formatting_is_idempotent() {
+    let dir = TempDir::new("fmt-formatting_is_idempotent");
+    for entry in fs::read_dir("tests/lox").unwrap() {
+        let path = entry.unwrap().path();
+        if path.extension() != Some("lox".as_ref()) || !fmt(&[], &path).status.success() {
+            continue;
+        }
+        let once = formatted(&path);
+        let again = dir.script("idempotent.lox", &once);
+        assert_eq!(formatted(&again), once, "{}", path.display());
+    }
+}
+
+#[test]
+fn check_lists_the_files_to_reformat() {
+    let dir = TempDir::new("fmt-check_lists_the_files_to_reformat");
+    let messy = dir.script("messy.lox", "var a=1;\n");
+    let tidy = dir.script("tidy.lox", "var a = 1;\n");
+    let out = fmt(&["--check"], &messy);
+    assert_eq!(out.status.code(), Some(1));
+    assert_eq!(
+        String::from_utf8(out.stdout).unwrap(),
+        format!("Would reformat: {}\n", messy.display())
+    );
+    assert_eq!(fs::read_to_string(&messy).unwrap(), "var a=1;\n");
+    let out = fmt(&["--check"], &tidy);
+    assert_eq!(out.status.code(), Some(0));
+    assert!(out.stdout.is_empty());
+}
+
+#[test]
+fn write_rewrites_the_files() {
+    let dir = TempDir::new("fmt-write_rewrites_the_files");
+    let path = dir.script("write.lox", "if(true){print -1;}else print - -2;\n");
+    let out = fmt(&["--write"], &path);
+    assert!(out.status.success());
+    assert!(out.stdout.is_empty());
+    assert_eq!(
+        fs::read_to_string(&path).unwrap(),
+        "if (true) {\n  print -1;\n} else print --2;\n"
+    );
+}
+
+#[test]
+fn invalid_scripts_are_left_alone() {
+    let dir = TempDir::new("fmt-invalid_scripts_are_left_alone");
+    for (name, source) in [
+        ("exponent", "print 1e3;\n"),
+        ("slashes", "fun f(a,b){ /* c */ return a+b; }\n"),
+        ("open-end", "print 1\n"),
+        ("lexical", "print @;\n"),
+    ] {
+        let path = dir.script(&format!("{}.lox", name), source);
+        let out = fmt(&["--write"], &path);
+        assert_eq!(out.status.code(), Some(65), "{}", source);
+        assert_eq!(fs::read_to_string(&path).unwrap(), source);
+        assert!(fmt(&[], &path).stdout.is_empty(), "{}", source);
+    }
+}
@@ -0,0 +1,22 @@
+use std::process::Command;
+
+/// Runs the scripts under `tests/lox` with the `test` command.
+fn run_suite(backend: &str) {
+    let out = Command::new(env!("CARGO_BIN_EXE_codecrafters-interpreter"))
+        .args(["test", "tests/lox", backend])
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    assert!(out.status.success(), "{}", stdout);
+    assert!(stdout.contains(" passed, 0 failed"), "{}", stdout);
+}
+
+#[test]
+fn scripts_pass_on_the_tree_backend() {
+    run_suite("--backend=tree");
+}
+
+#[test]
+fn scripts_pass_on_the_vm_backend() {
+    run_suite("--backend=vm");
+}
@@ -0,0 +1,128 @@
+mod common;
+
+use codecrafters_interpreter::json::Json;
+use common::TempDir;
+use std::process::Command;
+
+/// Runs `command --format=json` on a script holding `source`, returns the
+/// document it prints and its exit status.
+fn json_of(command: &str, name: &str, source: &str) -> (Json, i32) {
+    let dir = TempDir::new(&format!("json-{}", name));
+    let path = dir.script("script.lox", source);
+    let out = Command::new(env!("CARGO_BIN_EXE_codecrafters-interpreter"))
+        .arg(command)
+        .arg(&path)
+        .arg("--format=json")
+        .output()
+        .unwrap();
+    let doc = Json::parse(&String::from_utf8(out.stdout).unwrap()).unwrap();
+    (doc, out.status.code().unwrap())
+}
+
+fn span(line: usize, column: usize, start: usize, end: usize) -> Json {
+    Json::object([
+        ("line", line.into()),
+        ("column", column.into()),
+        ("start", start.into()),
+        ("end", end.into()),
+    ])
+}
+
+fn items<'a>(doc: &'a Json, name: &str) -> &'a [Json] {
+    doc.get(name).and_then(Json::as_array).unwrap()
+}
+
+#[test]
+fn tokens_have_their_spans() {
+    let (doc, status) = json_of("tokenize", "tokens", "var a =\n\t-1.50;");
+    assert_eq!(status, 0);
+    assert_eq!(doc.get("version"), Some(&Json::Num(1.0)));
+    assert!(items(&doc, "errors").is_empty());
+    let tokens = items(&doc, "tokens");
+    let types: Vec<&str> = tokens
+        .iter()
+        .map(|t| t.get("type").and_then(Json::as_str).unwrap())
+        .collect();
+    assert_eq!(
+        types,
+        [
+            "VAR",
+            "IDENTIFIER",
+            "EQUAL",
+            "MINUS",
+            "NUMBER",
+            "SEMICOLON",
+            "EOF"
+        ]
+    );
+    let number = &tokens[4];
+    assert_eq!(number.get("lexeme").and_then(Json::as_str), Some("1.50"));
+    assert_eq!(number.get("literal"), Some(&Json::Num(1.5)));
+    assert_eq!(number.get("span"), Some(&span(2, 3, 10, 14)));
+}
+
+#[test]
+fn lexical_errors_have_a_message_and_a_span() {
+    let source = "print 1;\nprint \"a\\q\" @;\n1.2.3;";
+    let (doc, status) = json_of("tokenize", "lex-errors", source);
+    assert_eq!(status, 65);
+    let expected = [
+        ("Invalid escape sequence: \\q", span(2, 9, 17, 19)),
+        ("Unexpected character: @", span(2, 13, 21, 22)),
+        ("Invalid number: 1.2.3", span(3, 1, 24, 29)),
+    ];
+    let errors = items(&doc, "errors");
+    assert_eq!(errors.len(), expected.len());
+    for (err, (message, at)) in errors.iter().zip(&expected) {
+        assert_eq!(err.get("message").and_then(Json::as_str), Some(*message));
+        assert_eq!(err.get("span"), Some(at));
+    }
+
+    let (doc, status) = json_of("parse", "lex-errors", source);
+    assert_eq!(status, 65);
+    assert!(items(&doc, "statements").is_empty());
+    assert_eq!(items(&doc, "errors").len(), expected.len());
+}
+
+#[test]
+fn unterminated_strings_are_reported_at_the_end() {
+    let (doc, status) = json_of("tokenize", "unterminated", "print \"abc\n");
+    assert_eq!(status, 65);
+    let errors = items(&doc, "errors");
+    assert_eq!(errors[0].get("span"), Some(&span(2, 1, 11, 11)));
+}
+
+#[test]
+fn statements_parsed_despite_syntax_errors_are_kept() {
+    let (doc, status) = json_of("parse", "syntax", "print -1;\nvar = 2;\nprint 3;");
+    assert_eq!(status, 65);
+    let statements = items(&doc, "statements");
+    assert_eq!(statements.len(), 2);
+    let print = &statements[0];
+    assert_eq!(print.get("type").and_then(Json::as_str), Some("Print"));
+    assert_eq!(print.get("span"), Some(&span(1, 1, 0, 8)));
+    let negate = print.get("expression").unwrap();
+    assert_eq!(negate.get("type").and_then(Json::as_str), Some("Negate"));
+    let operand = negate.get("operand").unwrap();
+    assert_eq!(operand.get("value"), Some(&Json::Num(1.0)));
+    let errors = items(&doc, "errors");
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].get("span"), Some(&span(2, 5, 14, 15)));
+}
+
+#[test]
+fn bad_escapes_are_reported_whole() {
+    let source = "print \"\\u{zz}\";\nprint \"a\\u{12 b\";";
+    let (doc, status) = json_of("tokenize", "escapes", source);
+    assert_eq!(status, 65);
+    let expected = [
+        ("Invalid escape sequence: \\u{zz}", span(1, 8, 7, 13)),
+        ("Invalid escape sequence: \\u{12", span(2, 9, 24, 29)),
+    ];
+    let errors = items(&doc, "errors");
+    assert_eq!(errors.len(), expected.len());
+    for (err, (message, at)) in errors.iter().zip(&expected) {
+        assert_eq!(err.get("message").and_then(Json::as_str), Some(*message));
+        assert_eq!(err.get("span"), Some(at));
+    }
+}
@@ -0,0 +1,141 @@
+use codecrafters_interpreter::limits::DEFAULT_CALL_DEPTH;
+use codecrafters_interpreter::{Limit, Limits, Lox, LoxError, Value};
+use std::thread;
+use std::time::Duration;
+
+fn limit(result: Result<Value, LoxError>) -> Limit {
+    match result {
+        Err(LoxError::Limit(err)) => err.limit.unwrap(),
+        other => panic!("expected a limit error, got {:?}", other),
+    }
+}
+
+/// Runs `f` on a thread with the stack of a main thread, which the default
+/// call depth is sized for.
+fn on_main_stack(f: impl FnOnce() + Send + 'static) {
+    thread::Builder::new()
+        .stack_size(8 << 20)
+        .spawn(f)
+        .unwrap()
+        .join()
+        .unwrap();
+}
+
+#[test]
+fn steps_count_from_zero_for_every_eval() {
+    let mut lox = Lox::new();
+    lox.set_limits(Limits {
+        steps: Some(1000),
+        ..Limits::default()
+    });
+    assert_eq!(limit(lox.eval("while (true) {}")), Limit::Steps);
+    for _ in 0..10 {
+        assert_eq!(lox.eval("1 + 2").unwrap(), Value::Number(3.0));
+    }
+    lox.eval("fun spin() { while (true) {} }").unwrap();
+    assert_eq!(limit(lox.call("spin", &[])), Limit::Steps);
+    assert_eq!(lox.eval("2 * 2").unwrap(), Value::Number(4.0));
+}
+
+#[test]
+fn allocations_count_from_zero_for_every_eval() {
+    let mut lox = Lox::new();
+    lox.set_limits(Limits {
+        allocations: Some(5),
+        ..Limits::default()
+    });
+    assert_eq!(
+        limit(lox.eval("var l; while (true) l = [l];")),
+        Limit::Allocations
+    );
+    for _ in 0..10 {
+        lox.eval("[1]; [2];").unwrap();
+    }
+}
+
+#[test]
+fn allocations_count_the_size_of_strings_and_literals() {
+    let mut lox = Lox::new();
+    lox.set_limits(Limits {
+        allocations: Some(100),
+        ..Limits::default()
+    });
+    assert_eq!(
+        limit(lox.eval("var s = \"a\"; while (true) s = s + s;")),
+        Limit::Allocations
+    );
+    assert_eq!(
+        lox.eval("var s = \"a\"; for (var i = 0; i < 5; i = i + 1) s = s + s; len(s);")
+            .unwrap(),
+        Value::Number(32.0)
+    );
+    let items = vec!["1"; 101].join(", ");
+    assert_eq!(
+        limit(lox.eval(&format!("[{}];", items))),
+        Limit::Allocations
+    );
+    lox.eval(&format!("[{}];", &items[3..])).unwrap();
+}
+
+#[test]
+fn the_clock_starts_with_each_eval() {
+    let mut lox = Lox::new();
+    lox.set_limits(Limits {
+        time: Some(Duration::from_millis(50)),
+        ..Limits::default()
+    });
+    thread::sleep(Duration::from_millis(100));
+    assert_eq!(lox.eval("1").unwrap(), Value::Number(1.0));
+    assert_eq!(limit(lox.eval("while (true) {}")), Limit::Time);
+}
+
+#[test]
+fn limits_are_not_caught() {
+    let mut lox = Lox::new();
+    lox.set_limits(Limits {
+        steps: Some(100),
+        ..Limits::default()
+    });
+    let source = "var caught = false; try { while (true) {} } catch (e) { caught = true; }";
+    assert_eq!(limit(lox.eval(source)), Limit::Steps);
+    assert_eq!(lox.get_global("caught"), Some(Value::Bool(false)));
+}
+
+#[test]
+fn default_call_depth_fits_a_main_thread() {
+    on_main_stack(|| {
+        let mut lox = Lox::new();
+        lox.eval(
+            "fun down(n) {
+               for (var i = 0; i < 1; i = i + 1) {
+                 try {
+                   if (n > 0) return 1 + (2 * down(n - 1)) / 2;
+                 } catch (e) {}
+               }
+               return 0;
+             }",
+        )
+        .unwrap();
+        let n = (DEFAULT_CALL_DEPTH - 1) as f64;
+        assert_eq!(lox.call("down", &[n.into()]).unwrap(), Value::Number(n));
+        assert_eq!(limit(lox.eval("down(100000)")), Limit::CallDepth);
+    });
+}
+
+#[test]
+fn overflow_traces_are_shortened() {
+    let mut lox = Lox::new();
+    lox.set_limits(Limits {
+        call_depth: Some(50),
+        ..Limits::default()
+    });
+    let Err(LoxError::Limit(err)) = lox.eval("fun f() { f(); }\nf();") else {
+        panic!("expected a stack overflow");
+    };
+    assert_eq!(err.trace.len(), 51);
+    let lines: Vec<String> = err.to_string().lines().map(str::to_string).collect();
+    assert_eq!(lines.len(), 22);
+    assert_eq!(lines[0], "[line 1:11] Stack overflow.");
+    assert_eq!(lines[11], "... 31 more calls");
+    assert_eq!(lines[21], "[line 2] in script");
+}
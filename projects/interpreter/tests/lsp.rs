@@ -0,0 +1,204 @@
+use codecrafters_interpreter::json::Json;
+use codecrafters_interpreter::lsp;
+use std::io::ErrorKind;
+
+const URI: &str = "file:///main.lox";
+
+/// Defines `add` with a tab-indented body, then uses it.
+const MAIN: &str = "fun add(a, b) {\n\treturn a + b;\n}\nvar total = add(1, 2);\nprint total;\n";
+
+fn request(id: usize, method: &str, params: Json) -> Json {
+    Json::object([
+        ("jsonrpc", Json::str("2.0")),
+        ("id", id.into()),
+        ("method", Json::str(method)),
+        ("params", params),
+    ])
+}
+
+fn notification(method: &str, params: Json) -> Json {
+    Json::object([
+        ("jsonrpc", Json::str("2.0")),
+        ("method", Json::str(method)),
+        ("params", params),
+    ])
+}
+
+fn open(uri: &str, text: &str) -> Json {
+    let doc = Json::object([
+        ("uri", Json::str(uri)),
+        ("languageId", Json::str("lox")),
+        ("version", 1.into()),
+        ("text", Json::str(text)),
+    ]);
+    notification(
+        "textDocument/didOpen",
+        Json::object([("textDocument", doc)]),
+    )
+}
+
+fn position(line: usize, character: usize) -> Json {
+    Json::object([("line", line.into()), ("character", character.into())])
+}
+
+/// `TextDocumentPositionParams` for the main document.
+fn at(line: usize, character: usize) -> Json {
+    Json::object([
+        ("textDocument", Json::object([("uri", Json::str(URI))])),
+        ("position", position(line, character)),
+    ])
+}
+
+fn range(start: (usize, usize), end: (usize, usize)) -> Json {
+    Json::object([
+        ("start", position(start.0, start.1)),
+        ("end", position(end.0, end.1)),
+    ])
+}
+
+/// Sends `messages` to a server, returns its replies and whether it was
+/// shut down before `exit`.
+fn session(messages: &[Json]) -> (Vec<Json>, bool) {
+    let mut input = String::new();
+    for message in messages {
+        let content = message.to_string();
+        input.push_str(&format!(
+            "Content-Length: {}\r\n\r\n{}",
+            content.len(),
+            content
+        ));
+    }
+    let mut output = Vec::new();
+    let shut_down = lsp::serve(input.as_bytes(), &mut output).unwrap();
+    let mut output = String::from_utf8(output).unwrap();
+    let mut replies = Vec::new();
+    while let Some(rest) = output.strip_prefix("Content-Length: ") {
+        let (length, rest) = rest.split_once("\r\n\r\n").unwrap();
+        let length: usize = length.parse().unwrap();
+        replies.push(Json::parse(&rest[..length]).unwrap());
+        output = rest[length..].to_string();
+    }
+    assert!(output.is_empty());
+    (replies, shut_down)
+}
+
+fn result(reply: &Json, id: usize) -> &Json {
+    assert_eq!(reply.get("id"), Some(&id.into()));
+    reply.get("result").unwrap()
+}
+
+fn diagnostics(reply: &Json) -> &[Json] {
+    assert_eq!(
+        reply.get("method").and_then(Json::as_str),
+        Some("textDocument/publishDiagnostics")
+    );
+    let params = reply.get("params").unwrap();
+    params.get("diagnostics").and_then(Json::as_array).unwrap()
+}
+
+fn labels(items: &Json) -> Vec<&str> {
+    let items = items.as_array().unwrap();
+    items
+        .iter()
+        .map(|item| item.get("label").and_then(Json::as_str).unwrap())
+        .collect()
+}
+
+#[test]
+fn a_session_answers_every_request() {
+    let references = Json::object([
+        ("textDocument", Json::object([("uri", Json::str(URI))])),
+        ("position", position(3, 5)),
+        (
+            "context",
+            Json::object([("includeDeclaration", Json::Bool(true))]),
+        ),
+    ]);
+    let (replies, shut_down) = session(&[
+        request(1, "initialize", Json::object([])),
+        notification("initialized", Json::object([])),
+        open(URI, MAIN),
+        request(2, "textDocument/definition", at(3, 13)),
+        request(3, "textDocument/references", references),
+        request(4, "textDocument/hover", at(1, 8)),
+        request(5, "textDocument/completion", at(4, 0)),
+        request(6, "shutdown", Json::Null),
+        notification("exit", Json::Null),
+    ]);
+    assert!(shut_down);
+    assert_eq!(replies.len(), 7);
+
+    let capabilities = result(&replies[0], 1).get("capabilities").unwrap();
+    assert_eq!(
+        capabilities.get("definitionProvider"),
+        Some(&Json::Bool(true))
+    );
+    assert!(diagnostics(&replies[1]).is_empty());
+
+    let definition = result(&replies[2], 2);
+    assert_eq!(definition.get("uri").and_then(Json::as_str), Some(URI));
+    assert_eq!(definition.get("range"), Some(&range((0, 4), (0, 7))));
+
+    let locations = result(&replies[3], 3).as_array().unwrap();
+    let ranges: Vec<&Json> = locations.iter().map(|l| l.get("range").unwrap()).collect();
+    assert_eq!(ranges, [&range((3, 4), (3, 9)), &range((4, 6), (4, 11))]);
+
+    let hover = result(&replies[4], 4);
+    let contents = hover.get("contents").and_then(|c| c.get("value"));
+    assert!(contents
+        .and_then(Json::as_str)
+        .unwrap()
+        .contains("parameter a"));
+    assert_eq!(hover.get("range"), Some(&range((1, 8), (1, 9))));
+
+    let completion = labels(result(&replies[5], 5));
+    for label in ["add", "total", "print", "while"] {
+        assert!(completion.contains(&label), "{}", label);
+    }
+    assert!(!completion.contains(&"a"));
+
+    assert_eq!(result(&replies[6], 6), &Json::Null);
+}
+
+#[test]
+fn lexical_errors_are_reported_where_they_are() {
+    let text = "var a = 1..2;\n\tprint 1.2.3 @;\n";
+    let (replies, _) = session(&[open(URI, text)]);
+    let found: Vec<(&str, &Json)> = diagnostics(&replies[0])
+        .iter()
+        .map(|d| {
+            let message = d.get("message").and_then(Json::as_str).unwrap();
+            (message, d.get("range").unwrap())
+        })
+        .collect();
+    assert_eq!(
+        found,
+        [
+            ("Invalid number: 1..2", &range((0, 8), (0, 12))),
+            ("Invalid number: 1.2.3", &range((1, 7), (1, 12))),
+            ("Unexpected character: @", &range((1, 13), (1, 14))),
+        ]
+    );
+}
+
+#[test]
+fn syntax_errors_in_indented_code_are_reported_where_they_are() {
+    let (replies, _) = session(&[open(URI, "var total = 1;\n\tprint total\n")]);
+    let found = diagnostics(&replies[0]);
+    assert_eq!(found.len(), 1);
+    assert_eq!(
+        found[0].get("message").and_then(Json::as_str),
+        Some("Expect ';' after value.")
+    );
+    assert_eq!(found[0].get("range"), Some(&range((2, 0), (2, 0))));
+}
+
+#[test]
+fn oversized_messages_are_refused() {
+    let input = "Content-Length: 18446744073709551615\r\n\r\n{}";
+    let mut output = Vec::new();
+    let err = lsp::serve(input.as_bytes(), &mut output).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+    assert!(err.to_string().contains("over the limit"), "{}", err);
+    assert!(output.is_empty());
+}
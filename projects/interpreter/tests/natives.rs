@@ -0,0 +1,69 @@
+use codecrafters_interpreter::{Arity, Lox, LoxError, Value};
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+
+fn runtime_error(result: Result<Value, LoxError>) -> (String, usize) {
+    match result {
+        Err(LoxError::Runtime(err)) => (err.message, err.line),
+        other => panic!("expected a runtime error, got {:?}", other),
+    }
+}
+
+#[test]
+fn fixed_arity_is_checked_before_the_call() {
+    let calls = Rc::new(Cell::new(0));
+    let mut lox = Lox::new();
+    let counter = calls.clone();
+    lox.define_native("pair", Arity::Fixed(2), move |args| {
+        counter.set(counter.get() + 1);
+        Ok(Value::Number(args.len() as f64))
+    });
+    assert_eq!(lox.eval("pair(1, 2)").unwrap(), Value::Number(2.0));
+    assert_eq!(
+        runtime_error(lox.eval("pair(1);")),
+        ("Expected 2 arguments but got 1.".to_string(), 1)
+    );
+    assert_eq!(calls.get(), 1);
+}
+
+#[test]
+fn variadic_natives_take_any_number_of_arguments() {
+    let mut lox = Lox::new();
+    lox.define_native("count", Arity::Variadic, |args| {
+        Ok(Value::Number(args.len() as f64))
+    });
+    assert_eq!(lox.eval("count()").unwrap(), Value::Number(0.0));
+    assert_eq!(
+        lox.eval("count(1, \"a\", nil)").unwrap(),
+        Value::Number(3.0)
+    );
+}
+
+#[test]
+fn errors_become_runtime_errors_at_the_call() {
+    let mut lox = Lox::new();
+    lox.define_native("fail", Arity::Fixed(1), |args| match &args[0] {
+        Value::String(s) => Err(s.clone()),
+        _ => Ok(Value::Nil),
+    });
+    assert_eq!(
+        runtime_error(lox.eval("var a = 1;\nfail(\"no luck\");")),
+        ("no luck".to_string(), 2)
+    );
+    let caught = lox.eval("var m; try { fail(\"oops\"); } catch (e) { m = e.message; } m");
+    assert_eq!(caught.unwrap(), Value::String("oops".into()));
+}
+
+#[test]
+fn natives_capture_host_state() {
+    let log = Rc::new(RefCell::new(Vec::new()));
+    let mut lox = Lox::new();
+    let sink = log.clone();
+    lox.define_native("emit", Arity::Fixed(1), move |args| {
+        sink.borrow_mut().push(args[0].to_string());
+        Ok(Value::Nil)
+    });
+    lox.eval("for (var i = 0; i < 3; i = i + 1) emit(i * 2);")
+        .unwrap();
+    assert_eq!(*log.borrow(), ["0", "2", "4"]);
+}